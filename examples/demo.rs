@@ -254,7 +254,7 @@ impl eframe::App for CodeEditorDemo {
                 .show(ui, |ui| {
                     for token in Token::default().tokens(&self.syntax, &self.code) {
                         ui.horizontal(|h| {
-                            let fmt = editor.format_token(token.ty());
+                            let fmt = editor.format_token(token.ty(), usize::MAX);
                             h.label(egui::text::LayoutJob::single_section(
                                 format!("{:?}", token.ty()),
                                 fmt,
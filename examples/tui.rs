@@ -4,6 +4,8 @@ use egui_code_editor::{Syntax, Token, TokenType};
 fn color(token: TokenType) -> Color {
     match token {
         TokenType::Comment(_) => Color::Grey37,
+        TokenType::DocComment(_) => Color::Grey50,
+        TokenType::CommentTag => Color::Yellow1,
         TokenType::Function => Color::Yellow3b,
         TokenType::Keyword => Color::IndianRed1c,
         TokenType::Literal => Color::NavajoWhite1,
@@ -11,10 +13,12 @@ fn color(token: TokenType) -> Color {
         TokenType::Punctuation(_) => Color::Orange3,
         TokenType::Special => Color::Cyan,
         TokenType::Str(_) => Color::Green,
+        TokenType::StringDelim(_) => Color::DarkGreen,
         TokenType::Type => Color::GreenYellow,
         TokenType::Whitespace(_) => Color::White,
         TokenType::Hyperlink => Color::Blue3b,
         TokenType::Unknown => Color::Pink1,
+        _ => Color::White,
     }
 }
 
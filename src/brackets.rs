@@ -0,0 +1,97 @@
+use super::highlighting::Token;
+use super::syntax::{Syntax, TokenType};
+
+/// Opening and closing brackets recognized by the matcher.
+pub const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// Bracket highlighting options.
+pub struct BracketMatching {
+    /// Highlight the bracket next to the cursor and its pair.
+    pub pair: bool,
+    /// Mark brackets without a pair with the theme error color.
+    pub unmatched: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+/// Brackets of the code, only punctuation counts, brackets in strings, chars and comments
+/// are skipped.
+/// Offsets are in bytes.
+pub struct BracketPairs {
+    /// Opening and closing brackets of each pair.
+    pub pairs: Vec<(usize, usize)>,
+    /// Brackets without a pair, in order of appearance.
+    pub unmatched: Vec<usize>,
+}
+
+impl BracketPairs {
+    pub fn new(syntax: &Syntax, text: &str) -> Self {
+        let mut brackets = BracketPairs::default();
+        let mut stack: Vec<(usize, char)> = vec![];
+        let mut offset = 0;
+        for token in Token::default().tokens(syntax, text) {
+            if let TokenType::Punctuation(_) = token.ty() {
+                for (i, c) in token.buffer().char_indices() {
+                    let at = offset + i;
+                    if let Some((open, _)) = BRACKETS.iter().find(|(_, close)| *close == c) {
+                        // Openers left inside the pair are the stray ones, like `{` in `({)`
+                        match stack.iter().rposition(|(_, top)| top == open) {
+                            Some(pos) => {
+                                let strays = stack.drain(pos + 1..).map(|(at, _)| at);
+                                brackets.unmatched.extend(strays);
+                                let (start, _) = stack.pop().unwrap_or_default();
+                                brackets.pairs.push((start, at));
+                            }
+                            None => brackets.unmatched.push(at),
+                        }
+                    } else if BRACKETS.iter().any(|(open, _)| *open == c) {
+                        stack.push((at, c));
+                    }
+                }
+            }
+            offset += token.buffer().len();
+        }
+        brackets
+            .unmatched
+            .extend(stack.into_iter().map(|(at, _)| at));
+        brackets.unmatched.sort_unstable();
+        brackets
+    }
+
+    /// Offset of the bracket paired with the one at `offset`.
+    pub fn pair_of(&self, offset: usize) -> Option<usize> {
+        self.pairs.iter().find_map(|&(open, close)| {
+            if open == offset {
+                Some(close)
+            } else if close == offset {
+                Some(open)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Byte offsets of brackets without a pair.
+pub fn unmatched_brackets(syntax: &Syntax, text: &str) -> Vec<usize> {
+    BracketPairs::new(syntax, text).unmatched
+}
+
+#[cfg(feature = "egui")]
+#[derive(Default)]
+pub struct BracketScanner;
+
+#[cfg(feature = "egui")]
+impl egui::util::cache::ComputerMut<(&Syntax, &str), BracketPairs> for BracketScanner {
+    fn compute(&mut self, (syntax, text): (&Syntax, &str)) -> BracketPairs {
+        BracketPairs::new(syntax, text)
+    }
+}
+
+#[cfg(feature = "egui")]
+pub type BracketCache = egui::util::cache::FrameCache<BracketPairs, BracketScanner>;
+
+#[cfg(feature = "egui")]
+pub fn bracket_pairs(ctx: &egui::Context, syntax: &Syntax, text: &str) -> BracketPairs {
+    ctx.memory_mut(|mem| mem.caches.cache::<BracketCache>().get((syntax, text)))
+}
@@ -1,26 +1,43 @@
+use super::Callback;
 use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Members of a dynamic type, receives the method prefix and returns matching items
+pub type DynamicMembers = dyn Fn(&str) -> Vec<CompletionItem> + Send + Sync;
 
 /// Syntax style for method calls
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SyntaxStyle {
     /// Dot notation: self.move_to()
+    #[default]
     Dot,
     /// Colon notation: self:move_to()
     Colon,
+    /// Any other member access, like `::` for Type::new() or `->` for ptr->field
+    Separator(&'static str),
 }
 
-impl Default for SyntaxStyle {
-    fn default() -> Self {
-        Self::Dot
+impl SyntaxStyle {
+    /// Text between the type and its member
+    pub fn separator(&self) -> &'static str {
+        match self {
+            SyntaxStyle::Dot => ".",
+            SyntaxStyle::Colon => ":",
+            SyntaxStyle::Separator(separator) => separator,
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum CompType {
     Global,
     Field,
     Function,
     Snippet,
+    /// Value listed under a namespace, like the variants of an enum in `Color::Red`
+    Constant,
 }
 
 /// Helper struct for building completions with a fluent API
@@ -35,7 +52,7 @@ impl CompletionsBuilder {
     }
 
     /// Add a new completion item
-    pub fn add(&mut self, display: impl Into<String>, comp_type: CompType) -> ItemBuilder {
+    pub fn add(&mut self, display: impl Into<String>, comp_type: CompType) -> ItemBuilder<'_> {
         ItemBuilder {
             builder: self,
             display: display.into(),
@@ -67,7 +84,7 @@ pub struct ItemBuilder<'a> {
 }
 
 impl<'a> ItemBuilder<'a> {
-    /// Set the snippet to insert (can include $ for cursor position)
+    /// Set the snippet to insert (can include $ for cursor position, or $1, $2 and $0 tabstops)
     pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
         self.snippet = Some(snippet.into());
         self
@@ -135,8 +152,37 @@ pub trait CustomType {
     }
 }
 
+/// Several [`CustomType`]s registered at once, implemented for tuples of up to twelve types
+pub trait CustomTypes {
+    fn register(registry: &mut CustomTypeRegistry);
+}
+
+macro_rules! impl_custom_types {
+    ($($ty:ident),+) => {
+        impl<$($ty: CustomType),+> CustomTypes for ($($ty,)+) {
+            fn register(registry: &mut CustomTypeRegistry) {
+                $(registry.register_trait_type::<$ty>();)+
+            }
+        }
+    };
+}
+
+impl_custom_types!(A);
+impl_custom_types!(A, B);
+impl_custom_types!(A, B, C);
+impl_custom_types!(A, B, C, D);
+impl_custom_types!(A, B, C, D, E);
+impl_custom_types!(A, B, C, D, E, F);
+impl_custom_types!(A, B, C, D, E, F, G);
+impl_custom_types!(A, B, C, D, E, F, G, H);
+impl_custom_types!(A, B, C, D, E, F, G, H, I);
+impl_custom_types!(A, B, C, D, E, F, G, H, I, J);
+impl_custom_types!(A, B, C, D, E, F, G, H, I, J, K);
+impl_custom_types!(A, B, C, D, E, F, G, H, I, J, K, L);
+
 /// Represents a completion item with optional snippet and documentation
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub struct CompletionItem {
     /// The full text to display and match against
     pub display: String,
@@ -145,6 +191,11 @@ pub struct CompletionItem {
     /// Documentation to show in popup (supports markdown-like formatting)
     pub documentation: Option<String>,
     pub comp_type: CompType,
+    /// Text inserted at a char offset of the text along with the completion, like an import.
+    /// The offset is in the text before the completion is applied.
+    pub additional_edit: Option<(usize, String)>,
+    /// Complete again at the caret after accepting, like members after a `self.` snippet
+    pub retrigger_on_accept: bool,
 }
 
 impl CompletionItem {
@@ -154,6 +205,8 @@ impl CompletionItem {
             snippet: None,
             documentation: None,
             comp_type,
+            additional_edit: None,
+            retrigger_on_accept: false,
         }
     }
 
@@ -167,6 +220,8 @@ impl CompletionItem {
             snippet: Some(snippet.into()),
             documentation: None,
             comp_type,
+            additional_edit: None,
+            retrigger_on_accept: false,
         }
     }
 
@@ -181,6 +236,8 @@ impl CompletionItem {
             snippet: Some(snippet.into()),
             documentation: Some(documentation.into()),
             comp_type,
+            additional_edit: None,
+            retrigger_on_accept: false,
         }
     }
 
@@ -194,6 +251,24 @@ impl CompletionItem {
             snippet: None,
             documentation: Some(documentation.into()),
             comp_type,
+            additional_edit: None,
+            retrigger_on_accept: false,
+        }
+    }
+
+    /// List completions at the caret right after accepting, see [`Self::retrigger_on_accept`]
+    pub fn with_retrigger_on_accept(self, retrigger_on_accept: bool) -> Self {
+        CompletionItem {
+            retrigger_on_accept,
+            ..self
+        }
+    }
+
+    /// Also insert `text` at the char `offset` when accepted, see [`Self::additional_edit`]
+    pub fn with_additional_edit(self, offset: usize, text: impl Into<String>) -> Self {
+        CompletionItem {
+            additional_edit: Some((offset, text.into())),
+            ..self
         }
     }
 
@@ -204,34 +279,124 @@ impl CompletionItem {
 
     /// Check if this item has a cursor position marker ($)
     pub fn has_cursor_marker(&self) -> bool {
-        self.insert_text().contains('$')
+        self.cursor_info().1.is_some()
     }
 
     /// Get the cursor offset (position of $) and the text without $
+    ///
+    /// Only the first `$` places the cursor, other unescaped `$` markers are removed.
+    /// Use `\$` to insert a literal `$`.
     pub fn cursor_info(&self) -> (String, Option<usize>) {
-        let text = self.insert_text();
-        if let Some(pos) = text.find('$') {
-            let without_marker = text.replace('$', "");
-            (without_marker, Some(pos))
-        } else {
-            (text.to_string(), None)
+        strip_cursor_marker(self.insert_text())
+    }
+
+    /// Text the snippet inserts, with [`SNIPPET_CARET`] where the cursor lands.
+    /// `None` for items without a snippet.
+    pub fn snippet_preview(&self) -> Option<String> {
+        let (mut text, cursor) = strip_cursor_marker(self.snippet.as_deref()?);
+        if let Some(cursor) = cursor {
+            text.insert(cursor, SNIPPET_CARET);
         }
+        Some(text)
     }
 }
 
+/// Marks the cursor position of a snippet in [`CompletionItem::snippet_preview`]
+pub const SNIPPET_CARET: char = '‸';
+
+/// Removes `$` cursor markers from the snippet, unescapes `\$`
+/// and returns byte offset of the first marker in the resulting text.
+pub(crate) fn strip_cursor_marker(snippet: &str) -> (String, Option<usize>) {
+    let (text, stops) = snippet_tabstops(snippet);
+    (text, stops.first().copied())
+}
+
+/// Removes `$` markers from the snippet and returns byte offsets of the places the caret
+/// visits in the resulting text. Numbered tabstops go first, `$1`, `$2` and so on, and the
+/// final caret `$0` last, at the snippet end if it has none. Without numbered tabstops
+/// the first bare `$` is the only place, other bare `$` markers are removed.
+pub(crate) fn snippet_tabstops(snippet: &str) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(snippet.len());
+    let mut cursor = None;
+    let mut numbered = BTreeMap::new();
+    let mut chars = snippet.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'$') => {
+                text.push('$');
+                chars.next();
+            }
+            '$' => {
+                let mut number = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    number.push(digit);
+                }
+                match number.parse::<usize>() {
+                    // Repeated numbers keep their first place
+                    Ok(number) => {
+                        numbered.entry(number).or_insert(text.len());
+                    }
+                    Err(_) => {
+                        cursor.get_or_insert(text.len());
+                    }
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    if numbered.is_empty() {
+        return (text, cursor.into_iter().collect());
+    }
+    let last = numbered.remove(&0).unwrap_or(text.len());
+    let mut stops: Vec<usize> = numbered.into_values().collect();
+    stops.push(last);
+    (text, stops)
+}
+
 /// Extension to the Completer for custom type support
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct CustomTypeRegistry {
     /// Maps type names (like "self") to their available methods/properties
-    pub types: HashMap<String, TypeInfo>,
+    types: HashMap<String, TypeInfo>,
     /// Global completions (not tied to a type)
-    pub globals: BTreeMap<String, CompletionItem>,
+    globals: BTreeMap<String, CompletionItem>,
+    /// Members computed on demand, in addition to the ones in `types`
+    dynamic_types: HashMap<String, Callback<DynamicMembers>>,
+    /// Characters besides alphanumerics and `_` that belong to identifiers, like `-` in CSS
+    word_chars: Vec<char>,
+    /// Bumped by every change made through the registry methods
+    generation: u64,
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct TypeInfo {
     pub items: BTreeMap<String, CompletionItem>,
     pub syntax_style: SyntaxStyle,
+    /// Members match the method prefix regardless of case
+    pub case_insensitive: bool,
+}
+
+impl TypeInfo {
+    /// Members matched by case
+    pub fn new(items: BTreeMap<String, CompletionItem>, syntax_style: SyntaxStyle) -> Self {
+        TypeInfo {
+            items,
+            syntax_style,
+            case_insensitive: false,
+        }
+    }
+
+    /// Member name starts with the method prefix
+    fn matches(&self, member: &str, method_prefix: &str) -> bool {
+        if self.case_insensitive {
+            member
+                .to_lowercase()
+                .starts_with(&method_prefix.to_lowercase())
+        } else {
+            member.starts_with(method_prefix)
+        }
+    }
 }
 
 impl CustomTypeRegistry {
@@ -239,6 +404,51 @@ impl CustomTypeRegistry {
         Self::default()
     }
 
+    /// Members registered for the type by name, without the dynamic ones
+    pub fn type_members(&self, name: &str) -> Option<&BTreeMap<String, CompletionItem>> {
+        self.types.get(name).map(|info| &info.items)
+    }
+
+    /// Registered types by name, change them through the `register_*` methods
+    pub fn types(&self) -> &HashMap<String, TypeInfo> {
+        &self.types
+    }
+
+    /// Names of the registered types, sorted
+    pub fn type_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.types.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Completions not tied to a type
+    pub fn globals(&self) -> &BTreeMap<String, CompletionItem> {
+        &self.globals
+    }
+
+    /// Changes whenever types, globals or word chars are registered or changed,
+    /// so completions built from the registry know to rebuild
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Some type has members computed on demand, which may change without the generation
+    pub fn has_dynamic_types(&self) -> bool {
+        !self.dynamic_types.is_empty()
+    }
+
+    /// Types for a change, counted in [`Self::generation`]
+    fn types_mut(&mut self) -> &mut HashMap<String, TypeInfo> {
+        self.generation += 1;
+        &mut self.types
+    }
+
+    /// Globals for a change, counted in [`Self::generation`]
+    fn globals_mut(&mut self) -> &mut BTreeMap<String, CompletionItem> {
+        self.generation += 1;
+        &mut self.globals
+    }
+
     /// Register a type that implements the CustomType trait
     ///
     /// # Example
@@ -254,6 +464,28 @@ impl CustomTypeRegistry {
         self.register_type_with_snippets_and_style(type_name, completions, syntax_style);
     }
 
+    /// Register every type of a tuple implementing the CustomType trait
+    ///
+    /// # Example
+    /// ```
+    /// # use egui_code_editor::completer::custom_types::{CompletionsBuilder, CustomType};
+    /// # macro_rules! types {
+    /// #     ($($ty:ident),+) => {$(
+    /// #         struct $ty;
+    /// #         impl CustomType for $ty {
+    /// #             fn type_name() -> &'static str { stringify!($ty) }
+    /// #             fn build_completions(_: &mut CompletionsBuilder) {}
+    /// #         }
+    /// #     )+};
+    /// # }
+    /// # types!(MyCharacter, World, Inventory);
+    /// # let mut registry = egui_code_editor::completer::custom_types::CustomTypeRegistry::new();
+    /// registry.register_trait_types::<(MyCharacter, World, Inventory)>();
+    /// ```
+    pub fn register_trait_types<T: CustomTypes>(&mut self) {
+        T::register(self);
+    }
+
     /// Register a type with simple method names (no snippets)
     pub fn register_type_simple(&mut self, type_name: impl Into<String>, methods: Vec<String>) {
         let type_name = type_name.into();
@@ -262,11 +494,12 @@ impl CustomTypeRegistry {
             .map(|m| (m.clone(), CompletionItem::new(m, CompType::Function)))
             .collect();
 
-        self.types.insert(
+        self.types_mut().insert(
             type_name,
             TypeInfo {
                 items: methods_map,
                 syntax_style: SyntaxStyle::Dot,
+                case_insensitive: false,
             },
         );
     }
@@ -322,11 +555,12 @@ impl CustomTypeRegistry {
             })
             .collect();
 
-        self.types.insert(
+        self.types_mut().insert(
             type_name,
             TypeInfo {
                 items: methods_map,
                 syntax_style,
+                case_insensitive: false,
             },
         );
     }
@@ -360,11 +594,12 @@ impl CustomTypeRegistry {
             })
             .collect();
 
-        self.types.insert(
+        self.types_mut().insert(
             type_name,
             TypeInfo {
                 items: methods_map,
                 syntax_style: SyntaxStyle::Dot,
+                case_insensitive: false,
             },
         );
     }
@@ -398,15 +633,74 @@ impl CustomTypeRegistry {
             })
             .collect();
 
-        self.types.insert(
+        self.types_mut().insert(
             type_name,
             TypeInfo {
                 items: methods_map,
                 syntax_style: SyntaxStyle::Dot,
+                case_insensitive: false,
             },
         );
     }
 
+    /// Register a type whose members are computed on demand (uses Dot syntax by default)
+    /// The closure receives the method prefix and returns matching items,
+    /// statically registered members of the same type are kept
+    ///
+    /// Example:
+    /// ```
+    /// use egui_code_editor::completer::custom_types::{CompType, CompletionItem};
+    /// # let mut registry = egui_code_editor::completer::custom_types::CustomTypeRegistry::new();
+    ///
+    /// let fields = ["name", "position", "visible"];
+    /// registry.register_type_dynamic("node", move |prefix| {
+    ///     fields.iter()
+    ///         .filter(|field| field.starts_with(prefix))
+    ///         .map(|field| CompletionItem::new(*field, CompType::Field))
+    ///         .collect()
+    /// });
+    /// ```
+    pub fn register_type_dynamic(
+        &mut self,
+        type_name: impl Into<String>,
+        members: impl Fn(&str) -> Vec<CompletionItem> + Send + Sync + 'static,
+    ) {
+        let type_name = type_name.into();
+        self.types_mut()
+            .entry(type_name.clone())
+            .or_insert(TypeInfo {
+                items: BTreeMap::new(),
+                syntax_style: SyntaxStyle::Dot,
+                case_insensitive: false,
+            });
+        self.dynamic_types
+            .insert(type_name, Callback(Arc::new(members)));
+    }
+
+    /// Characters besides alphanumerics and `_` that belong to identifiers
+    pub fn set_word_chars(&mut self, word_chars: &[char]) {
+        self.generation += 1;
+        self.word_chars = word_chars.to_vec();
+    }
+
+    /// Characters set with [`Self::set_word_chars`]
+    pub fn word_chars(&self) -> &[char] {
+        &self.word_chars
+    }
+
+    /// Part of an identifier when scanning for the prefix
+    pub fn is_word_char(&self, c: char) -> bool {
+        c.is_alphanumeric() || c == '_' || self.word_chars.contains(&c)
+    }
+
+    /// Match members of a registered type regardless of case, like in SQL.
+    /// Registering the type again resets it to case-sensitive.
+    pub fn set_case_insensitive(&mut self, type_name: &str, case_insensitive: bool) {
+        if let Some(type_info) = self.types_mut().get_mut(type_name) {
+            type_info.case_insensitive = case_insensitive;
+        }
+    }
+
     /// Register global completions (like 'foreach', 'if', etc.) with full options
     ///
     /// Example:
@@ -439,13 +733,18 @@ impl CustomTypeRegistry {
             (None, Some(d)) => CompletionItem::with_docs(&name_str, d, comp_type),
             (None, None) => CompletionItem::new(&name_str, comp_type),
         };
-        self.globals.insert(name_str, item);
+        self.globals_mut().insert(name_str, item);
+    }
+
+    /// Register a global from a complete item, keyed by its display text
+    pub fn register_global_item(&mut self, item: CompletionItem) {
+        self.globals_mut().insert(item.display.clone(), item);
     }
 
     /// Register a simple global without snippet or docs
     pub fn register_global_simple(&mut self, name: impl Into<String>, comp_type: CompType) {
         let name = name.into();
-        self.globals
+        self.globals_mut()
             .insert(name.clone(), CompletionItem::new(name, comp_type));
     }
 
@@ -457,7 +756,7 @@ impl CustomTypeRegistry {
         comp_type: CompType,
     ) {
         let name_str = name.into();
-        self.globals.insert(
+        self.globals_mut().insert(
             name_str.clone(),
             CompletionItem::with_snippet(&name_str, snippet, comp_type),
         );
@@ -471,7 +770,7 @@ impl CustomTypeRegistry {
         comp_type: CompType,
     ) {
         let name_str = name.into();
-        self.globals.insert(
+        self.globals_mut().insert(
             name_str.clone(),
             CompletionItem::with_docs(&name_str, documentation, comp_type),
         );
@@ -486,7 +785,7 @@ impl CustomTypeRegistry {
         comp_type: CompType,
     ) {
         let name_str = name.into();
-        self.globals.insert(
+        self.globals_mut().insert(
             name_str.clone(),
             CompletionItem::with_snippet_and_docs(&name_str, snippet, documentation, comp_type),
         );
@@ -499,43 +798,94 @@ impl CustomTypeRegistry {
             .any(|info| info.syntax_style == SyntaxStyle::Colon)
     }
 
+    /// Separators registered with [`SyntaxStyle::Separator`]
+    fn custom_separators(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.types
+            .values()
+            .filter_map(|info| match info.syntax_style {
+                SyntaxStyle::Separator(separator) if !separator.is_empty() => Some(separator),
+                _ => None,
+            })
+    }
+
+    /// Split member access at the last separator, e.g. "self.mo" -> ("self", "mo").
+    /// When separators end at the same place the longest wins, so "obj::me" splits at "::"
+    pub fn split_member_access<'a>(&self, text: &'a str) -> Option<(&'a str, &'a str)> {
+        [".", ":"]
+            .into_iter()
+            .chain(self.custom_separators())
+            .filter_map(|separator| {
+                let start = text.rfind(separator)?;
+                Some((start + separator.len(), separator.len(), start))
+            })
+            .max()
+            .map(|(end, _, start)| (&text[..start], &text[end..]))
+    }
+
+    /// Byte index where the member access chain in front of the cursor starts,
+    /// e.g. the start of "self.mo" in "x = self.mo"
+    pub fn context_start(&self, text_before_cursor: &str) -> usize {
+        let mut separators = vec!["."];
+        if self.has_colon_syntax() {
+            separators.push(":");
+        }
+        separators.extend(self.custom_separators());
+        separators.sort_by_key(|separator| std::cmp::Reverse(separator.len()));
+
+        let mut start = text_before_cursor.len();
+        loop {
+            let rest = &text_before_cursor[..start];
+            if let Some(c) = rest.chars().next_back()
+                && self.is_word_char(c)
+            {
+                start -= c.len_utf8();
+            } else if let Some(separator) = separators.iter().find(|s| rest.ends_with(*s)) {
+                start -= separator.len();
+            } else {
+                return start;
+            }
+        }
+    }
+
     /// Get completions for a given prefix
     /// Returns (display_text, completion_item)
+    ///
+    /// A prefix ending with a separator of a known type, like `self.`, lists all of its members.
+    /// A separator without a known type in front of it gives no completions.
     pub fn get_completions(&self, prefix: &str) -> Vec<(String, CompletionItem)> {
         let mut results = Vec::new();
 
         // Check if we're completing a member access (e.g., "self.move" or "self:move")
-        // Try both separators
-        let separator_and_type = prefix
-            .rsplit_once('.')
-            .map(|(t, m)| (t, m, '.'))
-            .or_else(|| prefix.rsplit_once(':').map(|(t, m)| (t, m, ':')));
-
-        if let Some((type_part, method_prefix, separator)) = separator_and_type {
+        if let Some((type_part, method_prefix)) = self.split_member_access(prefix) {
             let type_name = type_part.trim();
 
             if let Some(type_info) = self.types.get(type_name) {
                 // Determine the correct separator for this type
-                let correct_separator = match type_info.syntax_style {
-                    SyntaxStyle::Dot => '.',
-                    SyntaxStyle::Colon => ':',
-                };
+                let correct_separator = type_info.syntax_style.separator();
 
                 // Add methods that match the prefix
                 for (method_name, item) in &type_info.items {
-                    if method_prefix.is_empty() || method_name.starts_with(method_prefix) {
+                    if type_info.matches(method_name, method_prefix) {
                         let display = format!("{}{}{}", type_name, correct_separator, method_name);
                         results.push((display, item.clone()));
                     }
                 }
 
-                return results;
+                if let Some(members) = self.dynamic_types.get(type_name) {
+                    for item in (members.0)(method_prefix) {
+                        let display = format!("{}{}{}", type_name, correct_separator, item.display);
+                        results.push((display, item));
+                    }
+                }
             }
+
+            // Only members follow a separator, never type names or globals
+            return results;
         }
 
-        // Check type names (e.g., "sel" -> "self")
+        // Check type names (e.g., "sel" -> "self"), a global of the same name replaces them
         for type_name in self.types.keys() {
-            if type_name.starts_with(prefix) {
+            if type_name.starts_with(prefix) && !self.globals.contains_key(type_name) {
                 results.push((
                     type_name.clone(),
                     CompletionItem::new(type_name, CompType::Field),
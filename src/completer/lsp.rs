@@ -0,0 +1,140 @@
+use super::custom_types::{CompType, CompletionItem};
+use serde_json::Value;
+
+/// LSP `InsertTextFormat` of snippets with tab stops
+const SNIPPET_FORMAT: u64 = 2;
+
+/// Completions from the result of an LSP `textDocument/completion` request,
+/// either a `CompletionList` or a bare array of `CompletionItem`.
+/// Items without a `label` are skipped.
+///
+/// Tab stops of snippet items become a cursor marker at the first one, placeholders keep their text.
+pub fn completions_from_lsp(
+    json: &str,
+) -> Result<Vec<(String, CompletionItem)>, serde_json::Error> {
+    let items = match serde_json::from_str::<Value>(json)? {
+        Value::Object(mut list) if list.contains_key("items") => {
+            list.remove("items").unwrap_or_default()
+        }
+        value => value,
+    };
+    let items: Vec<Value> = serde_json::from_value(items)?;
+    Ok(items.iter().filter_map(completion_item).collect())
+}
+
+fn completion_item(item: &Value) -> Option<(String, CompletionItem)> {
+    let label = item.get("label")?.as_str()?;
+    let insert = item
+        .get("textEdit")
+        .and_then(|edit| edit.get("newText"))
+        .or_else(|| item.get("insertText"))
+        .and_then(Value::as_str);
+    let snippet = insert.map(|text| {
+        if item.get("insertTextFormat").and_then(Value::as_u64) == Some(SNIPPET_FORMAT) {
+            convert_snippet(text)
+        } else {
+            text.replace('$', "\\$")
+        }
+    });
+    let documentation = item
+        .get("documentation")
+        .and_then(|docs| docs.as_str().or_else(|| docs.get("value")?.as_str()))
+        .or_else(|| item.get("detail")?.as_str())
+        .map(String::from);
+    let comp_type = comp_type(item.get("kind").and_then(Value::as_u64));
+    Some((
+        label.to_string(),
+        CompletionItem {
+            display: label.to_string(),
+            snippet: snippet.or_else(|| Some(label.replace('$', "\\$"))),
+            documentation,
+            comp_type,
+            additional_edit: None,
+            retrigger_on_accept: false,
+        },
+    ))
+}
+
+/// Maps LSP `CompletionItemKind`
+fn comp_type(kind: Option<u64>) -> CompType {
+    match kind {
+        // Method, Function, Constructor
+        Some(2..=4) => CompType::Function,
+        // Field, Property
+        Some(5 | 10) => CompType::Field,
+        Some(15) => CompType::Snippet,
+        // EnumMember, Constant
+        Some(20 | 21) => CompType::Constant,
+        _ => CompType::Global,
+    }
+}
+
+/// Converts LSP snippet syntax to the completer one: `$1`, `${1:name}` and `${1|a,b|}`
+/// turn into the numbered tabstop in front of the placeholder text or first choice,
+/// `$0` stays the final caret, variables are dropped and literal `$` is escaped.
+fn convert_snippet(snippet: &str) -> String {
+    let mut result = String::with_capacity(snippet.len());
+    convert_snippet_into(snippet, &mut result);
+    result
+}
+
+fn convert_snippet_into(snippet: &str, result: &mut String) {
+    let mut chars = snippet.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, '$')) => result.push_str("\\$"),
+                Some((_, escaped)) => result.push(escaped),
+                None => result.push('\\'),
+            },
+            '$' => match chars.peek() {
+                Some((_, '{')) => {
+                    // Find the matching brace, placeholders can be nested
+                    let start = i + 2;
+                    let mut depth = 0;
+                    let mut end = snippet.len();
+                    for (j, c) in chars.by_ref() {
+                        match c {
+                            '{' => depth += 1,
+                            '}' if depth == 1 => {
+                                end = j;
+                                break;
+                            }
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                    }
+                    let body = &snippet[start.min(end)..end];
+                    let name_end = body
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(body.len());
+                    let (name, rest) = body.split_at(name_end);
+                    if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+                        result.push('$');
+                        result.push_str(name);
+                    }
+                    if let Some(placeholder) = rest.strip_prefix(':') {
+                        convert_snippet_into(placeholder, result);
+                    } else if let Some(choices) = rest.strip_prefix('|') {
+                        let choice = choices.trim_end_matches('|').split(',').next();
+                        result.push_str(&choice.unwrap_or_default().replace('$', "\\$"));
+                    }
+                }
+                Some((_, next)) if next.is_alphanumeric() || *next == '_' => {
+                    let mut name = String::new();
+                    while let Some((_, c)) =
+                        chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_')
+                    {
+                        name.push(c);
+                    }
+                    if name.chars().all(|c| c.is_ascii_digit()) {
+                        result.push('$');
+                        result.push_str(&name);
+                    }
+                }
+                _ => result.push_str("\\$"),
+            },
+            c => result.push(c),
+        }
+    }
+}
@@ -1,28 +1,154 @@
 pub mod custom_types;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "path_completion")]
+pub mod path;
 mod trie;
-use crate::{CodeEditor, ColorTheme, Syntax, Token, TokenType, format_token};
-use custom_types::{CompType, CompletionItem, CustomTypeRegistry};
+mod user_words;
+use crate::highlighting::{Token, token_at};
+use crate::{CodeEditor, ColorTheme, Syntax, TokenType, format_token};
+use custom_types::{CompType, CompletionItem, CustomTypeRegistry, snippet_tabstops};
 use egui::{Event, Frame, Modifiers, Sense, Stroke, TextBuffer, text_edit::TextEditOutput};
-use std::collections::BTreeSet;
+#[cfg(feature = "lsp")]
+pub use lsp::completions_from_lsp;
+#[cfg(feature = "path_completion")]
+pub use path::PathCompletionSource;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
 use trie::Trie;
+use user_words::UserWords;
 
-impl From<&Syntax> for Trie {
-    fn from(syntax: &Syntax) -> Trie {
+/// Closure shared between clones of the Completer, compared by pointer.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Callback(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> std::fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Callback({:p})", Arc::as_ptr(&self.0))
+    }
+}
+
+impl<F: ?Sized> PartialEq for Callback<F> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Entries of the completion popup shown without scrolling
+const VISIBLE_ROWS: usize = 10;
+
+type CompletionFilter = dyn Fn(&str, &CompletionItem) -> bool + Send + Sync;
+type AcceptHook = Mutex<dyn FnMut(&str, &CompletionItem) + Send>;
+// Accepted completion: display, replaced, inserted text, additional edit
+type QueuedEdit = (
+    String,
+    std::ops::Range<usize>,
+    String,
+    Option<(usize, String)>,
+);
+
+/// Byte ranges of words in `text` starting with `prefix`, the ones the completer would offer.
+pub fn prefix_occurrences(text: &str, prefix: &str) -> Vec<std::ops::Range<usize>> {
+    if prefix.is_empty() {
+        return vec![];
+    }
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(prefix)
+        .filter(|(start, _)| {
+            text[..*start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !is_word(c))
+        })
+        .map(|(start, _)| start..start + prefix.len())
+        .collect()
+}
+
+/// Type the lexer gave the text a caret at `char_index` is in, within tokens as the editor
+/// draws them: comment tags and string quotes split out of their comment or string.
+fn lexed_type_at(tokens: &[Token], char_index: usize) -> Option<TokenType> {
+    let target = char_index.saturating_sub(1);
+    let mut end = 0;
+    let mut comment = TokenType::Comment(false);
+    for token in tokens {
+        let ty = match token.ty() {
+            TokenType::CommentTag => comment,
+            TokenType::StringDelim(quote) => TokenType::Str(quote),
+            ty => ty,
+        };
+        if matches!(ty, TokenType::Comment(_) | TokenType::DocComment(_)) {
+            comment = ty;
+        }
+        end += token.buffer().chars().count();
+        if target < end {
+            return Some(ty);
+        }
+    }
+    None
+}
+
+/// Char ranges of the [`prefix_occurrences`] on the galley rows within `visible`,
+/// a y range in galley coordinates.
+pub(crate) fn visible_prefix_occurrences(
+    galley: &egui::Galley,
+    visible: egui::Rangef,
+    prefix: &str,
+) -> Vec<std::ops::Range<usize>> {
+    let mut chars = 0;
+    let mut rows: Option<std::ops::Range<usize>> = None;
+    for row in &galley.rows {
+        let next = chars + row.char_count_including_newline();
+        if row.max_y() >= visible.min && row.min_y() <= visible.max {
+            rows = Some(rows.map_or(chars..next, |rows| rows.start..next));
+        }
+        chars = next;
+    }
+    let Some(rows) = rows else {
+        return vec![];
+    };
+    let text = galley.text();
+    let start = text.byte_index_from_char_index(rows.start);
+    let end = text[start..]
+        .char_indices()
+        .nth(rows.len())
+        .map_or(text.len(), |(i, _)| start + i);
+    // A wrapped row can start inside a word, the whole line keeps the word starts right
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let mut chars = rows.start - text[line_start..start].chars().count();
+    let mut byte = line_start;
+    let prefix_chars = prefix.chars().count();
+    prefix_occurrences(&text[line_start..end], prefix)
+        .into_iter()
+        .map(|range| {
+            let at = line_start + range.start;
+            chars += text[byte..at].chars().count();
+            byte = at;
+            chars..chars + prefix_chars
+        })
+        .collect()
+}
+
+fn key_press(key: egui::Key) -> Event {
+    Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+impl Trie {
+    fn from_syntax_words(words: &BTreeSet<&str>, case_sensitive: bool) -> Trie {
         let mut trie = Trie::default();
-        syntax.keywords.iter().for_each(|word| trie.push(word));
-        syntax.types.iter().for_each(|word| trie.push(word));
-        syntax.special.iter().for_each(|word| trie.push(word));
-        if !syntax.case_sensitive {
-            syntax
-                .keywords
-                .iter()
-                .for_each(|word| trie.push(&word.to_lowercase()));
-            syntax
-                .types
-                .iter()
-                .for_each(|word| trie.push(&word.to_lowercase()));
-            syntax
-                .special
+        words.iter().for_each(|word| trie.push(word));
+        if !case_sensitive {
+            words
                 .iter()
                 .for_each(|word| trie.push(&word.to_lowercase()));
         }
@@ -30,6 +156,168 @@ impl From<&Syntax> for Trie {
     }
 }
 
+#[derive(Default, Debug, Clone, PartialEq)]
+/// Syntax words kept per category, so they can be queried separately
+struct SyntaxTries {
+    keywords: Trie,
+    types: Trie,
+    special: Trie,
+    /// Words added with [`Completer::push_word`]
+    pushed: Trie,
+    /// Bumped by every pushed word
+    generation: u64,
+}
+
+impl From<&Syntax> for SyntaxTries {
+    fn from(syntax: &Syntax) -> SyntaxTries {
+        SyntaxTries {
+            keywords: Trie::from_syntax_words(&syntax.keywords, syntax.case_sensitive),
+            types: Trie::from_syntax_words(&syntax.types, syntax.case_sensitive),
+            special: Trie::from_syntax_words(&syntax.special, syntax.case_sensitive),
+            pushed: Trie::default(),
+            generation: 0,
+        }
+    }
+}
+
+impl SyntaxTries {
+    fn push_word(&mut self, word: &str) {
+        self.generation += 1;
+        self.pushed.push(word);
+    }
+
+    fn category(&self, ty: TokenType) -> Option<&Trie> {
+        match ty {
+            TokenType::Keyword => Some(&self.keywords),
+            TokenType::Type => Some(&self.types),
+            TokenType::Special => Some(&self.special),
+            TokenType::Literal => Some(&self.pushed),
+            _ => None,
+        }
+    }
+
+    /// Suffixes completing `prefix` in all categories
+    fn find_completions(&self, prefix: &str) -> Vec<String> {
+        [&self.keywords, &self.types, &self.special, &self.pushed]
+            .into_iter()
+            .flat_map(|trie| trie.find_completions(prefix))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TriggerKey(Option<egui::KeyboardShortcut>);
+
+impl Default for TriggerKey {
+    fn default() -> Self {
+        TriggerKey(Some(egui::KeyboardShortcut::new(
+            Modifiers::CTRL,
+            egui::Key::Space,
+        )))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Edit made by accepting a completion, reported once the editor applied it.
+pub struct AppliedCompletion {
+    /// Display text of the accepted completion
+    pub display: String,
+    /// Char range of the prefix and replaced trailing chars in the text before the edit
+    pub replaced: std::ops::Range<usize>,
+    /// Char range of the inserted text in the text after the edit
+    pub inserted: std::ops::Range<usize>,
+    /// Whole text after the edit
+    pub text: String,
+    /// [`CompletionItem::additional_edit`] with the offset moved to the text after the edit.
+    /// [`CodeEditor`](crate::CodeEditor) applies it, other widgets should insert it themselves.
+    pub additional_edit: Option<(usize, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Group of the completion popup with [`Completer::with_grouped_popup`], in the order they are listed.
+pub enum CompletionSection {
+    Keywords,
+    Types,
+    Members,
+    Functions,
+    Snippets,
+    /// Globals, constants and words learned from the text
+    Words,
+}
+
+impl CompletionSection {
+    /// Header shown above the group
+    pub fn label(&self) -> &'static str {
+        match self {
+            CompletionSection::Keywords => "Keywords",
+            CompletionSection::Types => "Types",
+            CompletionSection::Members => "Members",
+            CompletionSection::Functions => "Functions",
+            CompletionSection::Snippets => "Snippets",
+            CompletionSection::Words => "Words",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+/// When words learned from the document with [`Completer::with_user_words`] are collected again.
+pub enum UserWordRefresh {
+    /// On every edit
+    #[default]
+    EveryChange,
+    /// At most once per interval, edits in between are picked up when it passes
+    Interval(std::time::Duration),
+    /// Once the given number of edits piled up
+    Changes(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How the selected entry of the completion popup stands out from the others.
+pub enum SelectionHighlight {
+    /// Outline in the color, theme's literal color if None
+    Stroke(Option<egui::Color32>),
+    /// Background of the entry
+    Fill(egui::Color32),
+    /// Outline and background together
+    Both {
+        stroke: Option<egui::Color32>,
+        fill: egui::Color32,
+    },
+}
+
+impl Default for SelectionHighlight {
+    fn default() -> Self {
+        SelectionHighlight::Stroke(None)
+    }
+}
+
+impl SelectionHighlight {
+    /// Background of an entry, unselected ones keep the theme background.
+    pub fn fill(&self, theme: &ColorTheme, selected: bool) -> egui::Color32 {
+        match self {
+            SelectionHighlight::Fill(fill) | SelectionHighlight::Both { fill, .. } if selected => {
+                *fill
+            }
+            _ => theme.bg(),
+        }
+    }
+
+    /// Outline of an entry with the given width, none for unselected ones.
+    pub fn stroke(&self, theme: &ColorTheme, selected: bool, width: f32) -> Stroke {
+        match self {
+            SelectionHighlight::Stroke(color) | SelectionHighlight::Both { stroke: color, .. }
+                if selected =>
+            {
+                Stroke::new(
+                    width,
+                    color.unwrap_or_else(|| theme.type_color(TokenType::Literal)),
+                )
+            }
+            _ => Stroke::NONE,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq)]
 /// Code-completer with pop-up above CodeEditor.
 pub struct Completer {
@@ -37,29 +325,299 @@ pub struct Completer {
     prefix_range: (usize, usize), // Start and end position of the prefix being completed
     cursor: usize,
     ignore_cursor: Option<usize>,
-    trie_syntax: Trie,
-    trie_user: Option<Trie>,
+    refocus: bool, // Esc took the focus from the editor along with closing the popup
+    trie_syntax: SyntaxTries,
+    user_words: Option<UserWords>,
+    user_word_refresh: UserWordRefresh,
+    user_word_changes: usize,    // Edits since the user words were collected
+    user_word_time: Option<f64>, // Input time of the last collection
     variant_id: usize,
     completions: Vec<(String, CompletionItem)>, // Changed to Vec to maintain order and store items
     custom_types: CustomTypeRegistry,
+    filter: Option<Callback<CompletionFilter>>,
+    on_accept: Option<Callback<AcceptHook>>,
+    replace_trailing: bool,
+    prefix_highlight: bool,
+    selection_highlight: SelectionHighlight,
+    docs_size: Option<egui::Vec2>,
+    max_display_len: Option<usize>,
+    popup_gap: Option<egui::Vec2>,
+    grouped_popup: bool,
+    sections: Vec<(CompletionSection, usize)>, // First completion of each group
+    #[cfg(feature = "path_completion")]
+    path_source: Option<PathCompletionSource>,
+    #[cfg(feature = "path_completion")]
+    path_dir: Option<String>, // Directory of the path being typed in a string
+    trailing: usize, // Identifier chars right after the cursor
+    suppress_in: Vec<TokenType>,
+    token_at_caret: Option<TokenType>,
+    pub(crate) editor_tokens: Option<Arc<[Token]>>, // Drawn by the editor this frame, lexed here without it
+    built_for: Option<(String, u64, u64)>, // Prefix and generations the completions were built for
+    accepted: Option<QueuedEdit>,
+    retrigger: bool, // Accepted item completes again at the new caret
+    trigger_key: TriggerKey,
+    forced: bool, // Trigger key opened the popup at this caret, even without a prefix
+    editor_id: Option<egui::Id>, // Widget shown with the completer, the trigger key needs its focus
+    applied: Option<AppliedCompletion>,
+    indent: String, // Leading blanks of the caret line, continuation lines get them
+    snippet_stops: Vec<usize>, // Tabstops after the caret, char offsets in the accepted text
+    tabstops: Vec<usize>, // Char offsets Tab moves the caret to, next first
+    tabstop_len: usize, // Text length in chars the tabstops are placed in
+    jumped: bool,   // Caret moved to a tabstop, not away from the snippet
+    disabled_keys: Vec<egui::Key>,
 }
 
 impl Completer {
     /// Completer should be stored somewhere in your App struct.
     pub fn new_with_syntax(syntax: &Syntax) -> Self {
         Completer {
-            trie_syntax: Trie::from(syntax),
+            trie_syntax: SyntaxTries::from(syntax),
             ..Default::default()
         }
     }
 
     pub fn with_user_words(self) -> Self {
         Completer {
-            trie_user: Some(Trie::default()),
+            user_words: Some(UserWords::default()),
+            built_for: None,
+            ..self
+        }
+    }
+
+    /// How often words of the document are collected for [`Self::with_user_words`],
+    /// rebuilding them on every keystroke is costly for large documents.
+    ///
+    /// **Default: UserWordRefresh::EveryChange**
+    pub fn with_user_word_refresh(self, user_word_refresh: UserWordRefresh) -> Self {
+        Completer {
+            user_word_refresh,
+            ..self
+        }
+    }
+
+    /// Hide completions for which the predicate returns false.
+    /// Predicate receives display text and item, and runs every frame, so it can reflect
+    /// dynamic state of the app.
+    ///
+    /// # Example
+    /// ```rust
+    /// use egui_code_editor::{Completer, Syntax};
+    ///
+    /// let completer = Completer::new_with_syntax(&Syntax::rust())
+    ///     .with_filter(|display, _item| !display.ends_with("_deprecated"));
+    /// ```
+    pub fn with_filter(
+        self,
+        filter: impl Fn(&str, &CompletionItem) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Completer {
+            filter: Some(Callback(Arc::new(filter))),
+            built_for: None,
+            ..self
+        }
+    }
+
+    /// Call the hook with display text and item every time a completion is accepted.
+    /// Runs after the edit events are queued, before the editor applies them.
+    ///
+    /// # Example
+    /// ```rust
+    /// use egui_code_editor::{Completer, Syntax};
+    ///
+    /// let completer = Completer::new_with_syntax(&Syntax::rust())
+    ///     .on_accept(|display, _item| println!("completed {display}"));
+    /// ```
+    pub fn on_accept(self, hook: impl FnMut(&str, &CompletionItem) + Send + 'static) -> Self {
+        let hook: Arc<AcceptHook> = Arc::new(Mutex::new(hook));
+        Completer {
+            on_accept: Some(Callback(hook)),
+            ..self
+        }
+    }
+
+    /// Complete in the middle of a word, replacing its rest after the cursor on accept.
+    ///
+    /// **Default: false**
+    pub fn with_replace_trailing(self, replace_trailing: bool) -> Self {
+        Completer {
+            replace_trailing,
+            ..self
+        }
+    }
+
+    /// Softly highlight words starting with the prefix in the editor while the popup is open.
+    ///
+    /// **Default: false**
+    pub fn with_prefix_highlight(self, prefix_highlight: bool) -> Self {
+        Completer {
+            prefix_highlight,
+            ..self
+        }
+    }
+
+    /// Highlight of the selected completion in the popup.
+    ///
+    /// **Default: SelectionHighlight::Stroke(None)**
+    pub fn with_selection_highlight(self, selection_highlight: SelectionHighlight) -> Self {
+        Completer {
+            selection_highlight,
+            ..self
+        }
+    }
+
+    /// Maximum size of the documentation popup, longer docs scroll.
+    ///
+    /// **Default: 300.0 x 400.0**
+    pub fn with_docs_size(self, max_width: f32, max_height: f32) -> Self {
+        Completer {
+            docs_size: Some(egui::vec2(max_width, max_height)),
+            ..self
+        }
+    }
+
+    /// Maximum size of the documentation popup
+    pub fn docs_size(&self) -> egui::Vec2 {
+        self.docs_size.unwrap_or(egui::vec2(300.0, 400.0))
+    }
+
+    /// Space between the cursor and the completion list below it (`y`),
+    /// and between the list and the documentation popup to its right (`x`).
+    ///
+    /// **Default: 5.0 x 0.0**
+    pub fn with_popup_gap(self, x: f32, y: f32) -> Self {
+        Completer {
+            popup_gap: Some(egui::vec2(x, y)),
+            ..self
+        }
+    }
+
+    /// Space around the completion popups
+    pub fn popup_gap(&self) -> egui::Vec2 {
+        self.popup_gap.unwrap_or(egui::vec2(5.0, 0.0))
+    }
+
+    /// Shortcut opening the popup at the caret, with every entry when there is no prefix.
+    /// It is only taken while the editor has focus. `None` completes only while typing.
+    ///
+    /// **Default: Ctrl+Space**
+    pub fn with_trigger_key(self, trigger_key: Option<egui::KeyboardShortcut>) -> Self {
+        Completer {
+            trigger_key: TriggerKey(trigger_key),
+            ..self
+        }
+    }
+
+    /// Keys the completer leaves to the editor and the app even while the popup shows,
+    /// e.g. Esc or Tab bound globally. They also no longer move between snippet tabstops.
+    ///
+    /// **Default: none**
+    pub fn with_disabled_keys(self, keys: &[egui::Key]) -> Self {
+        Completer {
+            disabled_keys: keys.to_vec(),
+            ..self
+        }
+    }
+
+    /// Complete file paths inside string literals from the directories of `source`,
+    /// instead of words.
+    ///
+    /// **Default: None**
+    #[cfg(feature = "path_completion")]
+    pub fn with_path_completion(self, source: PathCompletionSource) -> Self {
+        Completer {
+            path_source: Some(source),
+            built_for: None,
+            ..self
+        }
+    }
+
+    /// List completions in groups under headers, like keywords, members and snippets.
+    /// Arrow keys move over the entries only.
+    ///
+    /// **Default: false**
+    pub fn with_grouped_popup(self, grouped_popup: bool) -> Self {
+        Completer {
+            grouped_popup,
+            built_for: None,
+            ..self
+        }
+    }
+
+    /// Don't complete while the caret is in a token of these types, like strings or comments.
+    /// Types are compared without their payload, so `TokenType::Str('"')` matches every string.
+    ///
+    /// **Default: empty**
+    pub fn with_suppress_in(self, types: &[TokenType]) -> Self {
+        Completer {
+            suppress_in: types.to_vec(),
+            ..self
+        }
+    }
+
+    /// Completion applied to the text on the last frame, to re-lint once per accepted completion
+    pub fn applied(&self) -> Option<&AppliedCompletion> {
+        self.applied.as_ref()
+    }
+
+    /// Type of the token the caret was in on the last frame, `None` without a focused caret
+    pub fn token_at_caret(&self) -> Option<TokenType> {
+        self.token_at_caret
+    }
+
+    /// Group of a completion in the popup
+    pub fn section(&self, display: &str, item: &CompletionItem) -> CompletionSection {
+        let syntax = &self.trie_syntax;
+        if item.comp_type == CompType::Snippet {
+            CompletionSection::Snippets
+        } else if self.custom_types.split_member_access(display).is_some() {
+            CompletionSection::Members
+        } else if syntax.keywords.contains(display) || syntax.special.contains(display) {
+            CompletionSection::Keywords
+        } else if syntax.types.contains(display) {
+            CompletionSection::Types
+        } else if item.comp_type == CompType::Function {
+            CompletionSection::Functions
+        } else {
+            CompletionSection::Words
+        }
+    }
+
+    /// Completions partitioned into their sections, in section order.
+    /// The order within a section is kept.
+    pub fn group_completions(
+        &self,
+        completions: Vec<(String, CompletionItem)>,
+    ) -> Vec<(CompletionSection, Vec<(String, CompletionItem)>)> {
+        let mut groups: BTreeMap<CompletionSection, Vec<_>> = BTreeMap::new();
+        for (display, item) in completions {
+            let section = self.section(&display, &item);
+            groups.entry(section).or_default().push((display, item));
+        }
+        groups.into_iter().collect()
+    }
+
+    /// Shorten entries of the popup longer than this many characters with `…`,
+    /// accepting one still inserts the full text.
+    ///
+    /// **Default: None**
+    pub fn with_max_display_len(self, max_display_len: usize) -> Self {
+        Completer {
+            max_display_len: Some(max_display_len),
             ..self
         }
     }
 
+    /// Text of a completion as listed in the popup
+    pub fn display_label<'a>(&self, display: &'a str) -> std::borrow::Cow<'a, str> {
+        match self.max_display_len {
+            Some(max) if display.chars().count() > max => {
+                let kept = display.chars().take(max.saturating_sub(1));
+                kept.chain(['…']).collect::<String>().into()
+            }
+            _ => display.into(),
+        }
+    }
+
     // Register a type that implements the CustomType trait (builder pattern)
     /// This is the recommended way to register custom types
     ///
@@ -83,6 +641,36 @@ impl Completer {
         self.custom_types.register_trait_type::<T>();
     }
 
+    /// Register every type of a tuple implementing the CustomType trait
+    ///
+    /// # Example
+    /// ```rust
+    /// use egui_code_editor::{Completer, Syntax};
+    /// # use egui_code_editor::completer::custom_types::{CompletionsBuilder, CustomType};
+    /// # macro_rules! types {
+    /// #     ($($ty:ident),+) => {$(
+    /// #         struct $ty;
+    /// #         impl CustomType for $ty {
+    /// #             fn type_name() -> &'static str { stringify!($ty) }
+    /// #             fn build_completions(_: &mut CompletionsBuilder) {}
+    /// #         }
+    /// #     )+};
+    /// # }
+    /// # types!(MyCharacter, World, Inventory);
+    ///
+    /// let completer = Completer::new_with_syntax(&Syntax::rust())
+    ///     .with_trait_types::<(MyCharacter, World, Inventory)>();
+    /// ```
+    pub fn with_trait_types<T: custom_types::CustomTypes>(mut self) -> Self {
+        self.custom_types.register_trait_types::<T>();
+        self
+    }
+
+    /// Register every type of a tuple implementing the CustomType trait on an existing completer
+    pub fn register_trait_types<T: custom_types::CustomTypes>(&mut self) {
+        self.custom_types.register_trait_types::<T>();
+    }
+
     pub fn custom_types(&self) -> &CustomTypeRegistry {
         &self.custom_types
     }
@@ -116,6 +704,31 @@ impl Completer {
         self
     }
 
+    /// Register a custom type with snippet and documentation support and its member access style
+    ///
+    /// # Example
+    /// ```
+    /// use egui_code_editor::completer::custom_types::{CompType, SyntaxStyle};
+    /// use egui_code_editor::{Completer, Syntax};
+    ///
+    /// let completer = Completer::new_with_syntax(&Syntax::rust())
+    ///     .with_custom_type_snippets_docs_and_style(
+    ///         "Vec",
+    ///         vec![("new", "new()", "Constructs a new, empty Vec", CompType::Function)],
+    ///         SyntaxStyle::Separator("::"),
+    ///     );
+    /// ```
+    pub fn with_custom_type_snippets_docs_and_style(
+        mut self,
+        type_name: impl Into<String>,
+        methods: Vec<(&str, &str, &str, CompType)>,
+        syntax_style: custom_types::SyntaxStyle,
+    ) -> Self {
+        self.custom_types
+            .register_type_with_snippets_and_style(type_name, methods, syntax_style);
+        self
+    }
+
     /// Register a custom type with only snippets (no docs)
     ///
     /// # Example
@@ -184,6 +797,12 @@ impl Completer {
         self
     }
 
+    /// Register a global from a complete item, like one with [`CompletionItem::with_additional_edit`]
+    pub fn with_global_item(mut self, item: CompletionItem) -> Self {
+        self.custom_types.register_global_item(item);
+        self
+    }
+
     /// Register a simple global (no snippet, no docs)
     pub fn with_global_simple(mut self, name: impl Into<String>, comp_type: CompType) -> Self {
         self.custom_types.register_global_simple(name, comp_type);
@@ -232,6 +851,38 @@ impl Completer {
         self.custom_types.register_type_simple(type_name, item);
     }
 
+    /// Characters that belong to identifiers in addition to alphanumerics and `_`,
+    /// like `$` of shell variables or `-` of CSS custom properties.
+    ///
+    /// **Default: none**
+    pub fn with_word_chars(mut self, word_chars: &[char]) -> Self {
+        self.custom_types.set_word_chars(word_chars);
+        self
+    }
+
+    /// Match members of a registered custom type regardless of case, like in SQL.
+    ///
+    /// **Default: false**
+    pub fn with_case_insensitive_members(
+        mut self,
+        type_name: &str,
+        case_insensitive: bool,
+    ) -> Self {
+        self.custom_types
+            .set_case_insensitive(type_name, case_insensitive);
+        self
+    }
+
+    /// Register a custom type whose members are computed from the method prefix on demand,
+    /// every frame the popup is built, so they follow the app state.
+    pub fn register_custom_type_dynamic(
+        &mut self,
+        type_name: impl Into<String>,
+        members: impl Fn(&str) -> Vec<CompletionItem> + Send + Sync + 'static,
+    ) {
+        self.custom_types.register_type_dynamic(type_name, members);
+    }
+
     /// Register a custom type with snippets and docs to an existing completer
     pub fn register_custom_type_snippets_docs(
         &mut self,
@@ -311,157 +962,414 @@ impl Completer {
             .register_global_snippet_docs(name, snippet, documentation, comp_type);
     }
 
-    pub fn push_word(&mut self, word: &str) {
-        self.trie_syntax.push(word);
+    #[cfg(test)]
+    pub(crate) fn user_words(&self, prefix: &str) -> Vec<String> {
+        self.user_words
+            .as_ref()
+            .map(|trie| trie.find_completions(prefix))
+            .unwrap_or_default()
     }
 
-    pub fn handle_input(&mut self, ctx: &egui::Context) {
-        if self.prefix.is_empty() {
-            return;
-        }
+    /// Text before the caret being completed, as of the last [`Self::show`]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
 
-        if let Some(cursor) = self.ignore_cursor
-            && cursor == self.cursor
+    /// Entries listed for [`Self::prefix`] with their display text, in popup order
+    pub fn current_completions(&self) -> &[(String, CompletionItem)] {
+        &self.completions
+    }
+
+    /// Index of the highlighted entry of [`Self::current_completions`]
+    pub fn selected_index(&self) -> usize {
+        self.variant_id
+    }
+
+    /// Header of the docs popup for a completion, the whole display of members like
+    /// `self.move_to` so the type shows, `None` for other completions
+    pub fn docs_header<'a>(&self, display: &'a str) -> Option<&'a str> {
+        self.custom_types
+            .split_member_access(display)
+            .map(|_| display)
+    }
+
+    /// Completion popup was shown in the last frame, so it takes Esc, arrows, Tab and Enter
+    /// in the next one.
+    pub fn is_open(&self) -> bool {
+        !((self.prefix.is_empty() && !self.forced) || self.completions.is_empty())
+            && self.ignore_cursor != Some(self.cursor)
+    }
+
+    /// Forget the prefix, cursor and completions of the current document,
+    /// e.g. when opening another one. Registered types, globals, snippets and options are kept.
+    pub fn reset(&mut self) {
+        self.prefix.clear();
+        self.prefix_range = (0, 0);
+        self.cursor = 0;
+        self.ignore_cursor = None;
+        self.refocus = false;
+        self.variant_id = 0;
+        self.completions.clear();
+        self.built_for = None;
+        self.accepted = None;
+        self.applied = None;
+        self.retrigger = false;
+        self.sections.clear();
+        self.trailing = 0;
+        self.forced = false;
+        self.snippet_stops.clear();
+        self.tabstops.clear();
+        self.jumped = false;
+        #[cfg(feature = "path_completion")]
         {
-            return;
+            self.path_dir = None;
         }
+    }
 
-        // Get completions from trie (these return just suffixes)
-        let completions_syntax = self.trie_syntax.find_completions(&self.prefix);
-        let completions_user = self
-            .trie_user
-            .as_ref()
-            .map(|t| t.find_completions(&self.prefix))
-            .unwrap_or_default();
+    pub fn push_word(&mut self, word: &str) {
+        self.trie_syntax.push_word(word);
+    }
 
-        // Convert trie completions to full words
-        let trie_items: Vec<(String, CompletionItem)> = completions_syntax
-            .into_iter()
-            .chain(completions_user)
-            .map(|suffix| {
-                let full_word = format!("{}{}", self.prefix, suffix);
-                (
-                    full_word.clone(),
-                    CompletionItem::new(full_word, CompType::Global),
-                )
+    /// Syntax words of one category starting with `prefix`:
+    /// [`TokenType::Keyword`], [`TokenType::Type`], [`TokenType::Special`],
+    /// or [`TokenType::Literal`] for words added with [`Self::push_word`]
+    pub fn syntax_completions(&self, ty: TokenType, prefix: &str) -> Vec<String> {
+        self.trie_syntax
+            .category(ty)
+            .map(|trie| {
+                trie.find_completions(prefix)
+                    .into_iter()
+                    .map(|suffix| format!("{prefix}{suffix}"))
+                    .collect()
             })
-            .collect();
+            .unwrap_or_default()
+    }
+
+    /// Token type coloring the completion in the popup
+    pub(crate) fn token_type(
+        &self,
+        syntax: &Syntax,
+        display: &str,
+        item: &CompletionItem,
+    ) -> TokenType {
+        if item.comp_type == CompType::Constant {
+            TokenType::Special
+        } else if self.custom_types.split_member_access(display).is_some() {
+            TokenType::Function
+        } else if syntax.is_keyword(display) {
+            TokenType::Keyword
+        } else if syntax.is_special(display) {
+            TokenType::Special
+        } else if syntax.is_type(display) {
+            TokenType::Type
+        } else {
+            TokenType::Literal
+        }
+    }
+
+    /// Directory entries when a path is typed in a string
+    #[cfg(feature = "path_completion")]
+    fn path_completions(&self) -> Option<Vec<(String, CompletionItem)>> {
+        let source = self.path_source.as_ref()?;
+        Some(source.entries(self.path_dir.as_ref()?, &self.prefix))
+    }
+
+    #[cfg(not(feature = "path_completion"))]
+    fn path_completions(&self) -> Option<Vec<(String, CompletionItem)>> {
+        None
+    }
 
-        // Get custom type completions (these already return full items)
-        let custom_items = self.custom_types.get_completions(&self.prefix);
+    /// Prefix with the generations of the registry and the pushed words,
+    /// so registering anything rebuilds the list
+    fn build_key(&self) -> (String, u64, u64) {
+        let generations = (self.custom_types.generation(), self.trie_syntax.generation);
+        (self.prefix.clone(), generations.0, generations.1)
+    }
+
+    /// Completions were built for the prefix and nothing was registered since
+    fn is_built(&self) -> bool {
+        self.built_for
+            .as_ref()
+            .is_some_and(|(prefix, types, words)| {
+                *prefix == self.prefix
+                    && *types == self.custom_types.generation()
+                    && *words == self.trie_syntax.generation
+            })
+    }
 
-        // Combine and deduplicate
-        let mut all_completions: BTreeSet<String> = BTreeSet::new();
-        let mut completion_map: std::collections::HashMap<String, CompletionItem> =
-            std::collections::HashMap::new();
+    /// Gathers, filters, deduplicates and ranks the completions of the prefix from scratch.
+    fn build_completions(&mut self) {
+        let candidates = self.path_completions().unwrap_or_else(|| {
+            // Get completions from trie (these return just suffixes)
+            let completions_syntax = self.trie_syntax.find_completions(&self.prefix);
+            let completions_user = self
+                .user_words
+                .as_ref()
+                .map(|t| t.find_completions(&self.prefix))
+                .unwrap_or_default();
+
+            // Convert trie completions to full words
+            let trie_items: Vec<(String, CompletionItem)> = completions_syntax
+                .into_iter()
+                .chain(completions_user)
+                .map(|suffix| {
+                    let full_word = format!("{}{}", self.prefix, suffix);
+                    (
+                        full_word.clone(),
+                        CompletionItem::new(full_word, CompType::Global),
+                    )
+                })
+                .collect();
+
+            // Get custom type completions (these already return full items)
+            let custom_items = self.custom_types.get_completions(&self.prefix);
+            trie_items.into_iter().chain(custom_items).collect()
+        });
 
-        for (display, item) in trie_items.into_iter().chain(custom_items) {
-            if all_completions.insert(display.clone()) {
-                completion_map.insert(display, item);
+        // Combine and deduplicate, the first candidate of a display text wins
+        self.completions.clear();
+        let filter = self.filter.as_ref();
+        self.completions.extend(
+            candidates
+                .into_iter()
+                .filter(|(display, item)| filter.is_none_or(|filter| (filter.0)(display, item))),
+        );
+        self.completions.sort_by(|(a, _), (b, _)| a.cmp(b));
+        self.completions.dedup_by(|(a, _), (b, _)| a == b);
+
+        // Exact match, then shorter words, then alphabetical.
+        // Members of custom types go last unless a member is being typed.
+        let member_access = self
+            .custom_types
+            .split_member_access(&self.prefix)
+            .is_some();
+        let prefix = &self.prefix;
+        let custom_types = &self.custom_types;
+        let rank = |display: &String| {
+            (
+                !member_access && custom_types.split_member_access(display).is_some(),
+                display != prefix,
+                display.chars().count(),
+            )
+        };
+        // Stable, so equally ranked ones stay alphabetical
+        self.completions.sort_by_key(|(display, _)| rank(display));
+
+        self.sections.clear();
+        if self.grouped_popup {
+            let completions = std::mem::take(&mut self.completions);
+            for (section, entries) in self.group_completions(completions) {
+                self.sections.push((section, self.completions.len()));
+                self.completions.extend(entries);
             }
         }
+    }
 
-        // Convert to sorted vec
-        self.completions = all_completions
-            .into_iter()
-            .map(|display| {
-                let item = completion_map.remove(&display).unwrap();
-                (display, item)
-            })
-            .collect();
+    /// Takes the keys of the popup, and Tab for the next tabstop of an accepted snippet.
+    ///
+    /// Snippets go to the caret with continuation lines indented like its line.
+    /// The caret lands on `$1`, Tab moves it on to `$2` and so on, then to `$0`.
+    pub fn handle_input(&mut self, ctx: &egui::Context) {
+        self.handle_popup_input(ctx);
+        if !self.tabstops.is_empty()
+            && !self.is_open()
+            && !self.disabled_keys.contains(&egui::Key::Tab)
+            && ctx.input_mut(|i| i.consume_key(Modifiers::NONE, egui::Key::Tab))
+        {
+            let stop = self.tabstops.remove(0);
+            let (key, count) = if stop < self.cursor {
+                (egui::Key::ArrowLeft, self.cursor - stop)
+            } else {
+                (egui::Key::ArrowRight, stop - self.cursor)
+            };
+            ctx.input_mut(|i| i.events.extend((0..count).map(|_| key_press(key))));
+            self.jumped = true;
+        }
+    }
+
+    /// Additional edit of the accepted completion, for the editor to insert before it applies
+    /// the completion: char offset, text and the caret moved along. The queued completion
+    /// is moved to match, and the edit stays queued to be reported once applied.
+    pub(crate) fn additional_edit_first(&mut self) -> Option<(usize, String, usize)> {
+        let (_, replaced, _, additional_edit) = self.accepted.as_mut()?;
+        let (offset, text) = additional_edit.as_mut()?;
+        // Edits before the end of the replaced chars go in front of them, like the report
+        // places them. With nothing replaced that is an edit at the caret too.
+        let before = *offset < replaced.end.max(replaced.start + 1);
+        if !before {
+            return Some((*offset, text.clone(), self.cursor));
+        }
+        *offset = (*offset).min(replaced.start);
+        let len = text.chars().count();
+        *replaced = replaced.start + len..replaced.end + len;
+        Some((*offset, text.clone(), self.cursor + len))
+    }
 
-        if self.completions.is_empty() {
+    /// Moves the tabstops at or after `from` by `delta` chars
+    pub(crate) fn shift_tabstops(&mut self, from: usize, delta: isize) {
+        for stop in self.tabstops.iter_mut().filter(|stop| **stop >= from) {
+            *stop = stop.saturating_add_signed(delta);
+        }
+        self.tabstop_len = self.tabstop_len.saturating_add_signed(delta);
+    }
+
+    fn handle_popup_input(&mut self, ctx: &egui::Context) {
+        if let Some(shortcut) = self.trigger_key.0
+            && self
+                .editor_id
+                .is_some_and(|id| ctx.memory(|m| m.has_focus(id)))
+            && ctx.input_mut(|i| crate::input::consume_exact(&mut i.events, &shortcut))
+        {
+            self.forced = true;
+            self.ignore_cursor = None;
+        }
+        if self.prefix.is_empty() && !self.forced {
+            return;
+        }
+
+        if let Some(cursor) = self.ignore_cursor
+            && cursor == self.cursor
+        {
+            return;
+        }
+
+        // Unchanged prefix keeps the list built for it, moving the cursor clears it.
+        // A filter or dynamic types may follow the app state, so they rebuild it every frame.
+        if self.filter.is_some() || self.custom_types.has_dynamic_types() || !self.is_built() {
+            self.build_completions();
+            self.built_for = Some(self.build_key());
+            self.variant_id = self
+                .variant_id
+                .min(self.completions.len().saturating_sub(1));
+        }
+
+        // Keys stay with the editor unless the popup shows
+        if !self.is_open() {
             return;
         }
 
         let last = self.completions.len().saturating_sub(1);
+        let disabled_keys = &self.disabled_keys;
+        let consume_key = |i: &mut egui::InputState, key| {
+            !disabled_keys.contains(&key) && i.consume_key(Modifiers::NONE, key)
+        };
         ctx.input_mut(|i| {
-            if i.consume_key(Modifiers::NONE, egui::Key::Escape) {
+            if consume_key(i, egui::Key::Escape) {
                 self.ignore_cursor = Some(self.cursor);
-            } else if i.consume_key(Modifiers::NONE, egui::Key::ArrowDown) {
+                self.refocus = true;
+            } else if consume_key(i, egui::Key::ArrowDown) {
                 self.variant_id = if self.variant_id == last {
                     0
                 } else {
                     self.variant_id.saturating_add(1).min(last)
                 };
-            } else if i.consume_key(Modifiers::NONE, egui::Key::ArrowUp) {
+            } else if consume_key(i, egui::Key::ArrowUp) {
                 self.variant_id = if self.variant_id == 0 {
                     last
                 } else {
                     self.variant_id.saturating_sub(1)
                 };
-            } else if i.consume_key(Modifiers::NONE, egui::Key::Tab)
-                || i.consume_key(Modifiers::NONE, egui::Key::Enter)
+            } else if consume_key(i, egui::Key::PageDown) {
+                self.variant_id = (self.variant_id + VISIBLE_ROWS).min(last);
+            } else if consume_key(i, egui::Key::PageUp) {
+                self.variant_id = self.variant_id.saturating_sub(VISIBLE_ROWS);
+            } else if consume_key(i, egui::Key::Home) {
+                self.variant_id = 0;
+            } else if consume_key(i, egui::Key::End) {
+                self.variant_id = last;
+            } else if (consume_key(i, egui::Key::Tab) || consume_key(i, egui::Key::Enter))
+                && let Some((display, item)) = self.completions.get(self.variant_id)
             {
-                if let Some((display, item)) = self.completions.get(self.variant_id) {
-                    // Determine what to delete and what to insert
-                    // Check for both dot and colon separators
-                    let separator_split = display
-                        .rsplit_once('.')
-                        .or_else(|| display.rsplit_once(':'));
-
-                    let (delete_count, insert_text) =
-                        if let Some((type_part, _method_part)) = separator_split {
-                            let prefix_split = self
-                                .prefix
-                                .rsplit_once('.')
-                                .or_else(|| self.prefix.rsplit_once(':'));
-
-                            if let Some((prefix_type, prefix_method)) = prefix_split {
-                                // Delete only the partial method part after the separator
-                                let delete = prefix_method.len();
-                                let insert = if item.snippet.is_some() {
-                                    item.insert_text().to_string()
-                                } else {
-                                    _method_part.to_string()
-                                };
-                                (delete, insert)
+                // Determine what to delete and what to insert
+                // Check for both dot and colon separators
+                let separator_split = self.custom_types.split_member_access(display);
+
+                let (delete_count, insert_text) =
+                    if let Some((_type_part, method_part)) = separator_split {
+                        let prefix_split = self.custom_types.split_member_access(&self.prefix);
+
+                        if let Some((_prefix_type, prefix_method)) = prefix_split {
+                            // Delete only the partial method part after the separator
+                            let delete = prefix_method.chars().count();
+                            let insert = if item.snippet.is_some() {
+                                item.insert_text().to_string()
                             } else {
-                                // Shouldn't happen, but fallback to replacing everything
-                                let delete = self.prefix_range.1 - self.prefix_range.0;
-                                (delete, display.clone())
-                            }
+                                method_part.to_string()
+                            };
+                            (delete, insert)
                         } else {
-                            // Regular completion (no separator), replace the entire prefix
+                            // Shouldn't happen, but fallback to replacing everything
                             let delete = self.prefix_range.1 - self.prefix_range.0;
-                            let insert = item.insert_text().to_string();
-                            (delete, insert)
-                        };
-
-                    // Calculate cursor offset if there's a $ marker
-                    let (final_text, cursor_offset) = if insert_text.contains('$') {
-                        let pos = insert_text.find('$').unwrap();
-                        (insert_text.replace('$', ""), Some(pos))
+                            (delete, display.clone())
+                        }
                     } else {
-                        (insert_text, None)
+                        // Regular completion (no separator), replace the entire prefix
+                        let delete = self.prefix_range.1 - self.prefix_range.0;
+                        let insert = item.insert_text().to_string();
+                        (delete, insert)
                     };
 
-                    // Delete the partial text, then insert the completion
-                    for _ in 0..delete_count {
+                // Continuation lines start at the indent of the caret line
+                let insert_text = insert_text.replace('\n', &format!("\n{}", self.indent));
+                // Calculate cursor offset if there's a $ marker
+                let (final_text, stops) = snippet_tabstops(&insert_text);
+                let cursor_offset = stops.first().copied();
+                self.snippet_stops = stops
+                    .iter()
+                    .skip(1)
+                    .map(|stop| final_text[..*stop].chars().count())
+                    .collect();
+
+                // Delete the partial text, then insert the completion
+                for _ in 0..delete_count {
+                    i.events.push(Event::Key {
+                        key: egui::Key::Backspace,
+                        physical_key: None,
+                        pressed: true,
+                        repeat: false,
+                        modifiers: Modifiers::NONE,
+                    });
+                }
+                for _ in 0..self.trailing {
+                    i.events.push(Event::Key {
+                        key: egui::Key::Delete,
+                        physical_key: None,
+                        pressed: true,
+                        repeat: false,
+                        modifiers: Modifiers::NONE,
+                    });
+                }
+
+                i.events.push(Event::Paste(final_text.clone()));
+
+                // If there's a cursor position, move back to it
+                if let Some(offset) = cursor_offset {
+                    let move_back = final_text[offset..].chars().count();
+                    for _ in 0..move_back {
                         i.events.push(Event::Key {
-                            key: egui::Key::Backspace,
+                            key: egui::Key::ArrowLeft,
                             physical_key: None,
                             pressed: true,
                             repeat: false,
                             modifiers: Modifiers::NONE,
                         });
                     }
+                }
 
-                    i.events.push(Event::Paste(final_text.clone()));
-
-                    // If there's a cursor position, move back to it
-                    if let Some(offset) = cursor_offset {
-                        let move_back = final_text.len() - offset;
-                        for _ in 0..move_back {
-                            i.events.push(Event::Key {
-                                key: egui::Key::ArrowLeft,
-                                physical_key: None,
-                                pressed: true,
-                                repeat: false,
-                                modifiers: Modifiers::NONE,
-                            });
-                        }
-                    }
+                let replaced =
+                    self.cursor.saturating_sub(delete_count)..self.cursor + self.trailing;
+                self.accepted = Some((
+                    display.clone(),
+                    replaced,
+                    final_text,
+                    item.additional_edit.clone(),
+                ));
+                self.retrigger = item.retrigger_on_accept;
+
+                if let Some(hook) = &self.on_accept
+                    && let Ok(mut hook) = hook.0.lock()
+                {
+                    hook(display, item);
                 }
             }
         });
@@ -475,7 +1383,67 @@ impl Completer {
         fontsize: f32,
         editor_output: &mut TextEditOutput,
     ) {
+        let font_id = egui::FontId::monospace(fontsize);
+        self.show_with_font(syntax, theme, font_id, editor_output);
+    }
+
+    /// [`Self::show`] with the completion entries in the font of the editor,
+    /// like [`CodeEditor::font_id`]
+    pub fn show_with_font(
+        &mut self,
+        syntax: &Syntax,
+        theme: &ColorTheme,
+        font_id: egui::FontId,
+        editor_output: &mut TextEditOutput,
+    ) {
+        let fontsize = font_id.size;
+        // Events of the accepted completion were applied by the editor before this
+        let text = editor_output.galley.text();
+        self.applied =
+            self.accepted
+                .take()
+                .and_then(|(display, replaced, inserted_text, additional_edit)| {
+                    let start = replaced.start;
+                    let inserted = inserted_text.chars().count();
+                    // A read-only editor drops the edit events, nothing to report then
+                    let applied = text.chars().count() >= start + inserted
+                        && text.char_range(start..start + inserted) == inserted_text;
+                    applied.then(|| {
+                        // Offsets after the replaced chars move by the size change of the edit
+                        let additional_edit = additional_edit.map(|(offset, text)| {
+                            let offset = if offset >= replaced.end {
+                                offset + inserted - replaced.len()
+                            } else {
+                                offset.min(start)
+                            };
+                            (offset, text)
+                        });
+                        AppliedCompletion {
+                            display,
+                            replaced,
+                            inserted: start..start + inserted,
+                            text: editor_output.galley.text().to_owned(),
+                            additional_edit,
+                        }
+                    })
+                });
+        if let Some(applied) = &self.applied {
+            let start = applied.inserted.start;
+            self.tabstops = self.snippet_stops.drain(..).map(|s| start + s).collect();
+            self.tabstop_len = applied.text.chars().count();
+        } else if editor_output.response.changed() && !self.tabstops.is_empty() {
+            // Typing at a tabstop moves the ones after it
+            let len = editor_output.galley.text().chars().count();
+            self.shift_tabstops(self.cursor, len as isize - self.tabstop_len as isize);
+        }
+        self.editor_id = Some(editor_output.response.id);
+        let editor_tokens = self.editor_tokens.take();
+        if std::mem::take(&mut self.refocus) {
+            editor_output.response.request_focus();
+        }
         if !editor_output.response.has_focus() {
+            self.token_at_caret = None;
+            self.tabstops.clear();
             return;
         }
 
@@ -483,14 +1451,28 @@ impl Completer {
         let galley = &editor_output.galley;
 
         if editor_output.response.changed() {
-            // Update Completer Dictionary
-            if let Some(trie_user) = self.trie_user.as_mut() {
-                trie_user.clear();
-                Token::default()
-                    .tokens(syntax, galley.text())
-                    .iter()
-                    .filter(|t| matches!(t.ty(), TokenType::Literal | TokenType::Function))
-                    .for_each(|t| trie_user.push(t.buffer()));
+            self.user_word_changes += 1;
+        }
+        // Update Completer Dictionary
+        if self.user_words.is_some() && self.user_word_changes > 0 {
+            let now = ctx.input(|i| i.time);
+            let due = match self.user_word_refresh {
+                UserWordRefresh::EveryChange => true,
+                UserWordRefresh::Interval(interval) => {
+                    let elapsed = self.user_word_time.map_or(f64::INFINITY, |t| now - t);
+                    let remaining = interval.as_secs_f64() - elapsed;
+                    if remaining > 0.0 {
+                        ctx.request_repaint_after_secs(remaining as f32);
+                    }
+                    remaining <= 0.0
+                }
+                UserWordRefresh::Changes(changes) => self.user_word_changes >= changes,
+            };
+            if due && let Some(user_words) = self.user_words.as_mut() {
+                user_words.update(syntax, galley.text());
+                self.built_for = None;
+                self.user_word_changes = 0;
+                self.user_word_time = Some(now);
             }
         }
 
@@ -499,14 +1481,31 @@ impl Completer {
         if let Some(range) = cursor_range {
             let cursor = range.primary;
             let cursor_pos_in_galley = galley.pos_from_cursor(cursor);
-            let cursor_rect =
-                cursor_pos_in_galley.translate(editor_output.response.rect.left_top().to_vec2());
+            // Galley position includes the margin of framed widgets like a plain TextEdit
+            let cursor_rect = cursor_pos_in_galley.translate(editor_output.galley_pos.to_vec2());
 
+            if self.cursor != cursor.index
+                || editor_output.response.changed()
+                || self.token_at_caret.is_none()
+            {
+                // Tokens of the editor highlighting, not lexed again
+                self.token_at_caret = match editor_tokens {
+                    Some(tokens) => lexed_type_at(&tokens, cursor.index),
+                    None => token_at(syntax, galley.text(), cursor.index),
+                };
+            }
+            let jumped = std::mem::take(&mut self.jumped);
             if self.cursor != cursor.index {
+                // Moving the caret away from the snippet leaves Tab to the editor
+                if !jumped && !editor_output.response.changed() {
+                    self.tabstops.clear();
+                }
                 self.cursor = cursor.index;
                 self.prefix.clear();
                 self.completions.clear();
+                self.built_for = None;
                 self.ignore_cursor = None;
+                self.forced = false;
                 self.variant_id = 0;
             }
 
@@ -517,53 +1516,95 @@ impl Completer {
                 self.ignore_cursor = None;
             }
 
-            let next_char_allows = galley
-                .chars()
-                .nth(cursor.index)
-                .is_none_or(|c| !(c.is_alphanumeric() || c == '_'))
+            let next_char_allows = self.replace_trailing
+                || galley
+                    .chars()
+                    .nth(cursor.index)
+                    .is_none_or(|c| !self.custom_types.is_word_char(c))
                 || (range.secondary.index > range.primary.index);
+            self.trailing = if self.replace_trailing && range.is_empty() {
+                galley
+                    .chars()
+                    .skip(cursor.index)
+                    .take_while(|c| self.custom_types.is_word_char(*c))
+                    .count()
+            } else {
+                0
+            };
+
+            let suppressed = self.token_at_caret.is_some_and(|ty| {
+                let kind = std::mem::discriminant(&ty);
+                self.suppress_in
+                    .iter()
+                    .any(|t| std::mem::discriminant(t) == kind)
+            });
 
             // Enhanced prefix extraction that handles member access
-            if next_char_allows {
+            if next_char_allows && !suppressed {
                 let text = galley.text();
                 let text_before_cursor = text.char_range(0..cursor.index);
 
                 // Find the start of the current completion context
-                // Include ':' as a valid separator only if any registered type uses colon syntax
-                let has_colon_syntax = self.custom_types.has_colon_syntax();
-
-                let context_start = text_before_cursor
-                    .rfind(|c: char| {
-                        !c.is_alphanumeric()
-                            && c != '_'
-                            && c != '.'
-                            && !(has_colon_syntax && c == ':')
-                    })
-                    .map(|pos| pos + 1)
-                    .unwrap_or(0);
-
-                // Find the first valid char boundary at or before context_start
-                let safe_start = text_before_cursor
-                    .char_indices()
-                    .take_while(|(i, _)| *i <= context_start)
-                    .last()
-                    .map(|(i, _)| i)
-                    .unwrap_or(0);
-
-                self.prefix = text_before_cursor[safe_start..].to_string();
-                self.prefix_range = (safe_start, cursor.index);
+                // ':' counts as a separator only if any registered type uses colon syntax
+                let context_start = self.custom_types.context_start(text_before_cursor);
+                let line_start = text_before_cursor.rfind('\n').map_or(0, |i| i + 1);
+                let line = &text_before_cursor[line_start..];
+                let blanks = line.len() - line.trim_start_matches([' ', '\t']).len();
+                self.indent = line[..blanks].to_string();
+
+                self.prefix = text_before_cursor[context_start..].to_string();
+                #[cfg(feature = "path_completion")]
+                {
+                    self.path_dir = None;
+                    if self.path_source.is_some()
+                        && let Some((dir, partial)) =
+                            path::path_before_cursor(syntax, text_before_cursor)
+                    {
+                        self.prefix = partial.to_string();
+                        self.path_dir = Some(dir.to_string());
+                    }
+                }
+                self.prefix_range = (cursor.index - self.prefix.chars().count(), cursor.index);
             } else {
                 self.prefix = String::new();
                 self.prefix_range = (cursor.index, cursor.index);
             }
+            // The accepted text is in the galley already, list what follows it in this frame
+            if std::mem::take(&mut self.retrigger) && !self.prefix.is_empty() {
+                self.build_completions();
+                self.built_for = Some(self.build_key());
+            }
+
+            if self.is_open() {
+                if self.prefix_highlight {
+                    let painter = ctx
+                        .layer_painter(editor_output.response.layer_id)
+                        .with_clip_rect(editor_output.text_clip_rect);
+                    let fill = theme.selection().gamma_multiply(0.4);
+                    let visible = editor_output
+                        .text_clip_rect
+                        .translate(-editor_output.galley_pos.to_vec2())
+                        .y_range();
+                    for range in visible_prefix_occurrences(galley, visible, &self.prefix) {
+                        let start = galley.pos_from_cursor(egui::text::CCursor::new(range.start));
+                        let end = galley.pos_from_cursor(egui::text::CCursor::new(range.end));
+                        // Wrapped occurrences are skipped
+                        if start.top() == end.top() {
+                            let rect = start
+                                .union(end)
+                                .translate(editor_output.galley_pos.to_vec2());
+                            painter.rect_filled(rect, 0.0, fill);
+                        }
+                    }
+                }
 
-            if !(self.prefix.is_empty() || self.completions.is_empty()) {
                 let completion_popup_response = egui::Popup::new(
                     egui::Id::new("Completer"),
                     ctx.clone(),
                     cursor_rect,
                     editor_output.response.layer_id,
                 )
+                .gap(self.popup_gap().y)
                 .frame(Frame::popup(&ctx.style()).fill(theme.bg()))
                 .sense(Sense::empty())
                 .show(|ui| {
@@ -573,45 +1614,41 @@ impl Completer {
                         + ui.style().visuals.widgets.hovered.bg_stroke.width * 2.0
                         + ui.style().spacing.button_padding.y * 2.0
                         + ui.style().spacing.item_spacing.y)
-                        * self.completions.len().min(10) as f32
+                        * (self.completions.len() + self.sections.len()).min(VISIBLE_ROWS) as f32
                         - ui.style().spacing.item_spacing.y;
                     ui.set_height(height);
                     egui::ScrollArea::vertical()
                         .auto_shrink([true, true])
                         .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
                         .show(ui, |ui| {
-                            for (i, (display, _item)) in self.completions.iter().enumerate() {
-                                // Determine token type for coloring
-                                let token_type = if display.contains('.') {
-                                    TokenType::Function
-                                } else if syntax.is_keyword(display) {
-                                    TokenType::Keyword
-                                } else if syntax.is_special(display) {
-                                    TokenType::Special
-                                } else if syntax.is_type(display) {
-                                    TokenType::Type
-                                } else {
-                                    TokenType::Literal
-                                };
-
-                                let fmt = format_token(theme, fontsize, token_type, None);
+                            for (i, (display, item)) in self.completions.iter().enumerate() {
+                                if let Some((section, _)) =
+                                    self.sections.iter().find(|(_, start)| *start == i)
+                                {
+                                    let color = theme.type_color(TokenType::Comment(false));
+                                    ui.label(
+                                        egui::RichText::new(section.label())
+                                            .size(fontsize * 0.85)
+                                            .color(color),
+                                    );
+                                }
+                                let token_type = self.token_type(syntax, display, item);
+
+                                let mut fmt = format_token(theme, fontsize, token_type, None);
+                                fmt.font_id = font_id.clone();
+                                let label = self.display_label(display).into_owned();
                                 let colored_text =
-                                    egui::text::LayoutJob::single_section(display.clone(), fmt);
+                                    egui::text::LayoutJob::single_section(label, fmt);
 
                                 let selected = i == self.variant_id;
+                                let width = ui.style().visuals.widgets.hovered.bg_stroke.width;
+                                let highlight = self.selection_highlight;
                                 let button = ui.add(
                                     egui::Button::new(colored_text)
                                         .sense(Sense::empty())
                                         .frame(true)
-                                        .fill(theme.bg())
-                                        .stroke(if selected {
-                                            Stroke::new(
-                                                ui.style().visuals.widgets.hovered.bg_stroke.width,
-                                                theme.type_color(TokenType::Literal),
-                                            )
-                                        } else {
-                                            Stroke::NONE
-                                        }),
+                                        .fill(highlight.fill(theme, selected))
+                                        .stroke(highlight.stroke(theme, selected, width)),
                                 );
 
                                 if selected {
@@ -619,50 +1656,75 @@ impl Completer {
                                 }
                             }
                         });
-                    // Return the popup rect for positioning the docs popup
-                    ui.min_rect()
                 });
 
                 // Show documentation popup to the right of the completion list
                 if let Some(popup_response) = completion_popup_response {
-                    let popup_rect = popup_response.inner;
-                    if let Some((_display, item)) = self.completions.get(self.variant_id) {
-                        if let Some(docs) = &item.documentation {
-                            // Position docs popup to the right of completion popup
-                            let docs_rect = egui::Rect::from_min_size(
-                                egui::pos2(popup_rect.right() + 5.0, popup_rect.top()),
-                                egui::vec2(1.0, 1.0), // Will auto-size
-                            );
-
-                            egui::Popup::new(
-                                egui::Id::new("Completer_Docs"),
-                                ctx.clone(),
-                                docs_rect,
-                                editor_output.response.layer_id,
-                            )
-                            .frame(Frame::popup(&ctx.style()).fill(theme.bg()))
-                            .sense(Sense::empty())
-                            .show(|ui| {
-                                ui.response().sense = Sense::empty();
-                                ui.set_max_width(300.0);
-                                ui.set_max_height(400.0);
-
-                                egui::ScrollArea::vertical()
-                                    .auto_shrink([false, true])
-                                    .show(ui, |ui| {
-                                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
-
-                                        let mut editor = CodeEditor::default()
-                                            .readonly(true)
-                                            .with_fontsize(14.0)
-                                            .with_theme(theme.clone())
-                                            .with_syntax(syntax.to_owned())
-                                            .with_numlines(false);
-
-                                        editor.show(ui, &mut docs.clone());
-                                    });
-                            });
-                        }
+                    // Outer rect, so the gap is measured between the frames
+                    let popup_rect = popup_response.response.rect;
+                    let selected = self.completions.get(self.variant_id);
+                    let header = selected.and_then(|(display, _)| self.docs_header(display));
+                    let preview = selected.and_then(|(_, item)| item.snippet_preview());
+                    let docs = selected.and_then(|(_, item)| item.documentation.as_ref());
+                    if preview.is_some() || docs.is_some() {
+                        // Position docs popup to the right of completion popup
+                        let docs_rect = egui::Rect::from_min_size(
+                            egui::pos2(popup_rect.right() + self.popup_gap().x, popup_rect.top()),
+                            egui::vec2(1.0, 1.0), // Will auto-size
+                        );
+
+                        egui::Popup::new(
+                            egui::Id::new("Completer_Docs"),
+                            ctx.clone(),
+                            docs_rect,
+                            editor_output.response.layer_id,
+                        )
+                        .frame(Frame::popup(&ctx.style()).fill(theme.bg()))
+                        .sense(Sense::empty())
+                        .show(|ui| {
+                            ui.response().sense = Sense::empty();
+                            ui.set_max_size(self.docs_size());
+
+                            egui::ScrollArea::vertical()
+                                .auto_shrink([false, true])
+                                .show(ui, |ui| {
+                                    ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
+
+                                    let editor = CodeEditor::default()
+                                        .with_fontsize(14.0)
+                                        .with_theme(*theme)
+                                        .with_syntax(syntax.to_owned())
+                                        .with_word_wrap(true);
+
+                                    // Member with its type, highlighted as code
+                                    if let Some(header) = header {
+                                        let galley = editor.layout_only(ui, header);
+                                        ui.add(egui::Label::new(galley));
+                                        ui.separator();
+                                    }
+
+                                    // What the snippet inserts, above the docs
+                                    if let Some(preview) = &preview {
+                                        Frame::new()
+                                            .fill(
+                                                theme
+                                                    .type_color(TokenType::Comment(false))
+                                                    .gamma_multiply(0.1),
+                                            )
+                                            .inner_margin(4.0)
+                                            .show(ui, |ui| {
+                                                let galley = editor.layout_only(ui, preview);
+                                                ui.add(egui::Label::new(galley));
+                                            });
+                                        if docs.is_some() {
+                                            ui.separator();
+                                        }
+                                    }
+                                    if let Some(docs) = docs {
+                                        ui.add(egui::Label::new(editor.layout_only(ui, docs)));
+                                    }
+                                });
+                        });
                     }
                 }
             }
@@ -670,6 +1732,13 @@ impl Completer {
     }
 
     /// Completer on text-editing widget, see demo for example
+    ///
+    /// Works with a plain [`egui::TextEdit`] without [`CodeEditor`]: `widget` should show it
+    /// and return its [`TextEditOutput`], whose galley holds the whole buffer, e.g.
+    /// `|ui| TextEdit::multiline(&mut text).lock_focus(true).show(ui)`. Without the focus lock
+    /// Tab moves the focus away instead of accepting. Accepted completions are applied by the
+    /// widget on the next frame, [`Self::applied`] reports them and any
+    /// [`AppliedCompletion::additional_edit`] left for the caller to insert.
     pub fn show_on_text_widget(
         &mut self,
         ui: &mut egui::Ui,
@@ -0,0 +1,78 @@
+use super::custom_types::{CompType, CompletionItem};
+use crate::{Syntax, Token, TokenType};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Completes file paths typed inside string literals with the entries of directories
+/// relative to `root`. Absolute paths and paths going up with `..` are not looked up,
+/// so only entries inside `root` are offered.
+pub struct PathCompletionSource {
+    root: PathBuf,
+}
+
+impl PathCompletionSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        PathCompletionSource { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Entries of `dir` starting with `partial`, directories end with `/`.
+    /// Hidden entries show only once `partial` starts with a dot.
+    pub fn entries(&self, dir: &str, partial: &str) -> Vec<(String, CompletionItem)> {
+        let inside_root = Path::new(dir)
+            .components()
+            .all(|part| matches!(part, Component::Normal(_) | Component::CurDir));
+        if !inside_root {
+            return vec![];
+        }
+        let Ok(read_dir) = std::fs::read_dir(self.root.join(dir)) else {
+            return vec![];
+        };
+        read_dir
+            .flatten()
+            .filter_map(|entry| {
+                let mut name = entry.file_name().into_string().ok()?;
+                if !name.starts_with(partial) || (name.starts_with('.') && partial.is_empty()) {
+                    return None;
+                }
+                let comp_type = if entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+                    name.push('/');
+                    CompType::Field
+                } else {
+                    CompType::Global
+                };
+                let item = CompletionItem::new(name.clone(), comp_type);
+                Some((name, item))
+            })
+            .collect()
+    }
+}
+
+/// Directory and partial entry name of a path typed in a string literal right before the cursor,
+/// like `("assets/", "im")` for `load("assets/im`. The string has to contain a `/`
+/// or start with a dot to look like a path.
+pub fn path_before_cursor<'a>(
+    syntax: &Syntax,
+    text_before_cursor: &'a str,
+) -> Option<(&'a str, &'a str)> {
+    let line_start = text_before_cursor.rfind('\n').map_or(0, |i| i + 1);
+    let line = &text_before_cursor[line_start..];
+    let last = Token::default().tokens(syntax, line).pop()?;
+    let TokenType::Str(quote) = last.ty() else {
+        return None;
+    };
+    let buffer = last.buffer();
+    if buffer.len() > quote.len_utf8() && buffer.ends_with(quote) {
+        // Closed before the cursor
+        return None;
+    }
+    let path = &line[line.len() - buffer.len() + quote.len_utf8()..];
+    if !(path.contains('/') || path.starts_with('.')) {
+        return None;
+    }
+    let split = path.rfind('/').map_or(0, |i| i + 1);
+    Some((&path[..split], &path[split..]))
+}
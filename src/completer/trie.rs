@@ -61,6 +61,26 @@ impl Trie {
         }
     }
 
+    /// Unmarks the word and prunes branches left without words.
+    /// Returns false when the word was not in the trie.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.remove_chars(&mut word.chars())
+    }
+
+    fn remove_chars(&mut self, word: &mut Chars) -> bool {
+        let Some(first) = word.next() else {
+            return std::mem::take(&mut self.is_word);
+        };
+        let Some(i) = self.leaves.iter().position(|l| l.root == first) else {
+            return false;
+        };
+        let removed = self.leaves[i].remove_chars(word);
+        if !self.leaves[i].is_word && self.leaves[i].leaves.is_empty() {
+            self.leaves.remove(i);
+        }
+        removed
+    }
+
     pub fn from_words(words: &[&str]) -> Self {
         let mut trie = Trie::new(ROOT_CHAR);
         words.iter().for_each(|w| {
@@ -88,6 +108,11 @@ impl Trie {
         }
     }
 
+    /// The whole word was pushed, not only as a prefix of another one
+    pub fn contains(&self, word: &str) -> bool {
+        self.find_by_prefix(word).is_some_and(|t| t.is_word)
+    }
+
     pub fn find_completions(&self, prefix: &str) -> Vec<String> {
         self.find_by_prefix(prefix)
             .map(|t| t.words())
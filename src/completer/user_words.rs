@@ -0,0 +1,91 @@
+use super::trie::Trie;
+use crate::{Syntax, Token, TokenType};
+use std::collections::HashMap;
+
+#[derive(Default, Debug, Clone, PartialEq)]
+/// Identifiers learned from the document, kept per line so that an edit
+/// only re-lexes the lines that differ from the last update.
+pub(crate) struct UserWords {
+    trie: Trie,
+    lines: Vec<String>,
+    line_words: Vec<Vec<String>>,
+    counts: HashMap<String, usize>, // Occurrences in the document, the word leaves the trie at zero
+    states: Vec<Token>,             // Lexer at the start of each line
+}
+
+impl UserWords {
+    pub fn find_completions(&self, prefix: &str) -> Vec<String> {
+        self.trie.find_completions(prefix)
+    }
+
+    /// Brings the words up to date with `text`, re-lexing only the edited lines and the ones
+    /// after them whose start moved in or out of a multiline comment or string
+    pub fn update(&mut self, syntax: &Syntax, text: &str) {
+        let new_lines: Vec<&str> = text.split('\n').collect();
+        let start = self
+            .lines
+            .iter()
+            .zip(&new_lines)
+            .take_while(|(old, new)| old == *new)
+            .count();
+        let max_common = self.lines.len().min(new_lines.len()) - start;
+        let common_end = self
+            .lines
+            .iter()
+            .rev()
+            .zip(new_lines.iter().rev())
+            .take(max_common)
+            .take_while(|(old, new)| old == *new)
+            .count();
+        let mut old_end = self.lines.len() - common_end;
+        let mut new_end = new_lines.len() - common_end;
+
+        let mut lexer = self.states.get(start).cloned().unwrap_or_default();
+        let mut states = vec![];
+        let mut line_words: Vec<Vec<String>> = vec![];
+        for (i, line) in new_lines.iter().enumerate().skip(start) {
+            // Unchanged lines after the edit keep their words once they start in the same state
+            if i >= new_end {
+                if self.states.get(old_end) == Some(&lexer) {
+                    break;
+                }
+                old_end += 1;
+                new_end += 1;
+            }
+            states.push(lexer.clone());
+            let mut tokens = lexer.tokens_continued(syntax, line);
+            tokens.extend(lexer.tokens_continued(syntax, "\n"));
+            line_words.push(
+                tokens
+                    .into_iter()
+                    .filter(|t| matches!(t.ty(), TokenType::Literal | TokenType::Function))
+                    .map(|t| t.buffer().to_string())
+                    .collect(),
+            );
+        }
+
+        for word in self.line_words[start..old_end].iter().flatten() {
+            if let Some(count) = self.counts.get_mut(word) {
+                *count -= 1;
+                if *count == 0 {
+                    self.counts.remove(word);
+                    self.trie.remove(word);
+                }
+            }
+        }
+        for word in line_words.iter().flatten() {
+            let count = self.counts.entry(word.clone()).or_default();
+            if *count == 0 {
+                self.trie.push(word);
+            }
+            *count += 1;
+        }
+
+        self.lines.splice(
+            start..old_end,
+            new_lines[start..new_end].iter().map(|l| l.to_string()),
+        );
+        self.line_words.splice(start..old_end, line_words);
+        self.states.splice(start..old_end, states);
+    }
+}
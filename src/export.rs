@@ -0,0 +1,57 @@
+use crate::{ColorTheme, Syntax, Token, TokenType, coalesce_tokens};
+use std::fmt::Write;
+use std::ops::Range;
+
+fn escape(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Highlighted `text` as a `<pre>` block with inline styles.
+pub fn html(text: &str, syntax: &Syntax, theme: &ColorTheme) -> String {
+    html_range(text, 0..text.len(), syntax, theme)
+}
+
+/// Highlighted part of `text` in the byte `range` as a `<pre>` block with inline styles.
+/// The whole text is lexed, so a selection starting inside a comment or string keeps its colors.
+pub fn html_range(text: &str, range: Range<usize>, syntax: &Syntax, theme: &ColorTheme) -> String {
+    let mut html = format!(
+        "<pre style=\"background-color:{};color:{}\">",
+        theme.bg,
+        theme.type_color_str(TokenType::Literal)
+    );
+    let mut offset = 0;
+    for token in coalesce_tokens(Token::default().tokens(syntax, text)) {
+        let start = offset;
+        offset += token.buffer().len();
+        let (start, end) = (start.max(range.start), offset.min(range.end));
+        if start >= end {
+            continue;
+        }
+        let part = &text[start..end];
+        if let TokenType::Whitespace(_) = token.ty() {
+            escape(part, &mut html);
+            continue;
+        }
+        let _ = write!(
+            html,
+            "<span style=\"color:{}",
+            theme.type_color_str(token.ty())
+        );
+        if let Some(background) = theme.type_background_str(token.ty()) {
+            let _ = write!(html, ";background-color:{background}");
+        }
+        html.push_str("\">");
+        escape(part, &mut html);
+        html.push_str("</span>");
+    }
+    html.push_str("</pre>");
+    html
+}
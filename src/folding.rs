@@ -0,0 +1,73 @@
+use crate::brackets::BracketPairs;
+use crate::{FoldStyle, Syntax};
+use std::ops::RangeInclusive;
+
+/// Columns of the leading whitespace, a tab reaches the next multiple of 4.
+/// `None` for blank lines.
+fn indent_width(line: &str) -> Option<usize> {
+    let content = line.trim_start_matches([' ', '\t']);
+    if content.trim().is_empty() {
+        return None;
+    }
+    let indent = &line[..line.len() - content.len()];
+    Some(indent.chars().fold(0, |column, c| match c {
+        '\t' => (column / 4 + 1) * 4,
+        _ => column + 1,
+    }))
+}
+
+/// Foldable blocks as zero based line ranges, from the line kept visible to the last
+/// folded one, in order of their first line. Blocks of one line are left out.
+pub fn fold_ranges(syntax: &Syntax, text: &str) -> Vec<RangeInclusive<usize>> {
+    match syntax.fold_style {
+        FoldStyle::Braces => brace_folds(syntax, text),
+        FoldStyle::Indent => indent_folds(text),
+    }
+}
+
+fn brace_folds(syntax: &Syntax, text: &str) -> Vec<RangeInclusive<usize>> {
+    let line_starts = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+        .collect::<Vec<_>>();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+    let mut ranges = BracketPairs::new(syntax, text)
+        .pairs
+        .into_iter()
+        .filter(|(open, _)| text[*open..].starts_with('{'))
+        .map(|(open, close)| line_of(open)..=line_of(close))
+        .filter(|range| range.start() < range.end())
+        .collect::<Vec<_>>();
+    // Of blocks opened on the same line, the outermost folds
+    ranges.sort_by_key(|range| (*range.start(), std::cmp::Reverse(*range.end())));
+    ranges.dedup_by_key(|range| *range.start());
+    ranges
+}
+
+/// A line heads a block when the next non-blank line is indented deeper. The block runs to
+/// the last deeper line before one indented as little, blank lines inside it included.
+fn indent_folds(text: &str) -> Vec<RangeInclusive<usize>> {
+    let widths = text.lines().map(indent_width).collect::<Vec<_>>();
+    let mut ends = vec![0; widths.len()];
+    // Lines whose block is still open, indented less than every line after them
+    let mut open: Vec<(usize, usize)> = vec![];
+    let mut last = 0;
+    for (line, width) in widths.iter().enumerate() {
+        let Some(width) = *width else {
+            continue;
+        };
+        while let Some(&(start, _)) = open.last().filter(|(_, open)| *open >= width) {
+            ends[start] = last;
+            open.pop();
+        }
+        open.push((line, width));
+        last = line;
+    }
+    for (start, _) in open {
+        ends[start] = last;
+    }
+    ends.into_iter()
+        .enumerate()
+        .filter(|(start, end)| end > start)
+        .map(|(start, end)| start..=end)
+        .collect()
+}
@@ -1,10 +1,27 @@
-#[cfg(feature = "editor")]
+#[cfg(feature = "egui")]
 use super::Editor;
 
 use super::syntax::{QUOTES, SEPARATORS, Syntax, TokenType};
 use std::mem;
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Kind of construct left open at the end of the text.
+pub enum LexDiagnosticKind {
+    /// String opened with the given quote and never closed.
+    UnterminatedStr(char),
+    /// Multiline comment opened and never closed.
+    UnterminatedComment,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Problem found by the lexer, `range` is in bytes and runs up to the end of the text.
+pub struct LexDiagnostic {
+    pub kind: LexDiagnosticKind,
+    pub range: Range<usize>,
+}
 
-#[derive(Default, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Default, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 /// Lexer and Token
 pub struct Token {
     ty: TokenType,
@@ -37,7 +54,9 @@ impl Token {
             c if syntax.is_keyword(c.to_string().as_str()) => TokenType::Keyword,
             c if syntax.is_type(c.to_string().as_str()) => TokenType::Type,
             c if syntax.is_special(c.to_string().as_str()) => TokenType::Special,
-            c if syntax.comment == c.to_string().as_str() => TokenType::Comment(false),
+            c if syntax.line_comment_markers().any(|m| m == c.to_string()) => {
+                TokenType::Comment(false)
+            }
             c if syntax.comment_multiline[0] == c.to_string().as_str() => TokenType::Comment(true),
             _ => TokenType::from(c),
         };
@@ -47,10 +66,7 @@ impl Token {
     fn drain(&mut self, ty: TokenType) -> Option<Self> {
         let mut token = None;
         if !self.buffer().is_empty() {
-            token = Some(Token {
-                buffer: mem::take(&mut self.buffer),
-                ty: self.ty,
-            });
+            token = Some(Token::new(self.ty, mem::take(&mut self.buffer)));
         }
         self.ty = ty;
         token
@@ -71,43 +87,170 @@ impl Token {
     #[cfg(feature = "egui")]
     /// Syntax highlighting
     pub fn highlight<T: Editor>(&mut self, editor: &T, text: &str) -> LayoutJob {
-        *self = Token::default();
+        self.highlight_tokens(editor, text).0
+    }
+
+    #[cfg(feature = "egui")]
+    /// Syntax highlighting with the tokens as they are drawn, split by [`Editor::split`]
+    pub fn highlight_tokens<T: Editor>(
+        &mut self,
+        editor: &T,
+        text: &str,
+    ) -> (LayoutJob, Vec<Self>) {
+        let mut lexer = Lexer::default();
         let mut job = LayoutJob::default();
+        let mut tokens = vec![];
+        let mut emit = |job: &mut LayoutJob, token: Token, line: usize| {
+            if token.buffer().is_empty() {
+                return;
+            }
+            for part in editor.split(token) {
+                editor.append(job, &part, line);
+                tokens.push(part);
+            }
+        };
 
         let mut line = 0;
 
-        for c in text.chars() {
-            for token in self.automata(c, editor.syntax()) {
-                editor.append(&mut job, &token, line);
+        for text_line in text.split_inclusive('\n') {
+            // Too long to tokenize every frame, shown plain as a whole
+            if editor
+                .max_highlight_line_len()
+                .is_some_and(|max| text_line.len() > max)
+            {
+                for token in lexer.finish(editor.syntax()) {
+                    emit(&mut job, token, line);
+                }
+                emit(&mut job, mem::take(&mut lexer.token), line);
+                let content = text_line.strip_suffix('\n');
+                let plain = Token::new(TokenType::Literal, content.unwrap_or(text_line));
+                emit(&mut job, plain, line);
+                if content.is_some() {
+                    emit(
+                        &mut job,
+                        Token::new(TokenType::Whitespace('\n'), "\n"),
+                        line,
+                    );
+                }
+                line += 1;
+                continue;
             }
 
-            if c == '\n' {
-                line += 1;
+            for c in text_line.chars() {
+                for token in lexer.automata(c, editor.syntax()) {
+                    emit(&mut job, token, line);
+                }
+
+                if c == '\n' {
+                    line += 1;
+                }
             }
         }
 
-        editor.append(&mut job, self, usize::MAX);
-        job
+        for token in lexer.finish(editor.syntax()) {
+            emit(&mut job, token, line);
+        }
+        emit(&mut job, mem::take(&mut lexer.token), usize::MAX);
+        *self = lexer.token;
+        (job, tokens)
     }
 
     /// Lexer
     pub fn tokens(&mut self, syntax: &Syntax, text: &str) -> Vec<Self> {
+        self.tokens_with_diagnostics(syntax, text).0
+    }
+
+    #[cfg(feature = "egui")]
+    /// Lexes `text` on from the current state, tokens still open at its end stay in the lexer.
+    /// A string held at the end for a following `:` is given back as a value.
+    pub(crate) fn tokens_continued(&mut self, syntax: &Syntax, text: &str) -> Vec<Self> {
+        let mut lexer = Lexer::new(mem::take(self));
+        let mut tokens: Vec<Self> = text
+            .chars()
+            .flat_map(|c| lexer.automata(c, syntax))
+            .collect();
+        tokens.extend(lexer.release(false));
+        *self = lexer.token;
+        tokens
+    }
+
+    /// Lexer reporting strings and multiline comments left unterminated at the end of the text
+    pub fn tokens_with_diagnostics(
+        &mut self,
+        syntax: &Syntax,
+        text: &str,
+    ) -> (Vec<Self>, Vec<LexDiagnostic>) {
+        let mut lexer = Lexer::new(mem::take(self));
         let mut tokens: Vec<Self> = text
             .chars()
-            .flat_map(|c| self.automata(c, syntax))
+            .flat_map(|c| lexer.automata(c, syntax))
             .collect();
+        tokens.extend(lexer.finish(syntax));
+        *self = lexer.token;
+        let mut diagnostics = vec![];
 
         if !self.buffer.is_empty() {
+            let kind = match self.ty {
+                TokenType::Str(quote) => Some(LexDiagnosticKind::UnterminatedStr(quote)),
+                TokenType::Comment(true) | TokenType::DocComment(true) => {
+                    Some(LexDiagnosticKind::UnterminatedComment)
+                }
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                diagnostics.push(LexDiagnostic {
+                    kind,
+                    range: text.len() - self.buffer.len()..text.len(),
+                });
+            }
             tokens.push(mem::take(self));
         }
+        (tokens, diagnostics)
+    }
+
+    /// Multiline comment in the buffer ends with `close`, which closes the outermost one
+    /// when comments nest.
+    fn closes_comment(&self, close: &str, syntax: &Syntax) -> bool {
+        if !self.buffer.ends_with(close) {
+            return false;
+        }
+        !syntax.nested_comments
+            || self.buffer.matches(syntax.comment_multiline[0]).count()
+                <= self.buffer.matches(close).count()
+    }
+
+    /// Buffer holds the start of a comment opener longer than one char, like `=b` of `=begin`
+    fn is_partial_opener(&self) -> bool {
+        matches!(self.ty, TokenType::Punctuation(_)) && self.buffer.chars().nth(1).is_some()
+    }
+
+    /// Partial comment opener turned out to be something else, like `=b` of `=bar` where
+    /// `=begin` opens comments: the first char is punctuation and the rest is lexed again.
+    fn backtrack(&mut self, next: Option<char>, syntax: &Syntax) -> Vec<Self> {
+        let first = self.buffer.chars().next().map_or(0, char::len_utf8);
+        let rest = self.buffer.split_off(first);
+        let mut tokens = Vec::from_iter(self.drain(TokenType::Unknown));
+        for c in rest.chars().chain(next) {
+            tokens.extend(self.automata(c, syntax));
+        }
         tokens
     }
 
     fn automata(&mut self, c: char, syntax: &Syntax) -> Vec<Self> {
         use TokenType as Ty;
         let mut tokens = vec![];
+        if syntax.is_plain() {
+            if c == '\n' {
+                tokens.extend(self.drain(Ty::Whitespace(c)));
+                tokens.extend(self.push_drain(c, Ty::Literal));
+            } else {
+                self.ty = Ty::Literal;
+                self.buffer.push(c);
+            }
+            return tokens;
+        }
         match (self.ty, Ty::from(c)) {
-            (Ty::Comment(false), Ty::Whitespace('\n')) => {
+            (Ty::Comment(false) | Ty::DocComment(false), Ty::Whitespace('\n')) => {
                 self.buffer.push(c);
                 let n = self.buffer.pop();
                 tokens.extend(self.drain(Ty::Whitespace(c)));
@@ -117,13 +260,46 @@ impl Token {
             }
             (Ty::Comment(false), _) => {
                 self.buffer.push(c);
+                if syntax.doc_comment == Some(self.buffer.as_str()) {
+                    self.ty = Ty::DocComment(false);
+                } else if self.buffer == syntax.comment_multiline[0] {
+                    // Multiline opener extending the single line one, like Lua `--[[`
+                    self.ty = Ty::Comment(true);
+                }
+            }
+            (Ty::DocComment(false), _) => {
+                self.buffer.push(c);
             }
             (Ty::Comment(true), _) => {
                 self.buffer.push(c);
-                if self.buffer.ends_with(syntax.comment_multiline[1]) {
+                if let Some([open, _]) = syntax.doc_comment_multiline
+                    && self.buffer == open
+                {
+                    self.ty = Ty::DocComment(true);
+                } else if self.closes_comment(syntax.comment_multiline[1], syntax) {
+                    tokens.extend(self.drain(Ty::Unknown));
+                }
+            }
+            (Ty::DocComment(true), _) => {
+                self.buffer.push(c);
+                let close = syntax
+                    .doc_comment_multiline
+                    .map_or(syntax.comment_multiline[1], |[_, close]| close);
+                if self.closes_comment(close, syntax) {
+                    // Empty comment sharing its chars with the doc opener, like `/**/`
+                    let [open, close] = syntax.comment_multiline;
+                    if self.buffer.len() == open.len() + close.len()
+                        && self.buffer.starts_with(open)
+                        && self.buffer.ends_with(close)
+                    {
+                        self.ty = Ty::Comment(true);
+                    }
                     tokens.extend(self.drain(Ty::Unknown));
                 }
             }
+            (Ty::Punctuation(_), Ty::Whitespace(_)) if self.is_partial_opener() => {
+                tokens.extend(self.backtrack(Some(c), syntax));
+            }
             (Ty::Literal | Ty::Punctuation(_), Ty::Whitespace(_)) => {
                 tokens.extend(self.drain(Ty::Whitespace(c)));
                 tokens.extend(self.first(c, syntax));
@@ -153,9 +329,11 @@ impl Token {
                 _ => {
                     self.buffer.push(c);
                     self.ty = {
-                        if self.buffer.starts_with(syntax.comment) {
+                        // Empty markers mean the syntax has no such comments
+                        let starts = |open: &str| !open.is_empty() && self.buffer.starts_with(open);
+                        if syntax.line_comment_markers().any(starts) {
                             Ty::Comment(false)
-                        } else if self.buffer.starts_with(syntax.comment_multiline[0]) {
+                        } else if starts(syntax.comment_multiline[0]) {
                             Ty::Comment(true)
                         } else if syntax.is_hyperlink(&self.buffer) {
                             Ty::Hyperlink
@@ -179,32 +357,33 @@ impl Token {
                 self.buffer.push(c);
             }
             (Ty::Numeric(_), Ty::Literal) => {
-                tokens.extend(self.drain(self.ty));
+                if !syntax.unit_suffixes {
+                    tokens.extend(self.drain(self.ty));
+                }
                 self.buffer.push(c);
             }
-            (Ty::Numeric(_), _) | (Ty::Punctuation(_), Ty::Literal | Ty::Numeric(_)) => {
+            (Ty::Numeric(_), _) => {
                 tokens.extend(self.drain(self.ty));
                 tokens.extend(self.first(c, syntax));
             }
-            (Ty::Punctuation(_), Ty::Str(_)) => {
-                tokens.extend(self.drain_push(c, Ty::Str(c)));
-            }
-            (Ty::Punctuation(_), _) => {
-                if !(syntax.comment.starts_with(&self.buffer)
-                    || syntax.comment_multiline[0].starts_with(&self.buffer))
-                {
-                    tokens.extend(self.drain(self.ty));
-                    tokens.extend(self.first(c, syntax));
-                } else {
-                    self.buffer.push(c);
-                    if self.buffer.starts_with(syntax.comment) {
+            (Ty::Punctuation(_), next) => {
+                let mut candidate = self.buffer.clone();
+                candidate.push(c);
+                let opens = |open: &str| !open.is_empty() && open.starts_with(&candidate);
+                if syntax.line_comment_markers().any(opens) || opens(syntax.comment_multiline[0]) {
+                    if syntax.line_comment_markers().any(|m| m == candidate) {
                         self.ty = Ty::Comment(false);
-                    } else if self.buffer.starts_with(syntax.comment_multiline[0]) {
+                    } else if candidate == syntax.comment_multiline[0] {
                         self.ty = Ty::Comment(true);
-                    } else if let Some(c) = self.buffer.pop() {
-                        tokens.extend(self.drain(Ty::Punctuation(c)));
-                        tokens.extend(self.first(c, syntax));
                     }
+                    self.buffer = candidate;
+                } else if self.is_partial_opener() {
+                    tokens.extend(self.backtrack(Some(c), syntax));
+                } else if let Ty::Str(_) = next {
+                    tokens.extend(self.drain_push(c, Ty::Str(c)));
+                } else {
+                    tokens.extend(self.drain(self.ty));
+                    tokens.extend(self.first(c, syntax));
                 }
             }
             (Ty::Str(q), _) => {
@@ -240,20 +419,333 @@ impl Token {
     }
 }
 
+#[derive(Default)]
+/// Lexer state around the token being built: with [`Syntax::key_strings`] a closed string
+/// and the blanks after it are held until the next char tells keys from values.
+struct Lexer {
+    token: Token,
+    held: Vec<Token>,
+}
+
+impl Lexer {
+    fn new(token: Token) -> Self {
+        Lexer {
+            token,
+            held: vec![],
+        }
+    }
+
+    fn automata(&mut self, c: char, syntax: &Syntax) -> Vec<Token> {
+        if !self.held.is_empty() {
+            if c == ' ' || c == '\t' {
+                self.held.push(Token::new(TokenType::Whitespace(c), c));
+                return vec![];
+            }
+            let mut tokens = self.release(c == ':');
+            tokens.extend(self.automata(c, syntax));
+            return tokens;
+        }
+        let mut tokens = self.token.automata(c, syntax);
+        // Strings only come out of the automata once closed
+        if syntax.key_strings
+            && matches!(tokens.last(), Some(t) if matches!(t.ty, TokenType::Str(_)))
+        {
+            self.held.extend(tokens.pop());
+        }
+        tokens
+    }
+
+    /// Held string with the blanks after it, as a key of [`Syntax::key_strings`] or a value
+    fn release(&mut self, key: bool) -> Vec<Token> {
+        let mut held = mem::take(&mut self.held);
+        if key && let Some(string) = held.first_mut() {
+            string.ty = TokenType::Type;
+        }
+        held
+    }
+
+    /// Tokens settled by the end of the text, the token still open stays in the lexer
+    fn finish(&mut self, syntax: &Syntax) -> Vec<Token> {
+        let mut tokens = vec![];
+        if self.token.is_partial_opener() {
+            tokens.extend(self.token.backtrack(None, syntax));
+        }
+        tokens.extend(self.release(false));
+        tokens
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+/// Tokens of a single line, the last one ends with the newline unless it is the last line.
+pub struct TokenLine {
+    /// Line starts inside a multiline comment opened on a previous line.
+    pub in_comment: bool,
+    pub tokens: Vec<Token>,
+}
+
+/// Lexes `text` and groups the tokens per line, tokens spanning several lines are split
+/// at the newlines. There is always at least one line, like in the editor.
+pub fn tokenize_lines(text: &str, syntax: &Syntax) -> Vec<TokenLine> {
+    let mut lines = vec![];
+    let mut line = TokenLine::default();
+    for token in Token::default().tokens(syntax, text) {
+        for part in token.buffer.split_inclusive('\n') {
+            line.tokens.push(Token::new(token.ty, part));
+            if part.ends_with('\n') {
+                let in_comment = matches!(
+                    token.ty,
+                    TokenType::Comment(true) | TokenType::DocComment(true)
+                );
+                lines.push(mem::replace(
+                    &mut line,
+                    TokenLine {
+                        in_comment,
+                        tokens: vec![],
+                    },
+                ));
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Invisible characters which are easy to paste by accident: control characters other than
+/// tab and line breaks, zero width and direction marks, soft hyphen and byte order mark.
+pub fn is_invisible_char(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{2028}'..='\u{202E}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{2069}'
+                | '\u{FEFF}'
+        )
+}
+
+/// Char indices of the [`is_invisible_char`] characters in `text`
+pub fn invisible_chars(text: &str) -> Vec<usize> {
+    text.chars()
+        .enumerate()
+        .filter(|(_, c)| is_invisible_char(*c))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Merges adjacent tokens of the same type, like runs of single space tokens,
+/// into one token covering the same text.
+pub fn coalesce_tokens(tokens: Vec<Token>) -> Vec<Token> {
+    let mut merged: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        match merged.last_mut() {
+            Some(last) if last.ty == token.ty => last.buffer.push_str(&token.buffer),
+            _ => merged.push(token),
+        }
+    }
+    merged
+}
+
+/// Type of the token a caret at `char_index` is in, that is the token of the char before it.
+/// A caret at the start of the text is in the first token.
+pub fn token_at(syntax: &Syntax, text: &str, char_index: usize) -> Option<TokenType> {
+    token_type_at(&Token::default().tokens(syntax, text), char_index)
+}
+
+/// [`token_at`] within already lexed tokens of the text
+pub fn token_type_at(tokens: &[Token], char_index: usize) -> Option<TokenType> {
+    let target = char_index.saturating_sub(1);
+    let mut end = 0;
+    tokens
+        .iter()
+        .find(|token| {
+            end += token.buffer().chars().count();
+            target < end
+        })
+        .map(|token| token.ty())
+}
+
+/// Splits whole word `tags` out of comment token as [`TokenType::CommentTag`] tokens.
+/// Other tokens are returned as is.
+pub fn split_comment_tags(token: Token, tags: &[String]) -> Vec<Token> {
+    if !matches!(token.ty, TokenType::Comment(_) | TokenType::DocComment(_)) || tags.is_empty() {
+        return vec![token];
+    }
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut tokens = vec![];
+    let mut rest = token.buffer.as_str();
+    let mut from = 0;
+    while let Some(start) = rest[from..].find(is_word).map(|i| i + from) {
+        let end = rest[start..]
+            .find(|c| !is_word(c))
+            .map_or(rest.len(), |i| i + start);
+        if tags.iter().any(|tag| *tag == rest[start..end]) {
+            if start > 0 {
+                tokens.push(Token::new(token.ty, &rest[..start]));
+            }
+            tokens.push(Token::new(TokenType::CommentTag, &rest[start..end]));
+            rest = &rest[end..];
+            from = 0;
+        } else {
+            from = end;
+        }
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::new(token.ty, rest));
+    }
+    tokens
+}
+
+/// Splits the quotes out of a string token as [`TokenType::StringDelim`] tokens,
+/// an unterminated string keeps only the opening one. Other tokens are returned as is.
+pub fn split_string_delims(token: Token) -> Vec<Token> {
+    let TokenType::Str(quote) = token.ty else {
+        return vec![token];
+    };
+    let Some(body) = token.buffer.strip_prefix(quote) else {
+        return vec![token];
+    };
+    let delim = || Token::new(TokenType::StringDelim(quote), quote);
+    // Closed like the lexer closes strings, by a quote not right after a backslash
+    let (body, closed) = match body.strip_suffix(quote) {
+        Some(inner) if !inner.ends_with('\\') => (inner, true),
+        _ => (body, false),
+    };
+    let mut tokens = vec![delim()];
+    if !body.is_empty() {
+        tokens.push(Token::new(token.ty, body));
+    }
+    if closed {
+        tokens.push(delim());
+    }
+    tokens
+}
+
 #[cfg(feature = "egui")]
 use egui::text::LayoutJob;
 
 #[cfg(feature = "egui")]
-impl<T: Editor> egui::util::cache::ComputerMut<(&T, &str), LayoutJob> for Token {
-    fn compute(&mut self, (cache, text): (&T, &str)) -> LayoutJob {
-        self.highlight(cache, text)
+impl<T: Editor> egui::util::cache::ComputerMut<(&T, &str), Highlighted> for Token {
+    fn compute(&mut self, (cache, text): (&T, &str)) -> Highlighted {
+        let (job, tokens) = self.highlight_tokens(cache, text);
+        (job, tokens.into())
     }
 }
 
 #[cfg(feature = "egui")]
-pub type HighlightCache = egui::util::cache::FrameCache<LayoutJob, Token>;
+/// Layout job of the text and the tokens it was drawn from
+pub type Highlighted = (LayoutJob, std::sync::Arc<[Token]>);
+
+#[cfg(feature = "egui")]
+pub type HighlightCache = egui::util::cache::FrameCache<Highlighted, Token>;
 
 #[cfg(feature = "egui")]
 pub fn highlight<T: Editor>(ctx: &egui::Context, cache: &T, text: &str) -> egui::text::LayoutJob {
+    highlight_with_tokens(ctx, cache, text).0
+}
+
+#[cfg(feature = "egui")]
+/// [`highlight`] with the drawn tokens, both from one lexing pass cached between frames
+pub fn highlight_with_tokens<T: Editor>(ctx: &egui::Context, cache: &T, text: &str) -> Highlighted {
     ctx.memory_mut(|mem| mem.caches.cache::<HighlightCache>().get((cache, text)))
 }
+
+#[cfg(feature = "egui")]
+/// Changes format of the byte range, splitting sections of the job on its bounds.
+pub fn restyle(
+    job: &mut LayoutJob,
+    range: Range<usize>,
+    style: impl Fn(&mut egui::text::TextFormat),
+) {
+    if range.is_empty() {
+        return;
+    }
+    let mut sections = Vec::with_capacity(job.sections.len() + 2);
+    for section in job.sections.drain(..) {
+        let bounds = section.byte_range.clone();
+        if bounds.end <= range.start || bounds.start >= range.end {
+            sections.push(section);
+            continue;
+        }
+        let start = bounds.start.max(range.start);
+        let end = bounds.end.min(range.end);
+        let mut leading_space = section.leading_space;
+        if bounds.start < start {
+            sections.push(egui::text::LayoutSection {
+                leading_space,
+                byte_range: bounds.start..start,
+                format: section.format.clone(),
+            });
+            leading_space = 0.0;
+        }
+        let mut format = section.format.clone();
+        style(&mut format);
+        sections.push(egui::text::LayoutSection {
+            leading_space,
+            byte_range: start..end,
+            format,
+        });
+        if end < bounds.end {
+            sections.push(egui::text::LayoutSection {
+                leading_space: 0.0,
+                byte_range: end..bounds.end,
+                format: section.format,
+            });
+        }
+    }
+    job.sections = sections;
+}
+
+#[cfg(feature = "egui")]
+/// [`restyle`] of many byte ranges in one pass over the sections, `ranges` sorted by start.
+/// Where ranges overlap, the earlier one keeps the overlap.
+pub fn restyle_sorted<T>(
+    job: &mut LayoutJob,
+    ranges: impl IntoIterator<Item = (Range<usize>, T)>,
+    style: impl Fn(&mut egui::text::TextFormat, &T),
+) {
+    let mut ranges = ranges.into_iter().peekable();
+    let mut sections = Vec::with_capacity(job.sections.len());
+    for section in job.sections.drain(..) {
+        let bounds = section.byte_range.clone();
+        if bounds.is_empty() {
+            sections.push(section);
+            continue;
+        }
+        let mut leading_space = section.leading_space;
+        let mut from = bounds.start;
+        let mut push = |range: Range<usize>, format| {
+            sections.push(egui::text::LayoutSection {
+                leading_space: mem::take(&mut leading_space),
+                byte_range: range,
+                format,
+            });
+        };
+        while from < bounds.end {
+            // Ranges ending before this point, also ones covered by an earlier range, are done
+            while ranges.peek().is_some_and(|(range, _)| range.end <= from) {
+                ranges.next();
+            }
+            let Some((range, value)) = ranges.peek() else {
+                push(from..bounds.end, section.format.clone());
+                break;
+            };
+            let start = range.start.max(from);
+            if start >= bounds.end {
+                push(from..bounds.end, section.format.clone());
+                break;
+            }
+            if from < start {
+                push(from..start, section.format.clone());
+            }
+            let end = range.end.min(bounds.end);
+            let mut format = section.format.clone();
+            style(&mut format, value);
+            push(start..end, format);
+            from = end;
+        }
+    }
+    job.sections = sections;
+}
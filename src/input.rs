@@ -0,0 +1,265 @@
+use egui::{Event, Key, Modifiers, TextBuffer};
+use std::ops::Range;
+
+/// Pairs typed together by auto-close, quotes close themselves.
+const AUTO_CLOSE_PAIRS: [(char, char); 5] =
+    [('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')];
+
+fn delete_key() -> Event {
+    key_event(Key::Delete)
+}
+
+fn key_event(key: Key) -> Event {
+    Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers: Modifiers::NONE,
+    }
+}
+
+/// Removes the first press of `shortcut` from `events`. Unlike egui's `consume_shortcut`,
+/// the modifiers match exactly, so Ctrl+Shift+Space is not taken for Ctrl+Space.
+pub fn consume_exact(events: &mut Vec<Event>, shortcut: &egui::KeyboardShortcut) -> bool {
+    let index = events.iter().position(|event| {
+        matches!(event, Event::Key { key, pressed: true, modifiers, .. }
+            if *key == shortcut.logical_key && modifiers.matches_exact(shortcut.modifiers))
+    });
+    index.map(|index| events.remove(index)).is_some()
+}
+
+/// Toggles `overtype` on Insert key presses, removing them from `events`.
+pub fn toggle_overtype(events: &mut Vec<Event>, overtype: &mut bool) {
+    events.retain(|event| match event {
+        Event::Key {
+            key: Key::Insert,
+            pressed,
+            modifiers,
+            ..
+        } if modifiers.is_none() => {
+            if *pressed {
+                *overtype = !*overtype;
+            }
+            false
+        }
+        _ => true,
+    });
+}
+
+/// Puts a Delete in front of every typed char which has a char to replace,
+/// stopping at the end of the line. `cursor` is a char index.
+pub fn overtype(events: &mut Vec<Event>, text: &str, cursor: usize) {
+    let mut following = text.chars().skip(cursor).take_while(|c| *c != '\n');
+    let mut result = Vec::with_capacity(events.len());
+    for event in events.drain(..) {
+        if let Event::Text(typed) = &event {
+            for _ in typed.chars() {
+                if following.next().is_some() {
+                    result.push(delete_key());
+                }
+            }
+        }
+        result.push(event);
+    }
+    *events = result;
+}
+
+/// Closer to type along with `typed`. Brackets close only in front of whitespace, a closer,
+/// `;` or `,`, quotes also not right after a word, so `don't` stays as typed.
+fn auto_closer(typed: char, before: Option<char>, after: Option<char>) -> Option<char> {
+    let (open, close) = AUTO_CLOSE_PAIRS
+        .into_iter()
+        .find(|(open, _)| *open == typed)?;
+    let free_after = after.is_none_or(|c| {
+        c.is_whitespace()
+            || c == ';'
+            || c == ','
+            || AUTO_CLOSE_PAIRS.iter().any(|(_, close)| *close == c)
+    });
+    let word_before = before.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    (free_after && !(open == close && word_before)).then_some(close)
+}
+
+/// Index of the first event acting on the text, typed text or a pressed key.
+fn first_input(events: &[Event]) -> Option<usize> {
+    events.iter().position(|event| match event {
+        Event::Text(_) => true,
+        Event::Key { pressed, .. } => *pressed,
+        _ => false,
+    })
+}
+
+/// Handles Tab and Shift+Tab ahead of egui, which replaces the selection with a tab.
+/// A selection spanning lines is indented with `indent` or outdented by a tab or up to
+/// `width` spaces, line by line, and the new selection is returned.
+/// Otherwise Tab types `indent`, spaces only up to the next multiple of `width`.
+/// `selection` is a sorted char range.
+pub fn tab(
+    events: &mut Vec<Event>,
+    text: &mut dyn TextBuffer,
+    selection: Range<usize>,
+    indent: &str,
+    width: usize,
+) -> Option<Range<usize>> {
+    let index = first_input(events)?;
+    let event = &mut events[index];
+    let Event::Key {
+        key: Key::Tab,
+        modifiers,
+        ..
+    } = event
+    else {
+        return None;
+    };
+    let outdent = match *modifiers {
+        Modifiers::NONE => false,
+        Modifiers::SHIFT => true,
+        _ => return None,
+    };
+    let chars = text.as_str().chars().collect::<Vec<_>>();
+    // Stored cursor may be left from a longer text
+    let selection = selection.start.min(chars.len())..selection.end.min(chars.len());
+    let first = chars[..selection.start]
+        .iter()
+        .rposition(|c| *c == '\n')
+        .map_or(0, |i| i + 1);
+    if !chars[selection.clone()].contains(&'\n') {
+        if !outdent && indent != "\t" {
+            let column = selection.start - first;
+            *event = Event::Text(" ".repeat(width - column % width));
+        }
+        return None;
+    }
+    // Line starts inside the selection, a line it ends at the start of isn't included
+    let mut starts = vec![first];
+    let newlines = chars[first..selection.end - 1].iter().enumerate();
+    starts.extend(
+        newlines
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| first + i + 1),
+    );
+    events.remove(index);
+    let mut deltas = vec![];
+    for &start in starts.iter().rev() {
+        if outdent {
+            let leading = &chars[start..];
+            let count = if leading.first() == Some(&'\t') {
+                1
+            } else {
+                leading
+                    .iter()
+                    .take(width)
+                    .take_while(|c| **c == ' ')
+                    .count()
+            };
+            text.delete_char_range(start..start + count);
+            deltas.push(-(count as isize));
+        } else {
+            deltas.push(text.insert_text(indent, start) as isize);
+        }
+    }
+    let total: isize = deltas.iter().sum();
+    let first_delta = deltas.last().copied().unwrap_or(0);
+    let start = if selection.start == first {
+        first
+    } else {
+        selection
+            .start
+            .saturating_add_signed(first_delta)
+            .max(first)
+    };
+    Some(start..selection.end.saturating_add_signed(total).max(start))
+}
+
+/// Wraps the selected chars in a pair when an opener is typed over them, instead of
+/// replacing them. Returns the char range of the wrapped text to select after the edit.
+/// `selection` is a sorted, non-empty char range.
+pub fn surround(events: &mut [Event], text: &str, selection: Range<usize>) -> Option<Range<usize>> {
+    let index = first_input(events)?;
+    let Event::Text(typed) = &mut events[index] else {
+        return None;
+    };
+    let mut typed_chars = typed.chars();
+    let (Some(c), None) = (typed_chars.next(), typed_chars.next()) else {
+        return None;
+    };
+    let (open, close) = AUTO_CLOSE_PAIRS.into_iter().find(|(open, _)| *open == c)?;
+    let selected = text.char_range(selection.clone());
+    *typed = format!("{open}{selected}{close}");
+    Some(selection.start + 1..selection.end + 1)
+}
+
+/// Applies auto-close to the typed chars and Backspaces in `events`: types the closer after
+/// an opener, steps over a typed closer already following the cursor and deletes empty pairs.
+/// Stops at the first other input, as the cursor can't be followed past it.
+/// `cursor` is a char index.
+pub fn auto_close(events: &mut Vec<Event>, text: &str, cursor: usize) {
+    let relevant = |event: &Event| {
+        matches!(
+            event,
+            Event::Text(_)
+                | Event::Key {
+                    key: Key::Backspace,
+                    ..
+                }
+        )
+    };
+    if !events.iter().any(relevant) {
+        return;
+    }
+    let mut chars = text.chars().collect::<Vec<_>>();
+    let mut cursor = cursor.min(chars.len());
+    let mut pending = std::mem::take(events).into_iter();
+    for event in pending.by_ref() {
+        let before = cursor.checked_sub(1).map(|i| chars[i]);
+        let after = chars.get(cursor).copied();
+        match &event {
+            Event::Text(typed) => {
+                let mut typed_chars = typed.chars();
+                if let (Some(c), None) = (typed_chars.next(), typed_chars.next()) {
+                    if after == Some(c) && AUTO_CLOSE_PAIRS.iter().any(|(_, close)| *close == c) {
+                        events.push(key_event(Key::ArrowRight));
+                        cursor += 1;
+                        continue;
+                    }
+                    if let Some(close) = auto_closer(c, before, after) {
+                        events.push(event);
+                        events.push(Event::Text(close.to_string()));
+                        events.push(key_event(Key::ArrowLeft));
+                        chars.splice(cursor..cursor, [c, close]);
+                        cursor += 1;
+                        continue;
+                    }
+                }
+                let count = typed.chars().count();
+                chars.splice(cursor..cursor, typed.chars());
+                cursor += count;
+            }
+            Event::Key {
+                key: Key::Backspace,
+                pressed: true,
+                modifiers,
+                ..
+            } if modifiers.is_none() => {
+                if let (Some(before), Some(after)) = (before, after)
+                    && AUTO_CLOSE_PAIRS.contains(&(before, after))
+                {
+                    events.push(delete_key());
+                    chars.remove(cursor);
+                }
+                if cursor > 0 {
+                    cursor -= 1;
+                    chars.remove(cursor);
+                }
+            }
+            Event::Key { pressed: false, .. } => {}
+            _ => {
+                events.push(event);
+                break;
+            }
+        }
+        events.push(event);
+    }
+    events.extend(pending);
+}
@@ -0,0 +1,175 @@
+use egui::text::{CCursor, LayoutJob, LayoutSection};
+use egui::{Galley, Pos2, Rangef, Rect, pos2};
+
+/// Glyph marking rows continued on the next visual row.
+pub const WRAP_INDICATOR: char = '↵';
+
+/// Anchors for wrap indicators relative to the galley: vertical center of the right edge
+/// of every row which was wrapped instead of ending with a newline.
+pub fn wrap_indicator_positions(galley: &Galley) -> Vec<Pos2> {
+    let last = galley.rows.len().saturating_sub(1);
+    galley
+        .rows
+        .iter()
+        .enumerate()
+        .filter(|(i, row)| *i < last && !row.ends_with_newline)
+        .map(|(_, row)| {
+            let rect = row.rect();
+            pos2(rect.right(), rect.center().y)
+        })
+        .collect()
+}
+
+/// Zero based text line labeled on each visual row of the gutter, `None` on the rows continuing
+/// a wrapped line. `row_ends` tells for every visual row if it ends with a newline.
+pub fn gutter_lines(row_ends: &[bool]) -> Vec<Option<usize>> {
+    let mut line = 0;
+    let mut starts_line = true;
+    row_ends
+        .iter()
+        .map(|&ends_with_newline| {
+            let label = starts_line.then_some(line);
+            if ends_with_newline {
+                line += 1;
+            }
+            starts_line = ends_with_newline;
+            label
+        })
+        .collect()
+}
+
+/// Vertical distance the viewport has to move to keep `margin` between the cursor and
+/// its top and bottom edges. Negative moves up, zero when the cursor is far enough.
+///
+/// The margin shrinks when the viewport is too short to fit it on both sides.
+pub fn scrolloff_delta(cursor: Rect, viewport: Rect, margin: f32) -> f32 {
+    let margin = margin.min(((viewport.height() - cursor.height()) / 2.0).max(0.0));
+    if cursor.top() - margin < viewport.top() {
+        cursor.top() - margin - viewport.top()
+    } else if cursor.bottom() + margin > viewport.bottom() {
+        cursor.bottom() + margin - viewport.bottom()
+    } else {
+        0.0
+    }
+}
+
+/// Vertical extent of the zero based text `line` in the galley, over all of its wrapped rows.
+pub fn line_y_range(galley: &Galley, line: usize) -> Option<Rangef> {
+    let mut current = 0;
+    let mut range: Option<Rangef> = None;
+    for row in &galley.rows {
+        if current == line {
+            let rect = row.rect();
+            let top = range.map_or(rect.top(), |r| r.min);
+            range = Some(Rangef::new(top, rect.bottom()));
+        } else if current > line {
+            break;
+        }
+        if row.ends_with_newline {
+            current += 1;
+        }
+    }
+    range
+}
+
+/// Where the zero based `line` of `old` text is in `new` text after a single edit,
+/// `None` if the line was removed. Inserting at the start of the line moves it down.
+pub fn shift_line(old: &str, new: &str, line: usize) -> Option<usize> {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(old.len().min(new.len()), |((i, _), _)| i);
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    let removed = &old[prefix..old.len() - suffix];
+    let inserted = &new[prefix..new.len() - suffix];
+
+    let edit_line = old[..prefix].matches('\n').count();
+    let removed_lines = removed.matches('\n').count();
+    let inserted_lines = inserted.matches('\n').count();
+    let line_start = prefix == 0 || old[..prefix].ends_with('\n');
+
+    let last_removed = edit_line + removed_lines;
+    if line < edit_line {
+        Some(line)
+    } else if line == edit_line && !line_start {
+        // Head of the line stays in front of the edit
+        Some(line)
+    } else if line == edit_line && removed.is_empty() {
+        Some(line + inserted_lines)
+    } else if line == last_removed && line_start {
+        // Tail of the last removed line stays after the edit
+        Some(edit_line + inserted_lines)
+    } else if line <= last_removed {
+        None
+    } else {
+        Some(line + inserted_lines - removed_lines)
+    }
+}
+
+/// Byte index of the char at each of the char `offsets`, sorted in ascending order, for inlay
+/// hints which need space made for them, that is which aren't at a line end. One walk over
+/// the text.
+pub fn inlay_hint_spaces(
+    text: &str,
+    offsets: impl IntoIterator<Item = usize>,
+) -> Vec<Option<usize>> {
+    let mut chars = text.char_indices().enumerate().peekable();
+    offsets
+        .into_iter()
+        .map(|offset| {
+            while chars.next_if(|(i, _)| *i < offset).is_some() {}
+            chars
+                .peek()
+                .filter(|(i, (_, c))| *i == offset && *c != '\n')
+                .map(|(_, (at, _))| *at)
+        })
+        .collect()
+}
+
+/// Adds empty space of the given width in front of every byte of `spaces`, sorted in ascending
+/// order, splitting sections in one walk over them. The text and its offsets stay the same.
+pub fn insert_spaces(job: &mut LayoutJob, spaces: impl IntoIterator<Item = (usize, f32)>) {
+    let mut spaces = spaces.into_iter().peekable();
+    let mut sections = Vec::with_capacity(job.sections.len());
+    for mut section in job.sections.drain(..) {
+        // Spaces before the section found no char to go in front of
+        while spaces
+            .next_if(|(at, _)| *at < section.byte_range.start)
+            .is_some()
+        {}
+        while let Some((at, width)) = spaces.next_if(|(at, _)| section.byte_range.contains(at)) {
+            if at == section.byte_range.start {
+                section.leading_space += width;
+                continue;
+            }
+            let rest = LayoutSection {
+                leading_space: width,
+                byte_range: at..section.byte_range.end,
+                format: section.format.clone(),
+            };
+            section.byte_range.end = at;
+            sections.push(std::mem::replace(&mut section, rest));
+        }
+        sections.push(section);
+    }
+    job.sections = sections;
+}
+
+/// Top left corner, relative to the galley, of an inlay hint `width` wide shown in front of
+/// the char `offset`: in the space made by [`insert_spaces`] when `spaced`, or right after
+/// a line end.
+pub fn inlay_hint_pos_spaced(galley: &Galley, offset: usize, width: f32, spaced: bool) -> Pos2 {
+    let rect = galley.pos_from_cursor(CCursor::new(offset));
+    if spaced {
+        pos2(rect.left() - width, rect.top())
+    } else {
+        rect.left_top()
+    }
+}
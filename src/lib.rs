@@ -22,7 +22,7 @@
 //!
 //! ```toml
 //! [dependencies]
-//! egui_code_editor = { version = "0.2" , default-features = false }
+//! egui_code_editor = { version = "0.3" , default-features = false }
 //! colorful = "0.2.2"
 //! ```
 //!
@@ -35,6 +35,8 @@
 //! fn color(token: TokenType) -> Color {
 //!     match token {
 //!         TokenType::Comment(_) => Color::Grey37,
+//!         TokenType::DocComment(_) => Color::Grey50,
+//!         TokenType::CommentTag => Color::Yellow1,
 //!         TokenType::Function => Color::Yellow3b,
 //!         TokenType::Keyword => Color::IndianRed1c,
 //!         TokenType::Literal => Color::NavajoWhite1,
@@ -42,9 +44,11 @@
 //!         TokenType::Punctuation(_) => Color::Orange3,
 //!         TokenType::Special => Color::Cyan,
 //!         TokenType::Str(_) => Color::Green,
+//!         TokenType::StringDelim(_) => Color::DarkGreen,
 //!         TokenType::Type => Color::GreenYellow,
 //!         TokenType::Whitespace(_) => Color::White,
 //!         TokenType::Unknown => Color::Pink1,
+//!         _ => Color::White,
 //!     }
 //! }
 //!
@@ -67,34 +71,66 @@
 //!     }
 //! }
 //! ```
+pub mod brackets;
 #[cfg(feature = "egui")]
 pub mod completer;
+pub mod export;
+pub mod folding;
 pub mod highlighting;
+#[cfg(feature = "egui")]
+mod input;
+#[cfg(feature = "egui")]
+pub mod layout;
+pub mod outline;
+pub mod selection;
 mod syntax;
 #[cfg(test)]
 mod tests;
 mod themes;
 
+pub use brackets::BracketMatching;
 #[cfg(feature = "egui")]
 use egui::text::LayoutJob;
 #[cfg(feature = "egui")]
 use egui::widgets::text_edit::TextEditOutput;
-pub use highlighting::Token;
 #[cfg(feature = "egui")]
 use highlighting::highlight;
+pub use highlighting::{
+    LexDiagnostic, LexDiagnosticKind, Token, TokenLine, coalesce_tokens, tokenize_lines,
+};
 #[cfg(feature = "editor")]
 use std::hash::{Hash, Hasher};
-pub use syntax::{Syntax, TokenType};
-pub use themes::ColorTheme;
+#[cfg(feature = "editor")]
+use std::ops::Range;
+#[cfg(feature = "editor")]
+use std::sync::Arc;
+pub use syntax::{FoldStyle, Syntax, SyntaxExtension, TokenType};
 pub use themes::DEFAULT_THEMES;
+pub use themes::{ColorTheme, ColorThemeBuilder, MAX_BACKGROUNDS};
 
+#[cfg(all(feature = "editor", feature = "egui"))]
+use crate::completer::Callback;
 #[cfg(feature = "egui")]
 pub use crate::completer::Completer;
 
+#[cfg(all(feature = "editor", feature = "egui"))]
+type LayoutHook = dyn Fn(&mut LayoutJob, &str) + Send + Sync;
+
+/// Attention tags highlighted inside comments by default.
+pub const DEFAULT_COMMENT_TAGS: [&str; 4] = ["TODO", "FIXME", "HACK", "XXX"];
+
 #[cfg(feature = "egui")]
 pub trait Editor: Hash {
     fn append(&self, job: &mut LayoutJob, token: &Token, line: usize);
+    /// Tokens a lexed token is drawn as, each is then appended on its own
+    fn split(&self, token: Token) -> Vec<Token> {
+        vec![token]
+    }
     fn syntax(&self) -> &Syntax;
+    /// Lines longer than this in bytes are not tokenized, but shown as a single literal
+    fn max_highlight_line_len(&self) -> Option<usize> {
+        None
+    }
 }
 
 #[cfg(feature = "editor")]
@@ -108,28 +144,239 @@ pub struct CodeEditor {
     numlines_shift: isize,
     numlines_only_natural: bool,
     fontsize: f32,
+    #[cfg(feature = "egui")]
+    font_family: egui::FontFamily,
     rows: usize,
     vscroll: bool,
     stick_to_bottom: bool,
     desired_width: f32,
+    #[cfg(feature = "egui")]
     highlights: Vec<LineHighlight>,
-    readonly: bool,
+    interaction: Interaction,
+    bracket_matching: BracketMatching,
+    word_wrap: bool,
+    wrap_column: Option<usize>,
+    wrap_indicator: bool,
+    highlight_control_chars: bool,
+    comment_tags: Vec<String>,
+    string_delimiters: bool,
+    scrolloff: usize,
+    caret_width: Option<f32>,
+    caret_blink: bool,
+    overtype: bool,
+    auto_close: bool,
+    indent: IndentStyle,
+    #[cfg(feature = "egui")]
+    gutter_style: GutterStyle,
+    active_line_number: bool,
+    rich_copy: bool,
+    pending_selection: Option<Range<usize>>,
+    selection: Option<Range<usize>>,
+    pending_step: Option<SelectionStep>,
+    pending_retab: Option<IndentStyle>,
+    /// Char offset and text inserted before the events
+    pending_insert: Option<(usize, String)>,
+    /// Selections grown one after another, latest last
+    selection_steps: Vec<Range<usize>>,
+    #[cfg(feature = "egui")]
+    line_highlights: Vec<(usize, egui::Color32)>,
+    diff: Vec<DiffLine>,
+    #[cfg(feature = "egui")]
+    inlay_hints: Vec<InlayHint>,
+    search_matches: Vec<Range<usize>>,
+    search_active: Option<usize>,
+    max_highlight_line_len: Option<usize>,
+    #[cfg(feature = "egui")]
+    layout_hook: Option<Callback<LayoutHook>>,
+    size_limit: Option<(usize, usize)>,
+    degraded_mode: DegradedMode,
+    degraded: bool,
+    last_tokens: Arc<[Token]>,
+}
+
+#[cfg(feature = "editor")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SelectionStep {
+    Expand,
+    Shrink,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// How the user is allowed to interact with the editor.
+pub enum Interaction {
+    /// Caret, selection, copying and editing.
+    #[default]
+    Full,
+    /// Caret, selection and copying work, but the text can not be edited.
+    SelectCopy,
+    /// Text is only displayed, the caret is hidden.
+    View,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Features turned off while the text is over the [`CodeEditor::with_size_limit`] limits.
+pub struct DegradedMode {
+    /// Show the text plain, without tokenizing it.
+    pub plain_text: bool,
+    /// Keep the completion popup of [`CodeEditor::show_with_completer`] closed.
+    pub no_completion: bool,
+    /// Show a warning line above the editor.
+    pub banner: bool,
+}
+
+impl Default for DegradedMode {
+    fn default() -> Self {
+        DegradedMode {
+            plain_text: true,
+            no_completion: true,
+            banner: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Indentation found by [`CodeEditor::detect_indent`].
+pub enum IndentStyle {
+    Tabs,
+    /// Indent width in spaces
+    Spaces(usize),
+    /// Both tabs and spaces start lines
+    Mixed,
 }
 
+#[cfg(feature = "egui")]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// Annotation shown dimmed in front of a char, like a parameter name or an inferred type.
+/// It is not part of the text, so it can't be selected or edited.
+pub struct InlayHint {
+    /// Char index the hint is shown in front of, at the text length it ends the text
+    pub offset: usize,
+    pub text: String,
+}
+
+#[cfg(feature = "egui")]
 #[derive(Clone, Debug, PartialEq, Hash)]
 pub struct LineHighlight {
     pub line: usize,
     pub color: egui::Color32,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// Change of a line shown by [`CodeEditor::set_diff`].
+pub enum DiffLine {
+    Added,
+    Removed,
+    Modified,
+    #[default]
+    Unchanged,
+}
+
+#[cfg(feature = "egui")]
+impl DiffLine {
+    /// Color of the gutter stripe, `None` for unchanged lines
+    pub fn stripe(self) -> Option<egui::Color32> {
+        match self {
+            DiffLine::Added => Some(egui::Color32::from_rgb(0x3f, 0xb9, 0x50)),
+            DiffLine::Removed => Some(egui::Color32::from_rgb(0xf8, 0x51, 0x49)),
+            DiffLine::Modified => Some(egui::Color32::from_rgb(0xd2, 0x99, 0x22)),
+            DiffLine::Unchanged => None,
+        }
+    }
+
+    /// Line background, the stripe color faded
+    pub fn background(self) -> Option<egui::Color32> {
+        self.stripe().map(|color| color.gamma_multiply(0.2))
+    }
+}
+
+#[cfg(feature = "egui")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// Look of the line numbers gutter, colors left as `None` come from the theme.
+pub struct GutterStyle {
+    /// Gutter fill, theme background by default.
+    pub background: Option<egui::Color32>,
+    /// Line numbers color, theme comments color by default.
+    pub text: Option<egui::Color32>,
+    /// Draw a vertical line between the gutter and the code.
+    pub separator: bool,
+}
+
+#[cfg(feature = "egui")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Changes to the look of a token for [`format_token`], fields left as `None` come from the theme.
+pub struct TokenFormatOverrides {
+    /// Text color instead of the token type color.
+    pub color: Option<egui::Color32>,
+    pub italics: Option<bool>,
+    pub underline: Option<egui::Stroke>,
+    /// Fill behind the text instead of the token type background.
+    pub background: Option<egui::Color32>,
+}
+
+/// Output of [`CodeEditor::show`], dereferences to the inner [`TextEditOutput`]
+#[cfg(feature = "egui")]
+pub struct CodeEditorOutput {
+    pub text_edit: TextEditOutput,
+    /// Size of the laid out text, without the line numbers and frame
+    pub content_size: egui::Vec2,
+    /// Number of text lines, wrapped rows count as one
+    pub line_count: usize,
+    /// Editor is in overwrite mode
+    pub overtype: bool,
+    /// Completion accepted in [`CodeEditor::show_with_completer`] and applied this frame
+    pub completion: Option<completer::AppliedCompletion>,
+}
+
+#[cfg(feature = "egui")]
+impl CodeEditorOutput {
+    fn new(text_edit: TextEditOutput, overtype: bool) -> Self {
+        let galley = &text_edit.galley;
+        CodeEditorOutput {
+            overtype,
+            completion: None,
+            content_size: galley.size(),
+            line_count: galley
+                .rows
+                .iter()
+                .filter(|row| row.ends_with_newline)
+                .count()
+                + 1,
+            text_edit,
+        }
+    }
+}
+
+#[cfg(feature = "egui")]
+impl std::ops::Deref for CodeEditorOutput {
+    type Target = TextEditOutput;
+
+    fn deref(&self) -> &Self::Target {
+        &self.text_edit
+    }
+}
+
+#[cfg(feature = "egui")]
+impl std::ops::DerefMut for CodeEditorOutput {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.text_edit
+    }
+}
+
 #[cfg(feature = "editor")]
 impl Hash for CodeEditor {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.theme.hash(state);
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
         (self.fontsize as u32).hash(state);
+        #[cfg(feature = "egui")]
+        self.font_family.hash(state);
         self.syntax.hash(state);
+        #[cfg(feature = "egui")]
         self.highlights.hash(state);
+        self.comment_tags.hash(state);
+        self.string_delimiters.hash(state);
+        #[cfg(feature = "egui")]
+        Editor::max_highlight_line_len(self).hash(state);
     }
 }
 
@@ -145,12 +392,52 @@ impl Default for CodeEditor {
             numlines_shift: 0,
             numlines_only_natural: false,
             fontsize: 10.0,
+            #[cfg(feature = "egui")]
+            font_family: egui::FontFamily::Monospace,
             rows: 10,
             vscroll: true,
             stick_to_bottom: false,
             desired_width: f32::INFINITY,
+            #[cfg(feature = "egui")]
             highlights: Vec::new(),
-            readonly: false,
+            interaction: Interaction::Full,
+            bracket_matching: BracketMatching::default(),
+            word_wrap: false,
+            wrap_column: None,
+            wrap_indicator: false,
+            highlight_control_chars: false,
+            comment_tags: DEFAULT_COMMENT_TAGS.map(String::from).to_vec(),
+            string_delimiters: false,
+            scrolloff: 0,
+            caret_width: None,
+            caret_blink: true,
+            overtype: false,
+            auto_close: false,
+            indent: IndentStyle::Tabs,
+            #[cfg(feature = "egui")]
+            gutter_style: GutterStyle::default(),
+            active_line_number: false,
+            rich_copy: false,
+            pending_selection: None,
+            selection: None,
+            pending_step: None,
+            pending_retab: None,
+            pending_insert: None,
+            selection_steps: Vec::new(),
+            #[cfg(feature = "egui")]
+            line_highlights: Vec::new(),
+            diff: Vec::new(),
+            #[cfg(feature = "egui")]
+            inlay_hints: Vec::new(),
+            search_matches: Vec::new(),
+            search_active: None,
+            max_highlight_line_len: None,
+            #[cfg(feature = "egui")]
+            layout_hook: None,
+            size_limit: None,
+            degraded_mode: DegradedMode::default(),
+            degraded: false,
+            last_tokens: Arc::default(),
         }
     }
 }
@@ -164,8 +451,27 @@ impl CodeEditor {
         }
     }
 
+    /// Shortcut for [`Interaction::View`] or [`Interaction::Full`]
     pub fn readonly(self, readonly: bool) -> Self {
-        CodeEditor { readonly, ..self }
+        let interaction = if readonly {
+            Interaction::View
+        } else {
+            Interaction::Full
+        };
+        CodeEditor {
+            interaction,
+            ..self
+        }
+    }
+
+    /// Allowed user interaction: editing, selection and copying only or just viewing
+    ///
+    /// **Default: Interaction::Full**
+    pub fn with_interaction(self, interaction: Interaction) -> Self {
+        CodeEditor {
+            interaction,
+            ..self
+        }
     }
 
     /// Minimum number of rows to show.
@@ -189,6 +495,25 @@ impl CodeEditor {
         CodeEditor { fontsize, ..self }
     }
 
+    #[cfg(feature = "egui")]
+    /// Use custom font family and size.
+    /// Alignment of proportional fonts is not guaranteed.
+    ///
+    /// **Default: Monospace, 10.0**
+    pub fn with_font_id(self, font_id: egui::FontId) -> Self {
+        CodeEditor {
+            fontsize: font_id.size,
+            font_family: font_id.family,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Font used for the code and lines numbering
+    pub fn font_id(&self) -> egui::FontId {
+        egui::FontId::new(self.fontsize, self.font_family.clone())
+    }
+
     #[cfg(feature = "egui")]
     /// Use UI font size
     pub fn with_ui_fontsize(self, ui: &mut egui::Ui) -> Self {
@@ -232,6 +557,7 @@ impl CodeEditor {
         CodeEditor { syntax, ..self }
     }
 
+    #[cfg(feature = "egui")]
     /// Use custom line highlights
     ///
     /// **Default: Vec::new()**
@@ -239,6 +565,444 @@ impl CodeEditor {
         CodeEditor { highlights, ..self }
     }
 
+    /// Highlight bracket pair at the cursor and brackets without a pair
+    ///
+    /// **Default: both off**
+    pub fn with_bracket_matching(self, bracket_matching: BracketMatching) -> Self {
+        CodeEditor {
+            bracket_matching,
+            ..self
+        }
+    }
+
+    /// Wrap long lines at the editor width instead of scrolling horizontally
+    ///
+    /// **Default: false**
+    pub fn with_word_wrap(self, word_wrap: bool) -> Self {
+        CodeEditor { word_wrap, ..self }
+    }
+
+    /// Wrap long lines at a fixed column of chars, like 72 for commit messages,
+    /// whatever the editor width. Wider than the editor it scrolls horizontally
+    ///
+    /// **Default: None**
+    pub fn with_wrap_column(self, wrap_column: usize) -> Self {
+        CodeEditor {
+            wrap_column: Some(wrap_column),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Lines are wrapped, at the editor width or at [`Self::with_wrap_column`]
+    fn wraps(&self) -> bool {
+        self.word_wrap || self.wrap_column.is_some()
+    }
+
+    #[cfg(feature = "egui")]
+    /// Width lines wrap at, the wrap column or `available` with word wrap
+    fn wrap_width(&self, ui: &egui::Ui, available: f32) -> Option<f32> {
+        match self.wrap_column {
+            Some(column) => {
+                let advance = ui.fonts_mut(|f| f.glyph_width(&self.font_id(), '0'));
+                #[allow(clippy::cast_precision_loss)]
+                Some(column as f32 * advance)
+            }
+            None => self.word_wrap.then_some(available),
+        }
+    }
+
+    /// Draw a mark at the end of each wrapped row, works with word wrap enabled
+    ///
+    /// **Default: false**
+    pub fn with_wrap_indicator(self, wrap_indicator: bool) -> Self {
+        CodeEditor {
+            wrap_indicator,
+            ..self
+        }
+    }
+
+    /// Draw a box on a warning background in place of invisible characters,
+    /// like NUL, zero width spaces or a byte order mark
+    ///
+    /// **Default: false**
+    pub fn with_highlight_control_chars(self, highlight_control_chars: bool) -> Self {
+        CodeEditor {
+            highlight_control_chars,
+            ..self
+        }
+    }
+
+    /// Width of the caret and whether it blinks
+    ///
+    /// **Default: fontsize / 10, blinking**
+    pub fn with_caret(self, width: f32, blink: bool) -> Self {
+        CodeEditor {
+            caret_width: Some(width),
+            caret_blink: blink,
+            ..self
+        }
+    }
+
+    /// Caret width and blinking used when showing the editor
+    pub fn caret(&self) -> (f32, bool) {
+        let width = self.caret_width.unwrap_or(self.fontsize * 0.1);
+        (width, self.caret_blink)
+    }
+
+    #[cfg(feature = "egui")]
+    fn modify_caret_style(&self, style: &mut egui::Style) {
+        let (width, blink) = self.caret();
+        style.visuals.text_cursor.stroke.width = width;
+        style.visuals.text_cursor.blink = blink;
+    }
+
+    #[cfg(feature = "egui")]
+    /// Gutter background, line numbers color and separator
+    ///
+    /// **Default: theme colors, no separator**
+    pub fn with_gutter_style(self, gutter_style: GutterStyle) -> Self {
+        CodeEditor {
+            gutter_style,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Gutter look set with [`Self::with_gutter_style`]
+    pub fn gutter_style(&self) -> GutterStyle {
+        self.gutter_style
+    }
+
+    /// Show the line number of the caret line in the caret color,
+    /// without highlighting the line itself
+    ///
+    /// **Default: false**
+    pub fn with_active_line_number_highlight(self, active_line_number: bool) -> Self {
+        CodeEditor {
+            active_line_number,
+            ..self
+        }
+    }
+
+    /// Format of the gutter number of `line`, accented when it is `active_line`
+    #[cfg(feature = "egui")]
+    fn line_number_format(&self, line: usize, active_line: Option<usize>) -> egui::TextFormat {
+        let color = if self.active_line_number && active_line == Some(line) {
+            self.theme.cursor()
+        } else {
+            self.gutter_colors().1
+        };
+        egui::TextFormat::simple(self.font_id(), color)
+    }
+
+    /// Gutter background and line numbers colors with theme fallbacks
+    #[cfg(feature = "egui")]
+    pub fn gutter_colors(&self) -> (egui::Color32, egui::Color32) {
+        let style = self.gutter_style;
+        (
+            style.background.unwrap_or(self.theme.bg()),
+            style
+                .text
+                .unwrap_or(self.theme.type_color(TokenType::Comment(true))),
+        )
+    }
+
+    /// Select the char range and scroll it into view on the next [`Self::show`],
+    /// clamped to the text. Caret is placed at the end of the range
+    pub fn set_selection(&mut self, range: Range<usize>) {
+        self.pending_selection = Some(range);
+    }
+
+    /// Selected char range as of the last [`Self::show`], empty when there is only the caret
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.selection.clone()
+    }
+
+    /// Grow the selection to the next syntactic unit on the next [`Self::show`]:
+    /// word or string, inside of brackets, brackets, line, then the whole text.
+    /// Also bound to Alt+Up and Ctrl+Shift+Space.
+    pub fn expand_selection(&mut self) {
+        self.pending_step = Some(SelectionStep::Expand);
+    }
+
+    /// Undo the last [`Self::expand_selection`] on the next [`Self::show`], also bound to Alt+Down.
+    /// Does nothing once the selection was changed otherwise.
+    pub fn shrink_selection(&mut self) {
+        self.pending_step = Some(SelectionStep::Shrink);
+    }
+
+    /// Convert the leading indentation of the selected lines, or the caret line, on the next
+    /// [`Self::show`] as a single edit. Tabs are as wide as the spaces of the target or of
+    /// [`Self::with_indent`], 4 otherwise. Converting to `Mixed` or outside of
+    /// [`Interaction::Full`] does nothing.
+    pub fn retab_selection(&mut self, to: IndentStyle) {
+        self.pending_retab = Some(to);
+    }
+
+    #[cfg(feature = "egui")]
+    /// Tint whole lines, e.g. for coverage or blame. Lines are zero based and follow
+    /// the edits made in the editor, lines removed by an edit lose their tint
+    pub fn set_line_highlights(&mut self, line_highlights: Vec<(usize, egui::Color32)>) {
+        self.line_highlights = line_highlights;
+    }
+
+    #[cfg(feature = "egui")]
+    /// Tinted lines, shifted by the edits since [`Self::set_line_highlights`]
+    pub fn line_highlights(&self) -> &[(usize, egui::Color32)] {
+        &self.line_highlights
+    }
+
+    /// Tag the lines as a diff, zero based like the text lines: a stripe in the line numbers
+    /// gutter and a line background per tag, beneath the [`Self::set_line_highlights`] tints.
+    /// Tags stay on their line numbers, so show a fixed text with
+    /// [`Interaction::SelectCopy`] or [`Interaction::View`] for a read-only diff viewer
+    pub fn set_diff(&mut self, diff: Vec<DiffLine>) {
+        self.diff = diff;
+    }
+
+    /// Line tags of [`Self::set_diff`]
+    pub fn diff(&self) -> &[DiffLine] {
+        &self.diff
+    }
+
+    #[cfg(feature = "egui")]
+    /// Line backgrounds of the diff tags, then the line highlights painted over them
+    fn line_backgrounds(&self) -> impl Iterator<Item = (usize, egui::Color32)> + '_ {
+        self.diff
+            .iter()
+            .enumerate()
+            .filter_map(|(line, tag)| Some((line, tag.background()?)))
+            .chain(self.line_highlights.iter().copied())
+    }
+
+    #[cfg(feature = "egui")]
+    /// Show the hints between the chars of the text. Space is made for them in the layout
+    /// without changing the text, so char offsets of the caret and selection stay the same.
+    /// Offsets refer to the current text and are not moved by edits.
+    pub fn set_inlay_hints(&mut self, mut inlay_hints: Vec<InlayHint>) {
+        // Sorted once here, so every layout places them in a single pass
+        inlay_hints.sort_by_key(|hint| hint.offset);
+        self.inlay_hints = inlay_hints;
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn inlay_hints(&self) -> &[InlayHint] {
+        &self.inlay_hints
+    }
+
+    /// Highlight search matches, char ranges of the current text, with the backgrounds of
+    /// [`ColorTheme::search_match`] and [`ColorTheme::search_active_match`] for the match
+    /// at index `active`. Ranges are not moved by edits.
+    pub fn set_search_matches(&mut self, matches: Vec<Range<usize>>, active: Option<usize>) {
+        // Sorted once here, so every layout styles them in a single pass
+        let mut order: Vec<usize> = (0..matches.len()).collect();
+        order.sort_by_key(|&i| (matches[i].start, matches[i].end));
+        self.search_active = active.and_then(|active| order.iter().position(|&i| i == active));
+        self.search_matches = order.into_iter().map(|i| matches[i].clone()).collect();
+    }
+
+    /// Text with the leading whitespace of every line converted to `to`, counting a tab
+    /// as `tab_width` columns. Spaces short of a whole tab stay spaces when converting to tabs.
+    /// Whitespace after the first other char of a line is kept.
+    pub fn retab(text: &str, to: IndentStyle, tab_width: usize) -> String {
+        let tab_width = tab_width.max(1);
+        let mut result = String::with_capacity(text.len());
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_start_matches([' ', '\t']);
+            let indent = &line[..line.len() - content.len()];
+            let columns = indent.chars().fold(0, |column, c| match c {
+                '\t' => (column / tab_width + 1) * tab_width,
+                _ => column + 1,
+            });
+            match to {
+                IndentStyle::Tabs => {
+                    result.extend(std::iter::repeat_n('\t', columns / tab_width));
+                    result.extend(std::iter::repeat_n(' ', columns % tab_width));
+                }
+                IndentStyle::Spaces(_) => result.extend(std::iter::repeat_n(' ', columns)),
+                IndentStyle::Mixed => result.push_str(indent),
+            }
+            result.push_str(content);
+        }
+        result
+    }
+
+    /// Indentation of the text, judged by the leading whitespace of its indented lines.
+    /// The width of spaces is the most common step between the indents of neighbouring lines.
+    /// Text without indented lines gives `Spaces(4)`.
+    pub fn detect_indent(text: &str) -> IndentStyle {
+        let mut tabbed = 0;
+        let mut spaced = 0;
+        let mut steps = std::collections::BTreeMap::<usize, usize>::new();
+        let mut previous = 0;
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let spaces = indent.len() - indent.trim_start_matches(' ').len();
+            if indent.starts_with('\t') {
+                tabbed += 1;
+            } else if spaces > 0 {
+                spaced += 1;
+                if spaces > previous {
+                    *steps.entry(spaces - previous).or_default() += 1;
+                }
+            }
+            previous = spaces;
+        }
+        match (tabbed, spaced) {
+            (0, 0) => IndentStyle::Spaces(4),
+            (_, 0) => IndentStyle::Tabs,
+            (0, _) => steps
+                .into_iter()
+                .rev()
+                .max_by_key(|(_, count)| *count)
+                .map_or(IndentStyle::Spaces(4), |(width, _)| {
+                    IndentStyle::Spaces(width)
+                }),
+            _ => IndentStyle::Mixed,
+        }
+    }
+
+    /// Put highlighted HTML on the clipboard along with the plain text on copy and cut.
+    /// Needs the `rich_copy` feature, otherwise only plain text is copied
+    ///
+    /// **Default: false**
+    pub fn with_rich_copy(self, rich_copy: bool) -> Self {
+        CodeEditor { rich_copy, ..self }
+    }
+
+    /// Start in overwrite mode, where typing replaces the chars under the cursor.
+    /// Insert key toggles the mode, see [`CodeEditorOutput::overtype`].
+    ///
+    /// **Default: false**
+    pub fn with_overtype(self, overtype: bool) -> Self {
+        CodeEditor { overtype, ..self }
+    }
+
+    /// Type the closing bracket or quote along with the opening one, step over a typed closer
+    /// already after the cursor and delete both chars of an empty pair on Backspace.
+    /// Typing an opener over a selection wraps the selection in the pair.
+    ///
+    /// **Default: false**
+    pub fn with_auto_close(self, auto_close: bool) -> Self {
+        CodeEditor { auto_close, ..self }
+    }
+
+    /// Indentation typed by Tab. Keys go to the completion popup first while it shows,
+    /// then Tab indents and Shift+Tab outdents every line of a selection spanning lines,
+    /// otherwise Tab types the indent in place of the selection.
+    /// Spaces fill up to the next multiple of the width, `Mixed` types tabs.
+    ///
+    /// **Default: IndentStyle::Tabs**
+    pub fn with_indent(self, indent: IndentStyle) -> Self {
+        CodeEditor { indent, ..self }
+    }
+
+    /// Show lines longer than `max_len` bytes as plain text, without tokenizing them.
+    /// Keeps huge lines, like minified code, from slowing down every frame.
+    /// Multiline comments and strings don't continue past such a line.
+    ///
+    /// **Default: off**
+    pub fn with_max_highlight_line_len(self, max_len: usize) -> Self {
+        CodeEditor {
+            max_highlight_line_len: Some(max_len),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Call the hook with the highlighted job and the text before every layout of the editor
+    /// and [`Self::layout_only`], to add sections or change formats, like inlay hints.
+    /// Text added to the job shows in the editor, but the caret only moves over the buffer,
+    /// so keep added text at the line ends or zero width.
+    ///
+    /// # Example
+    /// ```rust
+    /// use egui_code_editor::CodeEditor;
+    ///
+    /// let editor = CodeEditor::default().with_layout_hook(|job, _text| {
+    ///     job.append("  // end", 0.0, egui::text::TextFormat::default());
+    /// });
+    /// ```
+    pub fn with_layout_hook(
+        self,
+        hook: impl Fn(&mut LayoutJob, &str) + Send + Sync + 'static,
+    ) -> Self {
+        CodeEditor {
+            layout_hook: Some(Callback(Arc::new(hook))),
+            ..self
+        }
+    }
+
+    /// Switch to a degraded mode while the text is longer than `max_bytes` or has more than
+    /// `max_lines` lines, keeping huge or untrusted input from freezing the UI.
+    /// Checked on every [`Self::show`], see [`Self::with_degraded_mode`] for what is turned off.
+    ///
+    /// **Default: off**
+    pub fn with_size_limit(self, max_bytes: usize, max_lines: usize) -> Self {
+        CodeEditor {
+            size_limit: Some((max_bytes, max_lines)),
+            ..self
+        }
+    }
+
+    /// Features turned off while the text is over the size limit
+    ///
+    /// **Default: all of them**
+    pub fn with_degraded_mode(self, degraded_mode: DegradedMode) -> Self {
+        CodeEditor {
+            degraded_mode,
+            ..self
+        }
+    }
+
+    /// Text was over the size limit on the last [`Self::show`]
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Tokens of the text as drawn on the last [`Self::show`], with the comment tags and string
+    /// delimiters split out and over long lines as one literal. Empty while it is shown plain
+    /// in the degraded mode. Collected by the highlighting pass, so reading them is cheap.
+    pub fn last_tokens(&self) -> &[Token] {
+        &self.last_tokens
+    }
+
+    #[cfg(feature = "egui")]
+    /// Text is longer than the size limit, lines are only counted under the byte limit
+    fn over_size_limit(&self, text: &str) -> bool {
+        self.size_limit.is_some_and(|(max_bytes, max_lines)| {
+            text.len() > max_bytes || text.bytes().filter(|b| *b == b'\n').count() >= max_lines
+        })
+    }
+
+    /// Minimum number of lines kept visible above and below the cursor while it moves
+    ///
+    /// **Default: 0**
+    pub fn with_scrolloff(self, scrolloff: usize) -> Self {
+        CodeEditor { scrolloff, ..self }
+    }
+
+    /// Words highlighted inside comments, empty to turn off
+    ///
+    /// **Default: TODO, FIXME, HACK, XXX**
+    pub fn with_comment_tags(self, comment_tags: Vec<String>) -> Self {
+        CodeEditor {
+            comment_tags,
+            ..self
+        }
+    }
+
+    /// Show the quotes of strings as [`TokenType::StringDelim`], colored like punctuation
+    ///
+    /// **Default: false**
+    pub fn with_string_delimiters(self, string_delimiters: bool) -> Self {
+        CodeEditor {
+            string_delimiters,
+            ..self
+        }
+    }
+
     /// Turn on/off scrolling on the vertical axis.
     ///
     /// **Default: true**
@@ -288,26 +1052,128 @@ impl CodeEditor {
             .iter()
             .find(|hl| (1.max(hl.line) - 1) == line);
 
-        format_token(&self.theme, self.fontsize, ty, highlight)
+        let mut format = format_token(&self.theme, self.fontsize, ty, None);
+        // Background of the token type stays on top of the line highlight
+        if let Some(highlight) = highlight
+            && format.background == egui::Color32::TRANSPARENT
+        {
+            format.background = highlight.color;
+        }
+        format.font_id = self.font_id();
+        format
+    }
+
+    #[cfg(feature = "egui")]
+    /// Formatting on top of syntax highlighting
+    fn decorate(&self, ctx: &egui::Context, job: &mut LayoutJob, text: &str) {
+        use highlighting::{restyle, restyle_sorted};
+
+        if !self.search_matches.is_empty() {
+            // Char offsets to bytes in one walk over the text, the matches are sorted
+            let mut bytes = text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain(std::iter::once(text.len()))
+                .enumerate()
+                .peekable();
+            let mut byte_at = |char_index: usize| {
+                while let Some(&(i, byte)) = bytes.peek() {
+                    if i >= char_index {
+                        return byte;
+                    }
+                    bytes.next();
+                }
+                text.len()
+            };
+            let mut ranges = Vec::with_capacity(self.search_matches.len());
+            let mut styled_to = 0;
+            for (i, range) in self.search_matches.iter().enumerate() {
+                // The overlap with the match before stays with it
+                let start = range.start.max(styled_to);
+                styled_to = range.end.max(start);
+                let active = self.search_active == Some(i);
+                ranges.push((byte_at(start)..byte_at(styled_to), active));
+            }
+            restyle_sorted(job, ranges, |format, active| {
+                format.background = if *active {
+                    self.theme.search_active_match()
+                } else {
+                    self.theme.search_match()
+                };
+            });
+        }
+        if self.bracket_matching.unmatched {
+            let error = self.theme.error();
+            for &at in &brackets::bracket_pairs(ctx, &self.syntax, text).unmatched {
+                restyle(job, at..at + 1, |format| {
+                    format.background = error.gamma_multiply(0.25);
+                    format.underline = egui::Stroke::new(1.0, error);
+                });
+            }
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Bracket next to the caret and its pair, as char indices
+    fn matched_brackets(
+        &self,
+        ctx: &egui::Context,
+        text: &str,
+        cursor: usize,
+    ) -> Option<(usize, usize)> {
+        use egui::TextBuffer;
+
+        let brackets = brackets::bracket_pairs(ctx, &self.syntax, text);
+        let at = text.byte_index_from_char_index(cursor);
+        let before = text[..at].char_indices().next_back().map(|(i, _)| i);
+        let (at, other) = [Some(at), before]
+            .into_iter()
+            .flatten()
+            .find_map(|at| Some((at, brackets.pair_of(at)?)))?;
+        let char_index = |at: usize| text[..at].chars().count();
+        Some((char_index(at), char_index(other)))
+    }
+
+    #[cfg(feature = "egui")]
+    /// Backgrounds are painted by `show` into a slot beneath the text edit
+    fn paints_background(&self) -> bool {
+        self.bracket_matching.pair || self.line_backgrounds().next().is_some()
     }
 
     #[cfg(feature = "egui")]
-    fn numlines_show(&self, ui: &mut egui::Ui, text: &str) {
+    fn numlines_show(&self, ui: &mut egui::Ui, id: egui::Id, text: &str) {
         use egui::TextBuffer;
 
-        let total = if text.ends_with('\n') || text.is_empty() {
+        let line_count = if text.ends_with('\n') || text.is_empty() {
             text.lines().count() + 1
         } else {
             text.lines().count()
-        }
-        .max(self.rows) as isize;
+        };
+        // Wrapped rows of the last frame, unless the text got other lines since
+        let mut lines = self
+            .wraps()
+            .then(|| ui.data(|d| d.get_temp::<Vec<bool>>(id.with("gutter_rows"))))
+            .flatten()
+            .map(|row_ends| layout::gutter_lines(&row_ends))
+            .filter(|lines| lines.iter().flatten().count() == line_count)
+            .unwrap_or_else(|| (0..line_count).map(Some).collect::<Vec<_>>());
+        lines.extend(
+            (line_count..)
+                .take(self.rows.saturating_sub(lines.len()))
+                .map(Some),
+        );
+        let total = line_count.max(lines.iter().flatten().count()) as isize;
         let max_indent = total
             .to_string()
             .len()
             .max(!self.numlines_only_natural as usize * self.numlines_shift.to_string().len());
-        let mut counter = (1..=total)
-            .map(|i| {
-                let num = i + self.numlines_shift;
+        let mut counter = lines
+            .iter()
+            .map(|&line| {
+                let Some(line) = line else {
+                    return String::new();
+                };
+                let num = line as isize + 1 + self.numlines_shift;
                 if num <= 0 && self.numlines_only_natural {
                     String::new()
                 } else {
@@ -321,80 +1187,668 @@ impl CodeEditor {
             .collect::<Vec<String>>()
             .join("\n");
 
+        let font_id = self.font_id();
+        let digit_width = ui.fonts_mut(|f| f.glyph_width(&font_id, '0'));
         #[allow(clippy::cast_precision_loss)]
         let width = max_indent as f32
-            * self.fontsize
-            * 0.5
+            * digit_width
             * !(total + self.numlines_shift <= 0 && self.numlines_only_natural) as u8 as f32;
 
+        let (background, color) = self.gutter_colors();
+        let active_line = self
+            .active_line_number
+            .then(|| egui::TextEdit::load_state(ui.ctx(), id))
+            .flatten()
+            .and_then(|state| state.cursor.char_range())
+            .map(|range| {
+                text.chars()
+                    .take(range.primary.index)
+                    .filter(|c| *c == '\n')
+                    .count()
+            });
         let mut layouter = |ui: &egui::Ui, text_buffer: &dyn TextBuffer, _wrap_width: f32| {
-            let layout_job = egui::text::LayoutJob::single_section(
-                text_buffer.as_str().to_string(),
-                egui::TextFormat::simple(
-                    egui::FontId::monospace(self.fontsize),
-                    self.theme.type_color(TokenType::Comment(true)),
-                ),
-            );
+            let mut layout_job = egui::text::LayoutJob::default();
+            for (i, (label, line)) in text_buffer.as_str().split('\n').zip(&lines).enumerate() {
+                let format = match line {
+                    Some(line) => self.line_number_format(*line, active_line),
+                    None => egui::TextFormat::simple(font_id.clone(), color),
+                };
+                let newline = if i == 0 { "" } else { "\n" };
+                layout_job.append(&format!("{newline}{label}"), 0.0, format);
+            }
             ui.fonts_mut(|f| f.layout_job(layout_job))
         };
 
-        ui.add(
-            egui::TextEdit::multiline(&mut counter)
-                .id_source(format!("{}_numlines", self.id))
-                .font(egui::TextStyle::Monospace)
-                .interactive(false)
-                .frame(false)
-                .desired_rows(self.rows)
-                .desired_width(width)
-                .layouter(&mut layouter),
-        );
+        let output = egui::Frame::NONE
+            .fill(background)
+            .show(ui, |ui| {
+                egui::TextEdit::multiline(&mut counter)
+                    .id_source(format!("{}_numlines", self.id))
+                    .font(self.font_id())
+                    .interactive(false)
+                    .frame(false)
+                    .desired_rows(self.rows)
+                    .desired_width(width)
+                    .layouter(&mut layouter)
+                    .show(ui)
+            })
+            .inner;
+        let response = output.response;
+
+        // Diff stripes at the left edge, over the wrapped rows of the line as well
+        let mut tag = DiffLine::Unchanged;
+        for (row, line) in lines.iter().enumerate() {
+            if let Some(line) = line {
+                tag = self.diff.get(*line).copied().unwrap_or_default();
+            }
+            if let Some(color) = tag.stripe()
+                && let Some(y) = layout::line_y_range(&output.galley, row)
+            {
+                let x = egui::Rangef::new(response.rect.left(), response.rect.left() + 3.0);
+                let top = output.galley_pos.y;
+                let y = egui::Rangef::new(top + y.min, top + y.max);
+                ui.painter()
+                    .rect_filled(egui::Rect::from_x_y_ranges(x, y), 0.0, color);
+            }
+        }
+
+        if self.gutter_style.separator {
+            let x = response.rect.right() + ui.spacing().item_spacing.x / 2.0;
+            ui.painter()
+                .vline(x, response.rect.y_range(), egui::Stroke::new(1.0, color));
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn text_edit_show(
+        &self,
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        text: &mut dyn egui::TextBuffer,
+    ) -> TextEditOutput {
+        use egui::TextBuffer;
+
+        let mut cursor_range =
+            egui::TextEdit::load_state(ui.ctx(), id).and_then(|state| state.cursor.char_range());
+        let mut surrounded = None;
+        let mut indented = false;
+        if self.interaction == Interaction::Full && ui.memory(|m| m.has_focus(id)) {
+            let (indent, width) = match self.indent {
+                IndentStyle::Spaces(width) => (" ".repeat(width.max(1)), width.max(1)),
+                IndentStyle::Tabs | IndentStyle::Mixed => (String::from("\t"), 4),
+            };
+            if let Some(range) = cursor_range
+                && let Some(selection) = ui.input_mut(|i| {
+                    let selection = range.as_sorted_char_range();
+                    input::tab(&mut i.events, text, selection, &indent, width)
+                })
+            {
+                self.store_selection(ui.ctx(), id, text.as_str(), selection);
+                cursor_range = egui::TextEdit::load_state(ui.ctx(), id)
+                    .and_then(|state| state.cursor.char_range());
+                indented = true;
+            }
+            let mut overtype = self.overtype_mode(ui.ctx(), id);
+            ui.input_mut(|i| input::toggle_overtype(&mut i.events, &mut overtype));
+            ui.data_mut(|d| d.insert_temp(id.with("overtype"), overtype));
+            if self.auto_close
+                && let Some(range) = cursor_range.filter(|r| !r.is_empty())
+            {
+                let selection = range.as_sorted_char_range();
+                surrounded =
+                    ui.input_mut(|i| input::surround(&mut i.events, text.as_str(), selection));
+            }
+            if let Some(range) = cursor_range.filter(|r| r.is_empty()) {
+                let text = text.as_str();
+                if overtype {
+                    ui.input_mut(|i| input::overtype(&mut i.events, text, range.primary.index));
+                } else if self.auto_close {
+                    ui.input_mut(|i| input::auto_close(&mut i.events, text, range.primary.index));
+                }
+            }
+        }
+        #[cfg(feature = "rich_copy")]
+        if self.rich_copy
+            && self.interaction != Interaction::View
+            && ui.memory(|m| m.has_focus(id))
+            && let Some(range) = cursor_range.filter(|r| !r.is_empty())
+        {
+            self.rich_copy(ui, text.as_str(), range);
+        }
+        let font_id = self.font_id();
+        let indicator_width = if self.wraps() && self.wrap_indicator {
+            ui.fonts_mut(|f| f.glyph_width(&font_id, layout::WRAP_INDICATOR))
+        } else {
+            0.0
+        };
+        let hints = self.inlay_hint_galleys(ui);
+        let hint_padding = self.fontsize * 0.25;
+        let mut layouter = |ui: &egui::Ui, text_buffer: &dyn TextBuffer, wrap_width: f32| {
+            let text = text_buffer.as_str();
+            let mut layout_job = highlight(ui.ctx(), self, text);
+            self.decorate(ui.ctx(), &mut layout_job, text);
+            // Hints are sorted by offset, so one walk over the text and the sections
+            let spaces = layout::inlay_hint_spaces(text, hints.iter().map(|(offset, _)| *offset));
+            let spaces = hints
+                .iter()
+                .zip(spaces)
+                .filter_map(|((_, galley), at)| Some((at?, galley.size().x + hint_padding * 2.0)));
+            layout::insert_spaces(&mut layout_job, spaces);
+            if let Some(hook) = &self.layout_hook {
+                (hook.0)(&mut layout_job, text);
+            }
+            if let Some(width) = self.wrap_width(ui, wrap_width - indicator_width) {
+                layout_job.wrap.max_width = width;
+            }
+            ui.fonts_mut(|f| f.layout_job(layout_job))
+        };
+        // Immutable buffer keeps caret, selection and copying, but blocks editing
+        let mut selectable: &str;
+        let buffer: &mut dyn TextBuffer = if self.interaction == Interaction::SelectCopy {
+            selectable = text.as_str();
+            &mut selectable
+        } else {
+            text
+        };
+        let mut text_edit = egui::TextEdit::multiline(buffer)
+            .id(id)
+            .lock_focus(true)
+            .desired_rows(1)
+            .interactive(self.interaction != Interaction::View)
+            .frame(true)
+            .desired_width(self.desired_width)
+            .layouter(&mut layouter);
+        if self.paints_background() {
+            // Background is painted beneath the line highlights by `show`
+            text_edit = text_edit.background_color(egui::Color32::TRANSPARENT);
+        }
+        let mut output = text_edit.show(ui);
+        if indented {
+            output.response.mark_changed();
+        }
+        if let Some(inner) = surrounded {
+            // Keep the wrapped text selected, instead of the caret after the closer
+            use egui::text::{CCursor, CCursorRange};
+            let range = CCursorRange::two(CCursor::new(inner.start), CCursor::new(inner.end));
+            output.state.cursor.set_char_range(Some(range));
+            output.state.clone().store(ui.ctx(), id);
+            output.cursor_range = Some(range);
+        }
+
+        if !hints.is_empty() {
+            let painter = ui.painter_at(output.text_clip_rect);
+            let text = output.galley.text();
+            let chars = text.chars().count();
+            let spaces = layout::inlay_hint_spaces(text, hints.iter().map(|(offset, _)| *offset));
+            for ((offset, galley), at) in hints.into_iter().zip(spaces) {
+                if offset > chars {
+                    continue;
+                }
+                let width = galley.size().x + hint_padding * 2.0;
+                let pos =
+                    layout::inlay_hint_pos_spaced(&output.galley, offset, width, at.is_some());
+                let pos = output.galley_pos + pos.to_vec2() + egui::vec2(hint_padding, 0.0);
+                painter.galley(pos, galley, egui::Color32::PLACEHOLDER);
+            }
+        }
+        if self.highlight_control_chars {
+            self.paint_control_chars(ui, &output);
+        }
+        if self.wraps() && self.wrap_indicator {
+            let painter = ui.painter_at(
+                output
+                    .text_clip_rect
+                    .expand2(egui::vec2(indicator_width, 0.0)),
+            );
+            let color = self.theme.type_color(TokenType::Comment(false));
+            for pos in layout::wrap_indicator_positions(&output.galley) {
+                painter.text(
+                    output.galley_pos + pos.to_vec2(),
+                    egui::Align2::LEFT_CENTER,
+                    layout::WRAP_INDICATOR,
+                    font_id.clone(),
+                    color,
+                );
+            }
+        }
+        output
+    }
+
+    /// Writes the selection as plain text and HTML to the system clipboard on copy or cut,
+    /// instead of the plain text only clipboard of egui. Cut deletes the selection afterwards.
+    #[cfg(feature = "rich_copy")]
+    fn rich_copy(&self, ui: &egui::Ui, text: &str, range: egui::text::CCursorRange) {
+        use egui::{Event, TextBuffer};
+
+        let copy = ui.input(|i| {
+            i.events
+                .iter()
+                .any(|e| matches!(e, Event::Copy | Event::Cut))
+        });
+        if !copy {
+            return;
+        }
+        let [start, end] = range
+            .sorted_cursors()
+            .map(|c| text.byte_index_from_char_index(c.index));
+        let html = export::html_range(text, start..end, &self.syntax, &self.theme);
+        let written = arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_html(html.as_str(), Some(&text[start..end])));
+        if written.is_err() {
+            // Let egui copy the plain text
+            return;
+        }
+        let editable = self.interaction == Interaction::Full;
+        ui.input_mut(|i| {
+            i.events = std::mem::take(&mut i.events)
+                .into_iter()
+                .filter_map(|event| match event {
+                    Event::Copy => None,
+                    Event::Cut if editable => Some(Event::Key {
+                        key: egui::Key::Delete,
+                        physical_key: None,
+                        pressed: true,
+                        repeat: false,
+                        modifiers: egui::Modifiers::NONE,
+                    }),
+                    Event::Cut => None,
+                    event => Some(event),
+                })
+                .collect();
+        });
     }
 
     #[cfg(feature = "egui")]
-    /// Show Code Editor with auto-completion feature
+    /// Laid out inlay hints with their char offsets, dimmed and in italics
+    fn inlay_hint_galleys(&self, ui: &egui::Ui) -> Vec<(usize, Arc<egui::Galley>)> {
+        let format = egui::text::TextFormat {
+            font_id: self.font_id(),
+            color: self.theme.type_color(TokenType::Comment(false)),
+            italics: true,
+            ..Default::default()
+        };
+        self.inlay_hints
+            .iter()
+            .map(|hint| {
+                let job = LayoutJob::single_section(hint.text.clone(), format.clone());
+                (hint.offset, ui.fonts_mut(|f| f.layout_job(job)))
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "egui")]
+    /// Text edit background with full width line highlights and the bracket pair at the
+    /// caret on top, beneath the text
+    fn paint_backgrounds(
+        &self,
+        ui: &egui::Ui,
+        background: egui::layers::ShapeIdx,
+        output: &TextEditOutput,
+    ) {
+        let rect = output.response.rect;
+        let mut shapes = vec![egui::Shape::rect_filled(
+            rect,
+            ui.visuals().widgets.inactive.corner_radius,
+            // Theme style is set on the inner ui only, not on this one
+            self.theme.bg(),
+        )];
+        for (line, color) in self.line_backgrounds() {
+            if let Some(y) = layout::line_y_range(&output.galley, line) {
+                let top = output.galley_pos.y;
+                let y = egui::Rangef::new(top + y.min, top + y.max);
+                let line_rect = egui::Rect::from_x_y_ranges(rect.x_range(), y);
+                shapes.push(egui::Shape::rect_filled(line_rect, 0.0, color));
+            }
+        }
+        // Caret after this frame's edits and moves, so the pair does not lag behind
+        if self.bracket_matching.pair
+            && let Some(range) = output.cursor_range
+            && let Some((at, other)) =
+                self.matched_brackets(ui.ctx(), output.galley.text(), range.primary.index)
+        {
+            use egui::text::CCursor;
+
+            let color = self.theme.selection().gamma_multiply(0.5);
+            for at in [at, other] {
+                let cursor = CCursor::new(at);
+                let layout = output.galley.layout_from_cursor(cursor);
+                let Some(glyph) = output.galley.rows[layout.row].glyphs.get(layout.column) else {
+                    continue;
+                };
+                let mut rect = output.galley.pos_from_cursor(cursor);
+                rect.max.x += glyph.advance_width;
+                shapes.push(egui::Shape::rect_filled(
+                    rect.translate(output.galley_pos.to_vec2()),
+                    0.0,
+                    color,
+                ));
+            }
+        }
+        ui.painter()
+            .with_clip_rect(output.text_clip_rect.union(rect).intersect(ui.clip_rect()))
+            .set(background, shapes);
+    }
+
+    #[cfg(feature = "egui")]
+    /// Boxes in place of the invisible characters of the text
+    fn paint_control_chars(&self, ui: &egui::Ui, output: &TextEditOutput) {
+        let chars = highlighting::invisible_chars(output.galley.text());
+        if chars.is_empty() {
+            return;
+        }
+        let width = ui.fonts_mut(|f| f.glyph_width(&self.font_id(), '0')) * 0.6;
+        let error = self.theme.error();
+        let painter = ui.painter_at(output.text_clip_rect);
+        for at in chars {
+            let cursor = output.galley.pos_from_cursor(egui::text::CCursor::new(at));
+            let rect = egui::Rect::from_center_size(
+                cursor.center(),
+                egui::vec2(width, cursor.height() * 0.7),
+            )
+            .translate(output.galley_pos.to_vec2());
+            painter.rect(
+                rect,
+                1.0,
+                error.gamma_multiply(0.3),
+                egui::Stroke::new(1.0, error),
+                egui::StrokeKind::Inside,
+            );
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    fn store_selection(&self, ctx: &egui::Context, id: egui::Id, text: &str, range: Range<usize>) {
+        use egui::text::{CCursor, CCursorRange};
+
+        let len = text.chars().count();
+        let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+        state.cursor.set_char_range(Some(CCursorRange::two(
+            CCursor::new(range.start.min(len)),
+            CCursor::new(range.end.min(len)),
+        )));
+        egui::TextEdit::store_state(ctx, id, state);
+        ctx.memory_mut(|m| m.request_focus(id));
+    }
+
+    #[cfg(feature = "egui")]
+    /// Retabs the whole lines touched by the char range `selection`, returns their new range
+    fn retab_lines(
+        &self,
+        text: &mut dyn egui::TextBuffer,
+        selection: Range<usize>,
+        to: IndentStyle,
+    ) -> Range<usize> {
+        let chars = text.as_str().chars().collect::<Vec<_>>();
+        // Stored cursor may be left from a longer text
+        let selection = selection.start.min(chars.len())..selection.end.min(chars.len());
+        // A selection ending at the start of a line leaves that line out
+        let last = if selection.end > selection.start && chars[selection.end - 1] == '\n' {
+            selection.end - 1
+        } else {
+            selection.end
+        };
+        let start = chars[..selection.start]
+            .iter()
+            .rposition(|c| *c == '\n')
+            .map_or(0, |i| i + 1);
+        let end = chars[last..]
+            .iter()
+            .position(|c| *c == '\n')
+            .map_or(chars.len(), |i| last + i);
+        let tab_width = match (to, self.indent) {
+            (IndentStyle::Spaces(width), _) | (_, IndentStyle::Spaces(width)) => width,
+            _ => 4,
+        };
+        let lines = chars[start..end].iter().collect::<String>();
+        let retabbed = Self::retab(&lines, to, tab_width);
+        if retabbed != lines {
+            text.delete_char_range(start..end);
+            text.insert_text(&retabbed, start);
+        }
+        start..start + retabbed.chars().count()
+    }
+
+    #[cfg(feature = "egui")]
+    /// Char range after growing or shrinking `current`, `steps` keeps the grown selections
+    fn step_selection(
+        &self,
+        steps: &mut Vec<Range<usize>>,
+        text: &str,
+        current: Range<usize>,
+        step: SelectionStep,
+    ) -> Option<Range<usize>> {
+        use egui::TextBuffer;
+
+        if steps.last() != Some(&current) {
+            steps.clear();
+            steps.push(current.clone());
+        }
+        match step {
+            SelectionStep::Expand => {
+                let bytes = text.byte_index_from_char_index(current.start)
+                    ..text.byte_index_from_char_index(current.end);
+                let grown = selection::expand_range(&self.syntax, text, bytes)?;
+                let chars = text[..grown.start].chars().count()..text[..grown.end].chars().count();
+                steps.push(chars.clone());
+                Some(chars)
+            }
+            SelectionStep::Shrink if steps.len() > 1 => {
+                steps.pop();
+                steps.last().cloned()
+            }
+            SelectionStep::Shrink => None,
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Overwrite mode of the editor with text edit `id`, toggled by Insert key
+    fn overtype_mode(&self, ctx: &egui::Context, id: egui::Id) -> bool {
+        ctx.data(|d| d.get_temp(id.with("overtype")))
+            .unwrap_or(self.overtype)
+    }
+
+    #[cfg(feature = "egui")]
+    /// Scrolls the cursor into view with the scrolloff margin,
+    /// `force` also scrolls without focus or margin
+    fn keep_scrolloff(&self, ui: &egui::Ui, output: &TextEditOutput, force: bool) {
+        if !force && (self.scrolloff == 0 || !output.response.has_focus()) {
+            return;
+        }
+        let Some(range) = output.cursor_range else {
+            return;
+        };
+        let cursor = output
+            .galley
+            .pos_from_cursor(range.primary)
+            .translate(output.galley_pos.to_vec2());
+        let row_height = ui.fonts_mut(|f| f.row_height(&self.font_id()));
+        let viewport = ui.clip_rect();
+        let delta = layout::scrolloff_delta(cursor, viewport, self.scrolloff as f32 * row_height);
+        if delta != 0.0 {
+            // scroll_to_rect stops item spacing short of the edge
+            let spacing = ui.spacing().item_spacing.y * delta.signum();
+            ui.scroll_to_rect(viewport.translate(egui::vec2(0.0, delta + spacing)), None);
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    /// Highlighted, non-interactive layout of `text` without a [`egui::TextEdit`],
+    /// cheaper for tooltips and previews. Paint it with [`egui::Label`] or the painter.
+    /// Wraps at the available width with [`Self::with_word_wrap`], or at the wrap column.
+    pub fn layout_only(&self, ui: &egui::Ui, text: &str) -> Arc<egui::Galley> {
+        let mut layout_job = highlight(ui.ctx(), self, text);
+        if let Some(hook) = &self.layout_hook {
+            (hook.0)(&mut layout_job, text);
+        }
+        if let Some(width) = self.wrap_width(ui, ui.available_width()) {
+            layout_job.wrap.max_width = width;
+        }
+        ui.fonts_mut(|f| f.layout_job(layout_job))
+    }
+
+    #[cfg(feature = "egui")]
+    /// Show Code Editor with auto-completion feature, only with [`Interaction::Full`]
     pub fn show_with_completer(
         &mut self,
         ui: &mut egui::Ui,
         text: &mut dyn egui::TextBuffer,
         completer: &mut Completer,
-    ) -> TextEditOutput {
+    ) -> CodeEditorOutput {
+        // Read-only text gets no completions, keys go to the editor
+        if self.interaction != Interaction::Full
+            || (self.degraded_mode.no_completion && self.over_size_limit(text.as_str()))
+        {
+            completer.reset();
+            return self.show(ui, text);
+        }
         completer.handle_input(ui.ctx());
+        // Inserted by `show` along with the completion, laid out and tracked like typed edits
+        if let Some((offset, edit, caret)) = completer.additional_edit_first() {
+            let offset = offset.min(text.as_str().chars().count());
+            completer.shift_tabstops(offset, edit.chars().count() as isize);
+            self.pending_insert = Some((offset, edit));
+            self.pending_selection = Some(caret..caret);
+        }
         let mut editor_output = self.show(ui, text);
-        completer.show(&self.syntax, &self.theme, self.fontsize, &mut editor_output);
+        completer.editor_tokens = Some(self.last_tokens.clone());
+        completer.show_with_font(
+            &self.syntax,
+            &self.theme,
+            self.font_id(),
+            &mut editor_output,
+        );
+        editor_output.completion = completer.applied().cloned();
         editor_output
     }
 
     #[cfg(feature = "egui")]
     /// Show Code Editor
-    pub fn show(&mut self, ui: &mut egui::Ui, text: &mut dyn egui::TextBuffer) -> TextEditOutput {
-        use egui::TextBuffer;
-
+    ///
+    /// `text` can be any [`egui::TextBuffer`], not only a `String`: a rope or shared document
+    /// implementing the trait is edited in place, highlighted and completed from its `as_str`
+    /// view without being copied into a `String` each frame.
+    ///
+    /// Right-to-left text is laid out left to right within its line, as egui does without
+    /// bidi support. Line numbers stay on the left and char offsets still map to the glyphs.
+    pub fn show(&mut self, ui: &mut egui::Ui, text: &mut dyn egui::TextBuffer) -> CodeEditorOutput {
+        self.degraded = self.over_size_limit(text.as_str());
+        if self.degraded && self.degraded_mode.banner {
+            let text = text.as_str();
+            let lines = text.bytes().filter(|b| *b == b'\n').count() + 1;
+            ui.colored_label(
+                self.theme.error(),
+                format!(
+                    "Text is too large ({} bytes, {lines} lines), some editor features are off",
+                    text.len()
+                ),
+            );
+        }
         let mut text_edit_output: Option<TextEditOutput> = None;
+        let selected = self.pending_selection.take();
+        let mut step = self.pending_step.take();
+        let retab = self.pending_retab.take();
+        let mut edited = false;
+        let mut insert = self.pending_insert.take();
+        let mut steps = std::mem::take(&mut self.selection_steps);
+        // Shape slot beneath the text edit, and old text for the line highlights to follow edits.
+        // Copied only on frames with input that may edit the text
+        let may_edit = insert.is_some()
+            || retab.is_some()
+            || ui.input(|i| {
+                i.events.iter().any(|event| {
+                    matches!(
+                        event,
+                        egui::Event::Text(_)
+                            | egui::Event::Paste(_)
+                            | egui::Event::Cut
+                            | egui::Event::Ime(_)
+                            | egui::Event::Key { pressed: true, .. }
+                    )
+                })
+            });
+        let tinted = self.paints_background().then(|| {
+            let old_text =
+                (may_edit && !self.line_highlights.is_empty()).then(|| text.as_str().to_owned());
+            (ui.painter().add(egui::Shape::Noop), old_text)
+        });
         let mut code_editor = |ui: &mut egui::Ui| {
             ui.horizontal_top(|h| {
                 self.theme.modify_style(h, self.fontsize);
+                h.style_mut().override_font_id = Some(self.font_id());
+                self.modify_caret_style(h.style_mut());
+                let id = h.make_persistent_id(&self.id);
+                if let Some((offset, insert)) = insert.take()
+                    && self.interaction == Interaction::Full
+                {
+                    text.insert_text(&insert, offset);
+                    edited = true;
+                }
                 if self.numlines {
-                    self.numlines_show(h, text.as_str());
+                    self.numlines_show(h, id, text.as_str());
                 }
-                egui::ScrollArea::horizontal()
-                    .id_salt(format!("{}_inner_scroll", self.id))
-                    .show(h, |ui| {
-                        let mut layouter =
-                            |ui: &egui::Ui, text_buffer: &dyn TextBuffer, _wrap_width: f32| {
-                                let layout_job = highlight(ui.ctx(), self, text_buffer.as_str());
-                                ui.fonts_mut(|f| f.layout_job(layout_job))
-                            };
-                        let output = egui::TextEdit::multiline(text)
-                            .id_source(&self.id)
-                            .lock_focus(true)
-                            .desired_rows(1)
-                            .interactive(!self.readonly)
-                            .frame(true)
-                            .desired_width(self.desired_width)
-                            .layouter(&mut layouter)
-                            .show(ui);
-                        text_edit_output = Some(output);
+                if let Some(range) = selected.clone() {
+                    self.store_selection(h.ctx(), id, text.as_str(), range);
+                }
+                if h.memory(|m| m.has_focus(id)) && self.interaction != Interaction::View {
+                    h.input_mut(|i| {
+                        let ctrl_shift = egui::Modifiers::CTRL | egui::Modifiers::SHIFT;
+                        if i.consume_key(egui::Modifiers::ALT, egui::Key::ArrowUp)
+                            || i.consume_key(ctrl_shift, egui::Key::Space)
+                        {
+                            step = Some(SelectionStep::Expand);
+                        } else if i.consume_key(egui::Modifiers::ALT, egui::Key::ArrowDown) {
+                            step = Some(SelectionStep::Shrink);
+                        }
                     });
+                }
+                if let Some(step) = step
+                    && let Some(current) = egui::TextEdit::load_state(h.ctx(), id)
+                        .and_then(|state| state.cursor.char_range())
+                    && let Some(range) = self.step_selection(
+                        &mut steps,
+                        text.as_str(),
+                        current.as_sorted_char_range(),
+                        step,
+                    )
+                {
+                    self.store_selection(h.ctx(), id, text.as_str(), range);
+                }
+                if let Some(to) = retab
+                    && self.interaction == Interaction::Full
+                    && let Some(current) = egui::TextEdit::load_state(h.ctx(), id)
+                        .and_then(|state| state.cursor.char_range())
+                {
+                    let lines = self.retab_lines(text, current.as_sorted_char_range(), to);
+                    self.store_selection(h.ctx(), id, text.as_str(), lines);
+                    edited = true;
+                }
+                let cursor = egui::TextEdit::load_state(h.ctx(), id)
+                    .and_then(|state| state.cursor.char_range());
+                let output = if self.word_wrap {
+                    self.text_edit_show(h, id, text)
+                } else {
+                    egui::ScrollArea::horizontal()
+                        .id_salt(format!("{}_inner_scroll", self.id))
+                        .show(h, |ui| self.text_edit_show(ui, id, text))
+                        .inner
+                };
+                if self.wraps() && self.numlines {
+                    // Gutter follows the wrapped rows from the next frame on
+                    let row_ends = output.galley.rows.iter().map(|row| row.ends_with_newline);
+                    let row_ends = row_ends.collect::<Vec<_>>();
+                    let key = id.with("gutter_rows");
+                    if h.data(|d| d.get_temp::<Vec<bool>>(key)).as_ref() != Some(&row_ends) {
+                        h.data_mut(|d| d.insert_temp(key, row_ends));
+                        h.ctx().request_repaint();
+                    }
+                }
+                // Outside of the horizontal ScrollArea, which would swallow the vertical target
+                if selected.is_some() {
+                    self.keep_scrolloff(h, &output, true);
+                } else if output.cursor_range != cursor || output.response.changed() {
+                    self.keep_scrolloff(h, &output, false);
+                }
+                text_edit_output = Some(output);
             });
         };
         if self.vscroll {
@@ -406,7 +1860,36 @@ impl CodeEditor {
             code_editor(ui);
         }
 
-        text_edit_output.expect("TextEditOutput should exist at this point")
+        let mut output = text_edit_output.expect("TextEditOutput should exist at this point");
+        if edited {
+            output.response.mark_changed();
+        }
+        self.selection_steps = steps;
+        if let Some((background, old_text)) = tinted {
+            if output.response.changed()
+                && let Some(old_text) = old_text
+            {
+                let new_text = text.as_str();
+                self.line_highlights = std::mem::take(&mut self.line_highlights)
+                    .into_iter()
+                    .filter_map(|(line, color)| {
+                        layout::shift_line(&old_text, new_text, line).map(|line| (line, color))
+                    })
+                    .collect();
+            }
+            self.paint_backgrounds(ui, background, &output);
+        }
+        self.selection = output
+            .cursor_range
+            .map(|range| range.as_sorted_char_range());
+        self.last_tokens = if self.degraded && self.degraded_mode.plain_text {
+            Arc::default()
+        } else {
+            // Same frame and key as the layouter, so the cached pass, not a second lexing
+            highlighting::highlight_with_tokens(ui.ctx(), self, text.as_str()).1
+        };
+        let overtype = self.overtype_mode(ui.ctx(), output.response.id);
+        CodeEditorOutput::new(output, overtype)
     }
 }
 
@@ -414,30 +1897,76 @@ impl CodeEditor {
 #[cfg(feature = "egui")]
 impl Editor for CodeEditor {
     fn append(&self, job: &mut LayoutJob, token: &Token, line: usize) {
-        if !token.buffer().is_empty() {
-            job.append(token.buffer(), 0.0, self.format_token(token.ty(), line));
+        if token.buffer().is_empty() {
+            return;
+        }
+        let format = self.format_token(token.ty(), line);
+        // Runs drawn alike share a section, fewer sections to lay out
+        if let Some(last) = job.sections.last_mut()
+            && last.format == format
+            && last.byte_range.end == job.text.len()
+        {
+            job.text.push_str(token.buffer());
+            last.byte_range.end = job.text.len();
+        } else {
+            job.append(token.buffer(), 0.0, format);
+        }
+    }
+
+    fn split(&self, token: Token) -> Vec<Token> {
+        if matches!(token.ty(), TokenType::Comment(_) | TokenType::DocComment(_))
+            && !self.comment_tags.is_empty()
+        {
+            highlighting::split_comment_tags(token, &self.comment_tags)
+        } else if matches!(token.ty(), TokenType::Str(_)) && self.string_delimiters {
+            highlighting::split_string_delims(token)
+        } else {
+            vec![token]
         }
     }
 
     fn syntax(&self) -> &Syntax {
         &self.syntax
     }
+
+    fn max_highlight_line_len(&self) -> Option<usize> {
+        // Every line counts as too long, so none is tokenized
+        if self.degraded && self.degraded_mode.plain_text {
+            Some(0)
+        } else {
+            self.max_highlight_line_len
+        }
+    }
 }
 
 #[cfg(feature = "egui")]
+/// Text format of a token as the editor draws it, for reuse in exports or custom widgets.
+/// `overrides` take precedence over the theme.
 pub fn format_token(
     theme: &ColorTheme,
     fontsize: f32,
     ty: TokenType,
-    highlight: Option<&LineHighlight>,
+    overrides: Option<TokenFormatOverrides>,
 ) -> egui::text::TextFormat {
     let font_id = egui::FontId::monospace(fontsize);
     let color = theme.type_color(ty);
 
     let mut format = egui::text::TextFormat::simple(font_id, color);
+    format.background = theme.type_background(ty);
 
-    if let Some(highlight) = highlight {
-        format.background = highlight.color;
+    if let Some(overrides) = overrides {
+        if let Some(color) = overrides.color {
+            format.color = color;
+        }
+        if let Some(italics) = overrides.italics {
+            format.italics = italics;
+        }
+        if let Some(underline) = overrides.underline {
+            format.underline = underline;
+        }
+        if let Some(background) = overrides.background {
+            format.background = background;
+        }
     }
 
     format
@@ -0,0 +1,79 @@
+use crate::{Syntax, Token, TokenType};
+use std::ops::Range;
+
+/// Keywords introducing a function definition.
+const FUNCTION_KEYWORDS: [&str; 3] = ["fn", "def", "function"];
+/// Keywords introducing a type definition.
+const TYPE_KEYWORDS: [&str; 7] = [
+    "struct",
+    "enum",
+    "trait",
+    "union",
+    "type",
+    "class",
+    "interface",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Type,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// Definition found in the text, `line` is zero based and `range` is the name in bytes.
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub line: usize,
+    pub range: Range<usize>,
+}
+
+fn definition_kind(token: &Token) -> Option<SymbolKind> {
+    if token.ty() != TokenType::Keyword {
+        return None;
+    }
+    let word = token.buffer().to_ascii_lowercase();
+    if FUNCTION_KEYWORDS.contains(&word.as_str()) {
+        Some(SymbolKind::Function)
+    } else if TYPE_KEYWORDS.contains(&word.as_str()) {
+        Some(SymbolKind::Type)
+    } else {
+        None
+    }
+}
+
+/// Function and type definitions: a definition keyword of the syntax (`fn`, `def`, `class`,
+/// `struct`, ...) followed by a name.
+pub fn outline(text: &str, syntax: &Syntax) -> Vec<Symbol> {
+    let mut symbols = vec![];
+    let mut pending: Option<SymbolKind> = None;
+    let mut offset = 0;
+    let mut line = 0;
+    for token in Token::default().tokens(syntax, text) {
+        let start = offset;
+        offset += token.buffer().len();
+        match token.ty() {
+            TokenType::Whitespace(c) => {
+                if c == '\n' {
+                    line += 1;
+                }
+                continue;
+            }
+            TokenType::Literal | TokenType::Function | TokenType::Type => {
+                if let Some(kind) = pending {
+                    symbols.push(Symbol {
+                        name: token.buffer().to_string(),
+                        kind,
+                        line,
+                        range: start..offset,
+                    });
+                }
+            }
+            _ => {}
+        }
+        pending = definition_kind(&token);
+        line += token.buffer().matches('\n').count();
+    }
+    symbols
+}
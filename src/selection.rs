@@ -0,0 +1,47 @@
+use crate::brackets::BracketPairs;
+use crate::{Syntax, Token, TokenType};
+use std::ops::Range;
+
+/// Smallest syntactic unit strictly containing the byte `range`: a word, string or comment,
+/// the inside of brackets, the brackets themselves, the trimmed line, the whole line
+/// and at last the whole text. `None` when the whole text is already selected.
+pub fn expand_range(syntax: &Syntax, text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let mut units: Vec<Range<usize>> = vec![];
+    units.push(0..text.len());
+
+    let mut offset = 0;
+    for token in Token::default().tokens(syntax, text) {
+        let len = token.buffer().len();
+        match token.ty() {
+            // Brackets come from the pairs below
+            TokenType::Whitespace(_) | TokenType::Punctuation(_) => {}
+            TokenType::Str(_) => {
+                if len >= 2 {
+                    units.push(offset + 1..offset + len - 1);
+                }
+                units.push(offset..offset + len);
+            }
+            _ => units.push(offset..offset + len),
+        }
+        offset += len;
+    }
+
+    for (open, close) in BracketPairs::new(syntax, text).pairs {
+        units.push(open + 1..close);
+        units.push(open..close + 1);
+    }
+
+    let mut start = 0;
+    for line in text.split('\n') {
+        let end = start + line.len();
+        let indent = line.len() - line.trim_start().len();
+        units.push(start + indent..start + line.trim_end().len());
+        units.push(start..end);
+        start = end + 1;
+    }
+
+    units
+        .into_iter()
+        .filter(|unit| unit.start <= range.start && range.end <= unit.end && *unit != range)
+        .min_by_key(|unit| unit.len())
+}
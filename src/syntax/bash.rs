@@ -0,0 +1,33 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn bash() -> Self {
+        Syntax {
+            language: "Bash",
+            case_sensitive: true,
+            comment: "#",
+            line_comments: &[],
+            comment_multiline: ["#"; 2],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Indent,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::from([
+                "break", "case", "continue", "declare", "do", "done", "elif", "else", "esac",
+                "exit", "export", "fi", "for", "function", "if", "in", "local", "readonly",
+                "return", "select", "then", "until", "while",
+            ]),
+            // Builtins and common commands
+            types: BTreeSet::from([
+                "awk", "cat", "cd", "echo", "grep", "ls", "mkdir", "printf", "pwd", "read", "rm",
+                "sed", "source", "test",
+            ]),
+            special: BTreeSet::from(["false", "true"]),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn css() -> Self {
+        Syntax {
+            language: "CSS",
+            case_sensitive: false,
+            // Block comments only
+            comment: "",
+            line_comments: &[],
+            comment_multiline: ["/*", "*/"],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: true,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            // At-rules, without the `@`
+            keywords: BTreeSet::from([
+                "CHARSET",
+                "CONTAINER",
+                "IMPORT",
+                "IMPORTANT",
+                "KEYFRAMES",
+                "LAYER",
+                "MEDIA",
+                "NAMESPACE",
+                "PAGE",
+                "SUPPORTS",
+            ]),
+            // Properties, hyphenated ones highlight by their parts
+            types: BTreeSet::from([
+                "ALIGN",
+                "ANIMATION",
+                "BACKGROUND",
+                "BORDER",
+                "BOTTOM",
+                "COLOR",
+                "CONTENT",
+                "CURSOR",
+                "DISPLAY",
+                "FILTER",
+                "FLEX",
+                "FLOAT",
+                "FONT",
+                "GAP",
+                "GRID",
+                "HEIGHT",
+                "LEFT",
+                "MARGIN",
+                "OPACITY",
+                "OUTLINE",
+                "OVERFLOW",
+                "PADDING",
+                "POSITION",
+                "RIGHT",
+                "TOP",
+                "TRANSFORM",
+                "TRANSITION",
+                "VISIBILITY",
+                "WIDTH",
+                "Z",
+            ]),
+            // Global and common values
+            special: BTreeSet::from([
+                "AUTO",
+                "INHERIT",
+                "INITIAL",
+                "NONE",
+                "REVERT",
+                "TRANSPARENT",
+                "UNSET",
+            ]),
+        }
+    }
+}
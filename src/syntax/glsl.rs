@@ -0,0 +1,93 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn glsl() -> Syntax {
+        Syntax {
+            language: "GLSL",
+            case_sensitive: true,
+            comment: "//",
+            line_comments: &[],
+            comment_multiline: ["/*", "*/"],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::from([
+                "attribute",
+                "break",
+                "case",
+                "centroid",
+                "const",
+                "continue",
+                "default",
+                "discard",
+                "do",
+                "else",
+                "flat",
+                "for",
+                "highp",
+                "if",
+                "in",
+                "inout",
+                "invariant",
+                "layout",
+                "lowp",
+                "mediump",
+                "noperspective",
+                "out",
+                "precision",
+                "return",
+                "smooth",
+                "struct",
+                "switch",
+                "uniform",
+                "varying",
+                "while",
+            ]),
+            types: BTreeSet::from([
+                "bool",
+                "bvec2",
+                "bvec3",
+                "bvec4",
+                "double",
+                "dvec2",
+                "dvec3",
+                "dvec4",
+                "float",
+                "int",
+                "ivec2",
+                "ivec3",
+                "ivec4",
+                "mat2",
+                "mat3",
+                "mat4",
+                "sampler2D",
+                "sampler3D",
+                "samplerCube",
+                "uint",
+                "uvec2",
+                "uvec3",
+                "uvec4",
+                "vec2",
+                "vec3",
+                "vec4",
+                "void",
+            ]),
+            special: BTreeSet::from([
+                "false",
+                "gl_FragColor",
+                "gl_FragCoord",
+                "gl_FragDepth",
+                "gl_PointSize",
+                "gl_Position",
+                "gl_VertexID",
+                "true",
+            ]),
+        }
+    }
+}
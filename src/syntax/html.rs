@@ -0,0 +1,53 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn html() -> Self {
+        Syntax {
+            language: "HTML",
+            case_sensitive: false,
+            comment: "",
+            line_comments: &[],
+            comment_multiline: ["<!--", "-->"],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["HTTP"]),
+            // Tag names
+            keywords: BTreeSet::from([
+                "A", "ARTICLE", "BODY", "BR", "BUTTON", "CANVAS", "CODE", "DIV", "DOCTYPE",
+                "FOOTER", "FORM", "H1", "H2", "H3", "H4", "H5", "H6", "HEAD", "HEADER", "HR",
+                "HTML", "IMG", "INPUT", "LABEL", "LI", "LINK", "MAIN", "META", "NAV", "OL",
+                "OPTION", "P", "PRE", "SCRIPT", "SECTION", "SELECT", "SPAN", "STYLE", "TABLE",
+                "TBODY", "TD", "TEXTAREA", "TH", "THEAD", "TITLE", "TR", "UL",
+            ]),
+            // Attribute names
+            types: BTreeSet::from([
+                "ALT",
+                "CHARSET",
+                "CHECKED",
+                "CLASS",
+                "CONTENT",
+                "DISABLED",
+                "FOR",
+                "HEIGHT",
+                "HREF",
+                "ID",
+                "LANG",
+                "NAME",
+                "PLACEHOLDER",
+                "REL",
+                "SRC",
+                "TARGET",
+                "TYPE",
+                "VALUE",
+                "WIDTH",
+            ]),
+            special: BTreeSet::new(),
+        }
+    }
+}
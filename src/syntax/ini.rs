@@ -0,0 +1,26 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    /// INI and `.properties` files, `[section]` headers and `key = value` pairs are plain text.
+    pub fn ini() -> Self {
+        Syntax {
+            language: "INI",
+            case_sensitive: false,
+            comment: ";",
+            line_comments: &["#"],
+            comment_multiline: ["", ""],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::new(),
+            types: BTreeSet::new(),
+            special: BTreeSet::from(["FALSE", "NO", "OFF", "ON", "TRUE", "YES"]),
+        }
+    }
+}
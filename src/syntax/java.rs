@@ -0,0 +1,62 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn java() -> Syntax {
+        Syntax {
+            language: "Java",
+            case_sensitive: true,
+            comment: "//",
+            line_comments: &[],
+            comment_multiline: ["/*", "*/"],
+            doc_comment: None,
+            doc_comment_multiline: Some(["/**", "*/"]),
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::from([
+                "abstract",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "continue",
+                "default",
+                "do",
+                "else",
+                "enum",
+                "extends",
+                "final",
+                "finally",
+                "for",
+                "if",
+                "implements",
+                "import",
+                "instanceof",
+                "interface",
+                "new",
+                "package",
+                "private",
+                "protected",
+                "public",
+                "return",
+                "static",
+                "switch",
+                "synchronized",
+                "throw",
+                "throws",
+                "try",
+                "void",
+                "while",
+            ]),
+            types: BTreeSet::from([
+                "boolean", "byte", "char", "double", "float", "int", "long", "short", "Boolean",
+                "Integer", "List", "Map", "Object", "String",
+            ]),
+            special: BTreeSet::from(["false", "null", "super", "this", "true"]),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::Syntax;
+use super::{FoldStyle, Syntax};
 use std::collections::BTreeSet;
 
 impl Syntax {
@@ -7,7 +7,15 @@ impl Syntax {
             language: "JavaScript",
             case_sensitive: true,
             comment: "//",
+            line_comments: &[],
             comment_multiline: ["/*", "*/"],
+            doc_comment: None,
+            doc_comment_multiline: Some(["/**", "*/"]),
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
             hyperlinks: BTreeSet::from(["http"]),
             keywords: BTreeSet::from([
                 // Control flow
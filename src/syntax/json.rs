@@ -0,0 +1,36 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    /// JSON, object keys are lexed as types and string values as strings.
+    pub fn json() -> Self {
+        Syntax {
+            language: "JSON",
+            case_sensitive: true,
+            comment: "",
+            line_comments: &[],
+            comment_multiline: ["", ""],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: true,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::new(),
+            keywords: BTreeSet::new(),
+            types: BTreeSet::new(),
+            special: BTreeSet::from(["false", "null", "true"]),
+        }
+    }
+
+    /// JSON with `//` and `/* */` comments, like VS Code settings.
+    pub fn jsonc() -> Self {
+        Syntax {
+            language: "JSONC",
+            comment: "//",
+            comment_multiline: ["/*", "*/"],
+            ..Syntax::json()
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn kotlin() -> Syntax {
+        Syntax {
+            language: "Kotlin",
+            case_sensitive: true,
+            comment: "//",
+            line_comments: &[],
+            comment_multiline: ["/*", "*/"],
+            doc_comment: None,
+            doc_comment_multiline: Some(["/**", "*/"]),
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::from([
+                "abstract",
+                "as",
+                "break",
+                "catch",
+                "class",
+                "companion",
+                "continue",
+                "data",
+                "do",
+                "else",
+                "enum",
+                "finally",
+                "for",
+                "fun",
+                "if",
+                "import",
+                "in",
+                "init",
+                "interface",
+                "internal",
+                "is",
+                "lateinit",
+                "object",
+                "open",
+                "override",
+                "package",
+                "private",
+                "protected",
+                "public",
+                "return",
+                "sealed",
+                "suspend",
+                "throw",
+                "try",
+                "typealias",
+                "val",
+                "var",
+                "when",
+                "while",
+            ]),
+            types: BTreeSet::from([
+                "Any",
+                "Array",
+                "Boolean",
+                "Byte",
+                "Char",
+                "Double",
+                "Float",
+                "Int",
+                "List",
+                "Long",
+                "Map",
+                "MutableList",
+                "MutableMap",
+                "Nothing",
+                "Set",
+                "Short",
+                "String",
+                "Unit",
+            ]),
+            special: BTreeSet::from(["false", "it", "null", "super", "this", "true"]),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::Syntax;
+use super::{FoldStyle, Syntax};
 use std::collections::BTreeSet;
 
 impl Syntax {
@@ -7,7 +7,15 @@ impl Syntax {
             language: "Lua",
             case_sensitive: true,
             comment: "--",
+            line_comments: &[],
             comment_multiline: ["--[[", "]]"],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Indent,
+            version: 0,
             hyperlinks: BTreeSet::from(["http"]),
             keywords: BTreeSet::from([
                 "and", "break", "do", "else", "elseif", "end", "for", "function", "if", "in",
@@ -16,7 +24,7 @@ impl Syntax {
             types: BTreeSet::from([
                 "boolean", "number", "string", "function", "userdata", "thread", "table",
             ]),
-            special: BTreeSet::from(["false", "nil", "true"]),
+            special: BTreeSet::from(["false", "nil", "self", "true"]),
         }
     }
 }
@@ -1,14 +1,25 @@
 #![allow(dead_code)]
 pub mod asm;
+pub mod bash;
+pub mod css;
+pub mod glsl;
+pub mod html;
+pub mod ini;
+pub mod java;
 pub mod javascript;
+pub mod json;
+pub mod kotlin;
 pub mod lua;
 pub mod python;
+pub mod ruby;
 pub mod rust;
 pub mod shell;
 pub mod sql;
+pub mod swift;
+pub mod typescript;
 
 use std::collections::BTreeSet;
-use std::hash::{Hash, Hasher};
+use std::hash::{DefaultHasher, Hash, Hasher};
 
 pub const SEPARATORS: [char; 1] = ['_'];
 pub const QUOTES: [char; 3] = ['\'', '"', '`'];
@@ -16,10 +27,14 @@ pub const QUOTES: [char; 3] = ['\'', '"', '`'];
 type MultiLine = bool;
 type Float = bool;
 
-#[derive(Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
 pub enum TokenType {
     Comment(MultiLine),
+    DocComment(MultiLine),
+    /// Attention tag inside a comment, like `TODO`
+    CommentTag,
     Function,
     Keyword,
     Literal,
@@ -28,6 +43,8 @@ pub enum TokenType {
     Punctuation(char),
     Special,
     Str(char),
+    /// Opening or closing quote of a string, with [`crate::CodeEditor::with_string_delimiters`]
+    StringDelim(char),
     Type,
     Whitespace(char),
     #[default]
@@ -47,6 +64,15 @@ impl std::fmt::Debug for TokenType {
                     }
                 }
             }
+            TokenType::DocComment(multiline) => {
+                name.push_str("DocComment");
+                if *multiline {
+                    name.push_str(" MultiLine");
+                } else {
+                    name.push_str(" SingleLine");
+                }
+            }
+            TokenType::CommentTag => name.push_str("CommentTag"),
             TokenType::Function => name.push_str("Function"),
             TokenType::Keyword => name.push_str("Keyword"),
             TokenType::Literal => name.push_str("Literal"),
@@ -65,6 +91,10 @@ impl std::fmt::Debug for TokenType {
                 name.push_str("Str ");
                 name.push(*quote);
             }
+            TokenType::StringDelim(quote) => {
+                name.push_str("StringDelim ");
+                name.push(*quote);
+            }
             TokenType::Type => name.push_str("Type"),
             TokenType::Whitespace(c) => {
                 name.push_str("Whitespace");
@@ -93,13 +123,42 @@ impl From<char> for TokenType {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// How [`crate::folding::fold_ranges`] finds the blocks of a language.
+pub enum FoldStyle {
+    /// From the line of a `{` to the line of its `}`
+    #[default]
+    Braces,
+    /// From a line over the following lines indented deeper, like Python blocks
+    Indent,
+}
+
 #[derive(Clone, Debug, PartialEq)]
-/// Rules for highlighting.
+/// Rules for highlighting. Hashing covers only the language name and [`Self::version`],
+/// so highlight cache lookups stay cheap.
+#[non_exhaustive]
 pub struct Syntax {
     pub language: &'static str,
     pub case_sensitive: bool,
     pub comment: &'static str,
+    /// Single line comment markers besides `comment`, like `#` next to `;` in INI
+    pub line_comments: &'static [&'static str],
     pub comment_multiline: [&'static str; 2],
+    /// Single line documentation comment, should start with `comment`, like `///`
+    pub doc_comment: Option<&'static str>,
+    /// Multiline documentation comment, should start with `comment_multiline`, like `/**`
+    pub doc_comment_multiline: Option<[&'static str; 2]>,
+    /// Multiline comments can contain other ones, like `/* outer /* inner */ still comment */`
+    pub nested_comments: bool,
+    /// Strings followed by `:` are keys lexed as [`TokenType::Type`], like JSON object keys
+    pub key_strings: bool,
+    /// Letters right after a number stay in its token, like CSS units `1.5rem`
+    pub unit_suffixes: bool,
+    pub fold_style: FoldStyle,
+    /// Fingerprint of the rules, `0` for the built-in syntaxes. The `with_*` builders and
+    /// [`Syntax::extend`] update it, change it after editing the other fields in place
+    /// so the editor doesn't reuse highlighting of the old rules.
+    pub version: u64,
     pub hyperlinks: BTreeSet<&'static str>,
     pub keywords: BTreeSet<&'static str>,
     pub types: BTreeSet<&'static str>,
@@ -113,6 +172,7 @@ impl Default for Syntax {
 impl Hash for Syntax {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.language.hash(state);
+        self.version.hash(state);
     }
 }
 impl Syntax {
@@ -127,39 +187,127 @@ impl Syntax {
             case_sensitive,
             ..self
         }
+        .revised()
     }
     pub fn with_comment(self, comment: &'static str) -> Self {
-        Syntax { comment, ..self }
+        Syntax { comment, ..self }.revised()
+    }
+    pub fn with_line_comments(self, line_comments: &'static [&'static str]) -> Self {
+        Syntax {
+            line_comments,
+            ..self
+        }
+        .revised()
     }
     pub fn with_comment_multiline(self, comment_multiline: [&'static str; 2]) -> Self {
         Syntax {
             comment_multiline,
             ..self
         }
+        .revised()
+    }
+    pub fn with_doc_comment(self, doc_comment: &'static str) -> Self {
+        Syntax {
+            doc_comment: Some(doc_comment),
+            ..self
+        }
+        .revised()
+    }
+    pub fn with_doc_comment_multiline(self, doc_comment_multiline: [&'static str; 2]) -> Self {
+        Syntax {
+            doc_comment_multiline: Some(doc_comment_multiline),
+            ..self
+        }
+        .revised()
+    }
+    pub fn with_nested_comments(self, nested_comments: bool) -> Self {
+        Syntax {
+            nested_comments,
+            ..self
+        }
+        .revised()
+    }
+    pub fn with_key_strings(self, key_strings: bool) -> Self {
+        Syntax {
+            key_strings,
+            ..self
+        }
+        .revised()
+    }
+    pub fn with_unit_suffixes(self, unit_suffixes: bool) -> Self {
+        Syntax {
+            unit_suffixes,
+            ..self
+        }
+        .revised()
+    }
+    pub fn with_fold_style(self, fold_style: FoldStyle) -> Self {
+        Syntax { fold_style, ..self }.revised()
     }
     pub fn with_hyperlinks<T: Into<BTreeSet<&'static str>>>(self, hyperlinks: T) -> Self {
         Syntax {
             hyperlinks: hyperlinks.into(),
             ..self
         }
+        .revised()
     }
     pub fn with_keywords<T: Into<BTreeSet<&'static str>>>(self, keywords: T) -> Self {
         Syntax {
             keywords: keywords.into(),
             ..self
         }
+        .revised()
     }
     pub fn with_types<T: Into<BTreeSet<&'static str>>>(self, types: T) -> Self {
         Syntax {
             types: types.into(),
             ..self
         }
+        .revised()
     }
     pub fn with_special<T: Into<BTreeSet<&'static str>>>(self, special: T) -> Self {
         Syntax {
             special: special.into(),
             ..self
         }
+        .revised()
+    }
+    /// New syntax on top of this one: word sets are merged,
+    /// comment markers are replaced only when the extension sets them.
+    pub fn extend(&self, extra: SyntaxExtension) -> Syntax {
+        let mut syntax = self.clone();
+        syntax.language = extra.language.unwrap_or(self.language);
+        syntax.comment = extra.comment.unwrap_or(self.comment);
+        syntax.comment_multiline = extra.comment_multiline.unwrap_or(self.comment_multiline);
+        syntax.doc_comment = extra.doc_comment.or(self.doc_comment);
+        syntax.doc_comment_multiline = extra.doc_comment_multiline.or(self.doc_comment_multiline);
+        syntax.hyperlinks.extend(extra.hyperlinks);
+        syntax.keywords.extend(extra.keywords);
+        syntax.types.extend(extra.types);
+        syntax.special.extend(extra.special);
+        syntax.revised()
+    }
+
+    /// Sets [`Self::version`] to a fingerprint of every other rule
+    fn revised(mut self) -> Self {
+        let mut state = DefaultHasher::new();
+        self.language.hash(&mut state);
+        self.case_sensitive.hash(&mut state);
+        self.comment.hash(&mut state);
+        self.line_comments.hash(&mut state);
+        self.comment_multiline.hash(&mut state);
+        self.doc_comment.hash(&mut state);
+        self.doc_comment_multiline.hash(&mut state);
+        self.nested_comments.hash(&mut state);
+        self.key_strings.hash(&mut state);
+        self.unit_suffixes.hash(&mut state);
+        self.fold_style.hash(&mut state);
+        self.hyperlinks.hash(&mut state);
+        self.keywords.hash(&mut state);
+        self.types.hash(&mut state);
+        self.special.hash(&mut state);
+        self.version = state.finish();
+        self
     }
 
     pub fn language(&self) -> &str {
@@ -168,6 +316,24 @@ impl Syntax {
     pub fn comment(&self) -> &str {
         self.comment
     }
+    /// `comment` and the other [`Self::line_comments`], leaving out empty markers
+    pub fn line_comment_markers(&self) -> impl Iterator<Item = &'static str> + '_ {
+        std::iter::once(self.comment)
+            .chain(self.line_comments.iter().copied())
+            .filter(|marker| !marker.is_empty())
+    }
+    /// No comments nor word sets, nothing to highlight
+    pub fn is_plain(&self) -> bool {
+        self.comment.is_empty()
+            && self.line_comments.is_empty()
+            && self.comment_multiline.iter().all(|m| m.is_empty())
+            && self.doc_comment.is_none()
+            && self.doc_comment_multiline.is_none()
+            && self.hyperlinks.is_empty()
+            && self.keywords.is_empty()
+            && self.types.is_empty()
+            && self.special.is_empty()
+    }
     pub fn is_hyperlink(&self, word: &str) -> bool {
         self.hyperlinks.contains(word.to_ascii_lowercase().as_str())
     }
@@ -194,17 +360,132 @@ impl Syntax {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Additions for [`Syntax::extend`], for dialects built on a base syntax.
+pub struct SyntaxExtension {
+    pub language: Option<&'static str>,
+    pub comment: Option<&'static str>,
+    pub comment_multiline: Option<[&'static str; 2]>,
+    pub doc_comment: Option<&'static str>,
+    pub doc_comment_multiline: Option<[&'static str; 2]>,
+    pub hyperlinks: BTreeSet<&'static str>,
+    pub keywords: BTreeSet<&'static str>,
+    pub types: BTreeSet<&'static str>,
+    pub special: BTreeSet<&'static str>,
+}
+impl SyntaxExtension {
+    pub fn new(language: &'static str) -> Self {
+        SyntaxExtension {
+            language: Some(language),
+            ..Default::default()
+        }
+    }
+    pub fn with_comment(self, comment: &'static str) -> Self {
+        SyntaxExtension {
+            comment: Some(comment),
+            ..self
+        }
+    }
+    pub fn with_comment_multiline(self, comment_multiline: [&'static str; 2]) -> Self {
+        SyntaxExtension {
+            comment_multiline: Some(comment_multiline),
+            ..self
+        }
+    }
+    pub fn with_doc_comment(self, doc_comment: &'static str) -> Self {
+        SyntaxExtension {
+            doc_comment: Some(doc_comment),
+            ..self
+        }
+    }
+    pub fn with_doc_comment_multiline(self, doc_comment_multiline: [&'static str; 2]) -> Self {
+        SyntaxExtension {
+            doc_comment_multiline: Some(doc_comment_multiline),
+            ..self
+        }
+    }
+    pub fn with_hyperlinks<T: Into<BTreeSet<&'static str>>>(self, hyperlinks: T) -> Self {
+        SyntaxExtension {
+            hyperlinks: hyperlinks.into(),
+            ..self
+        }
+    }
+    pub fn with_keywords<T: Into<BTreeSet<&'static str>>>(self, keywords: T) -> Self {
+        SyntaxExtension {
+            keywords: keywords.into(),
+            ..self
+        }
+    }
+    pub fn with_types<T: Into<BTreeSet<&'static str>>>(self, types: T) -> Self {
+        SyntaxExtension {
+            types: types.into(),
+            ..self
+        }
+    }
+    pub fn with_special<T: Into<BTreeSet<&'static str>>>(self, special: T) -> Self {
+        SyntaxExtension {
+            special: special.into(),
+            ..self
+        }
+    }
+}
+
 impl Syntax {
     pub fn simple(comment: &'static str) -> Self {
         Syntax {
             language: "",
             case_sensitive: false,
             comment,
+            line_comments: &[],
             comment_multiline: [comment; 2],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
             hyperlinks: BTreeSet::new(),
             keywords: BTreeSet::new(),
             types: BTreeSet::new(),
             special: BTreeSet::new(),
         }
+        .revised()
+    }
+
+    /// Plain text without highlighting, every line is a single literal
+    pub fn none() -> Self {
+        Syntax {
+            language: "Plain Text",
+            ..Syntax::simple("")
+        }
+    }
+
+    /// Built-in syntax for a file extension (without the dot), case-insensitive.
+    /// `None` when the extension is unknown.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        let syntax = match ext.to_ascii_lowercase().as_str() {
+            "asm" | "s" | "nasm" => Syntax::asm(),
+            "css" => Syntax::css(),
+            "glsl" | "vert" | "frag" | "geom" | "comp" => Syntax::glsl(),
+            "html" | "htm" => Syntax::html(),
+            "ini" | "cfg" | "properties" => Syntax::ini(),
+            "java" => Syntax::java(),
+            "json" => Syntax::json(),
+            "jsonc" => Syntax::jsonc(),
+            "js" | "mjs" | "cjs" | "jsx" => Syntax::javascript(),
+            "kt" | "kts" => Syntax::kotlin(),
+            "lua" => Syntax::lua(),
+            "py" | "pyw" | "pyi" => Syntax::python(),
+            "rb" => Syntax::ruby(),
+            "rs" => Syntax::rust(),
+            "bash" => Syntax::bash(),
+            "sh" | "zsh" => Syntax::shell(),
+            "sql" => Syntax::sql(),
+            "swift" => Syntax::swift(),
+            "ts" | "mts" | "cts" | "tsx" => Syntax::typescript(),
+            _ => return None,
+        };
+        Some(syntax)
     }
 }
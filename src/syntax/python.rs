@@ -1,4 +1,4 @@
-use super::Syntax;
+use super::{FoldStyle, Syntax};
 use std::collections::BTreeSet;
 
 impl Syntax {
@@ -7,7 +7,15 @@ impl Syntax {
             language: "Python",
             case_sensitive: true,
             comment: "#",
+            line_comments: &[],
             comment_multiline: [r#"'''"#, r#"'''"#],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Indent,
+            version: 0,
             hyperlinks: BTreeSet::from(["http"]),
             keywords: BTreeSet::from([
                 "and", "as", "assert", "break", "class", "continue", "def", "del", "elif", "else",
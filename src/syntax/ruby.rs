@@ -0,0 +1,32 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn ruby() -> Syntax {
+        Syntax {
+            language: "Ruby",
+            case_sensitive: true,
+            comment: "#",
+            line_comments: &[],
+            comment_multiline: ["=begin", "=end"],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Indent,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::from([
+                "alias", "and", "begin", "break", "case", "class", "def", "do", "else", "elsif",
+                "end", "ensure", "for", "if", "in", "module", "next", "not", "or", "raise",
+                "require", "rescue", "retry", "return", "then", "unless", "until", "when", "while",
+                "yield",
+            ]),
+            types: BTreeSet::from([
+                "Array", "Float", "Hash", "Integer", "Proc", "Range", "String", "Symbol",
+            ]),
+            special: BTreeSet::from(["__FILE__", "false", "nil", "self", "super", "true"]),
+        }
+    }
+}
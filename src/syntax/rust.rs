@@ -1,4 +1,4 @@
-use super::Syntax;
+use super::{FoldStyle, Syntax};
 use std::collections::BTreeSet;
 
 impl Syntax {
@@ -7,7 +7,15 @@ impl Syntax {
             language: "Rust",
             case_sensitive: true,
             comment: "//",
+            line_comments: &[],
             comment_multiline: ["/*", "*/"],
+            doc_comment: Some("///"),
+            doc_comment_multiline: Some(["/**", "*/"]),
+            nested_comments: true,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
             hyperlinks: BTreeSet::from(["http"]),
             keywords: BTreeSet::from([
                 "as", "break", "const", "continue", "crate", "else", "enum", "extern", "fn", "for",
@@ -1,4 +1,4 @@
-use super::Syntax;
+use super::{FoldStyle, Syntax};
 use std::collections::BTreeSet;
 
 impl Syntax {
@@ -7,6 +7,7 @@ impl Syntax {
             language: "Shell",
             case_sensitive: true,
             comment: "#",
+            line_comments: &[],
             hyperlinks: BTreeSet::from(["http"]),
             keywords: BTreeSet::from([
                 "echo", "read", "set", "unset", "readonly", "shift", "export", "if", "fi", "else",
@@ -14,6 +15,13 @@ impl Syntax {
                 "return", "trap", "wait", "eval", "exec", "ulimit", "umask",
             ]),
             comment_multiline: [": '", "'"],
+            doc_comment: None,
+            doc_comment_multiline: None,
+            nested_comments: false,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
             types: BTreeSet::from([
                 "ENV",
                 "HOME",
@@ -0,0 +1,77 @@
+use super::{FoldStyle, Syntax};
+use std::collections::BTreeSet;
+
+impl Syntax {
+    pub fn swift() -> Syntax {
+        Syntax {
+            language: "Swift",
+            case_sensitive: true,
+            comment: "//",
+            line_comments: &[],
+            comment_multiline: ["/*", "*/"],
+            doc_comment: Some("///"),
+            doc_comment_multiline: Some(["/**", "*/"]),
+            nested_comments: true,
+            key_strings: false,
+            unit_suffixes: false,
+            fold_style: FoldStyle::Braces,
+            version: 0,
+            hyperlinks: BTreeSet::from(["http"]),
+            keywords: BTreeSet::from([
+                "as",
+                "async",
+                "await",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "continue",
+                "default",
+                "defer",
+                "do",
+                "else",
+                "enum",
+                "extension",
+                "fallthrough",
+                "for",
+                "func",
+                "guard",
+                "if",
+                "import",
+                "in",
+                "init",
+                "is",
+                "let",
+                "private",
+                "protocol",
+                "public",
+                "repeat",
+                "return",
+                "static",
+                "struct",
+                "switch",
+                "throw",
+                "throws",
+                "try",
+                "var",
+                "where",
+                "while",
+            ]),
+            types: BTreeSet::from([
+                "Any",
+                "Array",
+                "Bool",
+                "Character",
+                "Dictionary",
+                "Double",
+                "Float",
+                "Int",
+                "Optional",
+                "Set",
+                "String",
+                "Void",
+            ]),
+            special: BTreeSet::from(["false", "nil", "self", "Self", "super", "true"]),
+        }
+    }
+}
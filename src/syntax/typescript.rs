@@ -0,0 +1,30 @@
+use super::{Syntax, SyntaxExtension};
+
+impl Syntax {
+    pub fn typescript() -> Syntax {
+        Syntax::javascript().extend(
+            SyntaxExtension::new("TypeScript")
+                .with_keywords([
+                    "abstract",
+                    "declare",
+                    "enum",
+                    "implements",
+                    "infer",
+                    "interface",
+                    "is",
+                    "keyof",
+                    "namespace",
+                    "private",
+                    "protected",
+                    "public",
+                    "readonly",
+                    "satisfies",
+                    "type",
+                ])
+                .with_types([
+                    "any", "bigint", "boolean", "never", "number", "object", "string", "symbol",
+                    "unknown", "void",
+                ]),
+        )
+    }
+}
@@ -63,3 +63,3718 @@ fn simple_rust() {
     println!("{str}");
     assert_eq!(input, output);
 }
+
+#[test]
+fn unterminated_str_diagnostic() {
+    let (tokens, diagnostics) =
+        Token::default().tokens_with_diagnostics(&Syntax::rust(), "\"unterminated");
+    assert_eq!(tokens, [Token::new(TokenType::Str('"'), "\"unterminated")]);
+    assert_eq!(
+        diagnostics,
+        [LexDiagnostic {
+            kind: LexDiagnosticKind::UnterminatedStr('"'),
+            range: 0..13,
+        }]
+    );
+
+    let (_, diagnostics) =
+        Token::default().tokens_with_diagnostics(&Syntax::rust(), "let a = 1; /* open\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, LexDiagnosticKind::UnterminatedComment);
+    assert_eq!(diagnostics[0].range.start, 11);
+
+    let (_, diagnostics) =
+        Token::default().tokens_with_diagnostics(&Syntax::rust(), "\"closed\" /* ok */");
+    assert!(diagnostics.is_empty());
+}
+
+fn raw_input(events: Vec<egui::Event>) -> egui::RawInput {
+    egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::vec2(800.0, 600.0),
+        )),
+        events,
+        ..Default::default()
+    }
+}
+
+fn frame<R>(
+    ctx: &egui::Context,
+    events: Vec<egui::Event>,
+    mut add_contents: impl FnMut(&mut egui::Ui) -> R,
+) -> R {
+    let mut result = None;
+    let _ = ctx.run(raw_input(events), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| result = Some(add_contents(ui)));
+    });
+    result.expect("frame should produce a result")
+}
+
+fn key(key: egui::Key, modifiers: egui::Modifiers) -> egui::Event {
+    egui::Event::Key {
+        key,
+        physical_key: None,
+        pressed: true,
+        repeat: false,
+        modifiers,
+    }
+}
+
+fn set_cursor(ctx: &egui::Context, id: egui::Id, range: std::ops::Range<usize>) {
+    use egui::text::{CCursor, CCursorRange};
+    let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+    state.cursor.set_char_range(Some(CCursorRange::two(
+        CCursor::new(range.start),
+        CCursor::new(range.end),
+    )));
+    egui::TextEdit::store_state(ctx, id, state);
+}
+
+/// Shows the editor once and gives it keyboard focus for the following frames.
+fn focused_editor(ctx: &egui::Context, editor: &mut CodeEditor, text: &mut String) -> egui::Id {
+    let id = frame(ctx, vec![], |ui| editor.show(ui, text).response.id);
+    ctx.memory_mut(|m| m.request_focus(id));
+    id
+}
+
+#[test]
+fn interaction_select_copy() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_interaction(Interaction::SelectCopy);
+    let mut text = String::from("let x = 1;");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+
+    set_cursor(&ctx, id, 3..3);
+    frame(&ctx, vec![egui::Event::Text("y".into())], |ui| {
+        editor.show(ui, &mut text);
+    });
+    assert_eq!(text, "let x = 1;");
+
+    let output = frame(
+        &ctx,
+        vec![key(egui::Key::ArrowRight, egui::Modifiers::SHIFT)],
+        |ui| editor.show(ui, &mut text),
+    );
+    let range = output.cursor_range.expect("selection should be kept");
+    assert_eq!((range.secondary.index, range.primary.index), (3, 4));
+    assert_eq!(text, "let x = 1;");
+}
+
+#[test]
+fn interaction_select_copy_no_completion() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_interaction(Interaction::SelectCopy);
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    let mut text = String::from("whi");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 3);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(!completer.is_open());
+
+    // Arrows move the caret instead of the popup selection
+    let left = vec![key(egui::Key::ArrowLeft, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, left);
+    assert_eq!(output.cursor_range.map(|c| c.primary.index), Some(2));
+    assert_eq!(text, "whi");
+}
+
+/// Frame with completer, same as `CodeEditor::show_with_completer`.
+fn completer_frame(
+    ctx: &egui::Context,
+    editor: &mut CodeEditor,
+    completer: &mut Completer,
+    text: &mut String,
+    events: Vec<egui::Event>,
+) -> CodeEditorOutput {
+    frame(ctx, events, |ui| {
+        editor.show_with_completer(ui, text, completer)
+    })
+}
+
+/// Places the cursor and lets the completer pick up the prefix in front of it.
+fn completer_at(
+    ctx: &egui::Context,
+    editor: &mut CodeEditor,
+    completer: &mut Completer,
+    text: &mut String,
+    cursor: usize,
+) -> egui::Id {
+    let id = focused_editor(ctx, editor, text);
+    set_cursor(ctx, id, cursor..cursor);
+    completer_frame(ctx, editor, completer, text, vec![]);
+    id
+}
+
+#[test]
+fn snippet_escaped_dollar() {
+    use completer::custom_types::{CompType, CompletionItem};
+
+    let item = CompletionItem::with_snippet("price", "price = \\$$amount", CompType::Snippet);
+    assert_eq!(item.cursor_info(), ("price = $amount".to_string(), Some(9)));
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_snippet(
+        "price",
+        "price = \\$$amount",
+        CompType::Snippet,
+    );
+    let mut text = String::from("pric");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    let output = completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, "price = $amount");
+    assert_eq!(output.cursor_range.map(|c| c.primary.index), Some(9));
+}
+
+#[test]
+fn custom_font_id() {
+    let font_id = egui::FontId::proportional(17.0);
+    let editor = CodeEditor::default().with_font_id(font_id.clone());
+    assert_eq!(editor.font_id(), font_id);
+    assert_eq!(editor.format_token(TokenType::Keyword, 0).font_id, font_id);
+
+    let ctx = egui::Context::default();
+    let mut editor = editor.with_rows(3);
+    let mut text = String::from("fn main() {\n\tlet x = 1;\n}");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+}
+
+#[test]
+fn theme_builder_overrides_single_color() {
+    let base = ColorTheme::AYU;
+    let theme = base
+        .builder()
+        .name("Custom")
+        .color(TokenType::Keyword, "#ff0000")
+        .build();
+    assert_eq!(theme.type_color(TokenType::Keyword), egui::Color32::RED);
+    assert_eq!(theme.name(), "Custom");
+    assert_eq!(theme.bg(), base.bg());
+    for ty in [
+        TokenType::Comment(false),
+        TokenType::Function,
+        TokenType::Literal,
+        TokenType::Hyperlink,
+        TokenType::Numeric(true),
+        TokenType::Punctuation(';'),
+        TokenType::Special,
+        TokenType::Str('"'),
+        TokenType::Type,
+        TokenType::Whitespace(' '),
+        TokenType::Unknown,
+    ] {
+        assert_eq!(theme.type_color(ty), base.type_color(ty), "{ty:?}");
+    }
+}
+
+fn displays(completer: &Completer) -> Vec<&str> {
+    completer
+        .current_completions()
+        .iter()
+        .map(|(display, _)| display.as_str())
+        .collect()
+}
+
+#[test]
+fn completer_filter_predicate() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_simple("load", CompType::Function)
+        .with_global_simple("load_deprecated", CompType::Function)
+        .with_global_simple("loader", CompType::Function)
+        .with_global_simple("lock_deprecated", CompType::Function)
+        .with_filter(|display, _| !display.ends_with("_deprecated"));
+    let mut text = String::from("lo");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["load", "loop", "loader"]);
+
+    // Runs again with the same prefix, following the state it reads
+    let hide_loader = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let hidden = hide_loader.clone();
+    let mut completer = completer.with_filter(move |display, _| {
+        let hide = display == "loader" && hidden.load(std::sync::atomic::Ordering::Relaxed);
+        !display.ends_with("_deprecated") && !hide
+    });
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["load", "loop", "loader"]);
+    hide_loader.store(true, std::sync::atomic::Ordering::Relaxed);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["load", "loop"]);
+}
+
+#[test]
+fn bracket_pair_follows_caret_same_frame() {
+    fn pair_rects(shapes: &[egui::Shape], color: egui::Color32) -> usize {
+        shapes
+            .iter()
+            .map(|shape| match shape {
+                egui::Shape::Vec(shapes) => pair_rects(shapes, color),
+                egui::Shape::Rect(rect) => usize::from(rect.fill == color),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_bracket_matching(BracketMatching {
+        pair: true,
+        unmatched: false,
+    });
+    let color = editor.theme.selection().gamma_multiply(0.5);
+    let mut text = String::from("ab (c)");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    let mut show = |events| {
+        let output = ctx.run(raw_input(events), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| editor.show(ui, &mut text));
+        });
+        let shapes: Vec<_> = output.shapes.into_iter().map(|s| s.shape).collect();
+        pair_rects(&shapes, color)
+    };
+
+    set_cursor(&ctx, id, 0..0);
+    assert_eq!(show(vec![]), 0);
+    // The frame moving the caret next to `)` already shows the pair
+    assert_eq!(show(vec![key(egui::Key::End, egui::Modifiers::NONE)]), 2);
+    assert_eq!(show(vec![key(egui::Key::Home, egui::Modifiers::NONE)]), 0);
+}
+
+#[test]
+fn unmatched_brackets_detected() {
+    use brackets::{BracketPairs, unmatched_brackets};
+
+    // `)` closes `(`, only the stray `{` between them is flagged
+    assert_eq!(unmatched_brackets(&Syntax::rust(), "({)"), [1]);
+    assert_eq!(unmatched_brackets(&Syntax::rust(), "(]"), [0, 1]);
+    assert_eq!(unmatched_brackets(&Syntax::rust(), "f(a[0]) }"), [8]);
+    // Brackets inside strings and comments are not code
+    assert!(unmatched_brackets(&Syntax::rust(), "f(\")\") // (").is_empty());
+
+    let brackets = BracketPairs::new(&Syntax::rust(), "{ (x) }");
+    assert_eq!(brackets.pair_of(0), Some(6));
+    assert_eq!(brackets.pair_of(4), Some(2));
+    assert_eq!(brackets.pair_of(3), None);
+    let brackets = BracketPairs::new(&Syntax::rust(), "({)");
+    assert_eq!(brackets.pair_of(0), Some(2));
+}
+
+#[test]
+fn restyle_splits_sections() {
+    let syntax = Syntax::rust();
+    let editor = CodeEditor::default().with_syntax(syntax);
+    let mut job = Token::default().highlight(&editor, "fn f() {");
+    highlighting::restyle(&mut job, 4..5, |format| {
+        format.background = egui::Color32::RED
+    });
+    let red = job
+        .sections
+        .iter()
+        .filter(|s| s.format.background == egui::Color32::RED)
+        .map(|s| (s.byte_range.start, s.byte_range.end))
+        .collect::<Vec<_>>();
+    assert_eq!(red, [(4, 5)]);
+    assert_eq!(job.sections.last().map(|s| s.byte_range.end), Some(8));
+}
+
+#[test]
+fn wrap_indicator_at_wrapped_rows() {
+    let ctx = egui::Context::default();
+    frame(&ctx, vec![], |_| ());
+    let font_id = egui::FontId::monospace(10.0);
+    let galley = ctx.fonts_mut(|f| {
+        let char_width = f.glyph_width(&font_id, 'a');
+        let mut job = egui::text::LayoutJob::single_section(
+            "aaaa bbbb cccc\nshort".to_string(),
+            egui::TextFormat::simple(font_id.clone(), egui::Color32::WHITE),
+        );
+        job.wrap.max_width = char_width * 10.5;
+        f.layout_job(job)
+    });
+    assert_eq!(galley.rows.len(), 3);
+    let positions = layout::wrap_indicator_positions(&galley);
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].x, galley.rows[0].rect().right());
+    assert!(galley.rows[0].rect().y_range().contains(positions[0].y));
+
+    let mut editor = CodeEditor::default()
+        .with_word_wrap(true)
+        .with_wrap_indicator(true);
+    let mut text = "word ".repeat(400);
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert!(output.galley.rows.len() > 1);
+}
+
+#[test]
+fn wrap_column_rows() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_wrap_column(10);
+    // No spaces to break at, so rows of exactly 10 chars
+    let mut text = format!("{}\nshort\n{}", "a".repeat(25), "b".repeat(10));
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    let rows = output
+        .galley
+        .rows
+        .iter()
+        .map(|row| row.char_count_excluding_newline())
+        .collect::<Vec<_>>();
+    assert_eq!(rows, [10, 10, 5, 5, 10]);
+
+    // Narrower than the editor, so independent of its width
+    let mut editor = editor.with_word_wrap(true);
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(output.galley.rows.len(), 5);
+}
+
+#[test]
+fn doc_comments() {
+    let syntax = Syntax::rust();
+    assert_eq!(
+        Token::default().tokens(&syntax, "/// hi\n// hi"),
+        [
+            Token::new(TokenType::DocComment(false), "/// hi"),
+            Token::new(TokenType::Whitespace('\n'), "\n"),
+            Token::new(TokenType::Comment(false), "// hi"),
+        ]
+    );
+    assert_eq!(
+        Token::default().tokens(&syntax, "/** doc */ /* hi */"),
+        [
+            Token::new(TokenType::DocComment(true), "/** doc */"),
+            Token::new(TokenType::Whitespace(' '), " "),
+            Token::new(TokenType::Comment(true), "/* hi */"),
+        ]
+    );
+    // Empty comment is not a doc comment
+    assert_eq!(
+        Token::default().tokens(&syntax, "/**/"),
+        [Token::new(TokenType::Comment(true), "/**/")]
+    );
+    // Block comments nest
+    assert_eq!(
+        Token::default().tokens(&syntax, "/* a /* b */ c */x"),
+        [
+            Token::new(TokenType::Comment(true), "/* a /* b */ c */"),
+            Token::new(TokenType::Literal, "x"),
+        ]
+    );
+    assert_eq!(
+        Token::default().tokens(&syntax, "/** a /* b */ c */"),
+        [Token::new(
+            TokenType::DocComment(true),
+            "/** a /* b */ c */"
+        )]
+    );
+}
+
+#[test]
+fn completion_replaces_trailing_identifier() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_simple("foobar", CompType::Global)
+        .with_replace_trailing(true);
+    let mut text = String::from("x = foobr;");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 7);
+    let output = completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, "x = foobar;");
+    assert_eq!(output.cursor_range.map(|c| c.primary.index), Some(10));
+}
+
+#[test]
+fn token_type_background() {
+    let theme = ColorTheme::GRUVBOX
+        .builder()
+        .background(TokenType::Str('"'), "#ff0000")
+        .build();
+    assert_eq!(
+        format_token(&theme, 10.0, TokenType::Str('\''), None).background,
+        egui::Color32::RED
+    );
+    assert_eq!(
+        format_token(&theme, 10.0, TokenType::Keyword, None).background,
+        egui::Color32::TRANSPARENT
+    );
+    assert_eq!(
+        ColorTheme::GRUVBOX.type_background(TokenType::Str('"')),
+        egui::Color32::TRANSPARENT
+    );
+
+    // Data of the type tells the backgrounds apart, setting one again replaces it
+    let theme = ColorTheme::GRUVBOX
+        .builder()
+        .background(TokenType::Comment(true), "#ff0000")
+        .background(TokenType::Comment(false), "#00ff00")
+        .background(TokenType::Comment(false), "#0000ff")
+        .build();
+    assert_eq!(
+        theme.type_background(TokenType::Comment(true)),
+        egui::Color32::RED
+    );
+    assert_eq!(
+        theme.type_background(TokenType::Comment(false)),
+        egui::Color32::BLUE
+    );
+    assert_eq!(theme.backgrounds.iter().flatten().count(), 2);
+
+    // Past the limit the first background set is dropped
+    let theme = "abcdefghi"
+        .chars()
+        .fold(ColorTheme::GRUVBOX.builder(), |builder, c| {
+            builder.background(TokenType::Punctuation(c), "#ff0000")
+        })
+        .build();
+    assert_eq!(theme.backgrounds.iter().flatten().count(), MAX_BACKGROUNDS);
+    assert_eq!(
+        theme.backgrounds[0],
+        Some((TokenType::Punctuation('b'), "#ff0000"))
+    );
+}
+
+#[test]
+fn string_delims_split() {
+    use highlighting::split_string_delims;
+
+    let delim = |q| Token::new(TokenType::StringDelim(q), q);
+    assert_eq!(
+        split_string_delims(Token::new(TokenType::Str('"'), "\"hi\"")),
+        [
+            delim('"'),
+            Token::new(TokenType::Str('"'), "hi"),
+            delim('"')
+        ]
+    );
+    assert_eq!(
+        split_string_delims(Token::new(TokenType::Str('\''), "''")),
+        [delim('\''), delim('\'')]
+    );
+    // Unterminated, the last quote is escaped
+    assert_eq!(
+        split_string_delims(Token::new(TokenType::Str('`'), "`a\\`")),
+        [delim('`'), Token::new(TokenType::Str('`'), "a\\`")]
+    );
+    let keyword = Token::new(TokenType::Keyword, "let");
+    assert_eq!(
+        split_string_delims(keyword),
+        [Token::new(TokenType::Keyword, "let")]
+    );
+
+    let editor = CodeEditor::default().with_string_delimiters(true);
+    let job = Token::default().highlight(&editor, "x = \"hi\";");
+    let str_color = ColorTheme::GRUVBOX.type_color(TokenType::Str('"'));
+    let strs = job
+        .sections
+        .iter()
+        .filter(|s| s.format.color == str_color)
+        .map(|s| &job.text[s.byte_range.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(strs, ["hi"]);
+}
+
+#[test]
+fn comment_tags_split() {
+    let tags = DEFAULT_COMMENT_TAGS.map(String::from);
+    let comment = Token::new(TokenType::Comment(false), "// TODO: fix");
+    assert_eq!(
+        highlighting::split_comment_tags(comment, &tags),
+        [
+            Token::new(TokenType::Comment(false), "// "),
+            Token::new(TokenType::CommentTag, "TODO"),
+            Token::new(TokenType::Comment(false), ": fix"),
+        ]
+    );
+    let comment = Token::new(TokenType::Comment(false), "// TODOS and XXX");
+    assert_eq!(
+        highlighting::split_comment_tags(comment, &tags),
+        [
+            Token::new(TokenType::Comment(false), "// TODOS and "),
+            Token::new(TokenType::CommentTag, "XXX"),
+        ]
+    );
+
+    let editor = CodeEditor::default();
+    let job = Token::default().highlight(&editor, "// TODO: fix");
+    let tag_color = ColorTheme::GRUVBOX.type_color(TokenType::CommentTag);
+    let tagged = job
+        .sections
+        .iter()
+        .filter(|s| s.format.color == tag_color)
+        .map(|s| &job.text[s.byte_range.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(tagged, ["TODO"]);
+}
+
+#[test]
+fn scrolloff_near_bottom_edge() {
+    let viewport = egui::Rect::from_min_max(egui::pos2(0.0, 100.0), egui::pos2(200.0, 300.0));
+    let row = |top: f32| egui::Rect::from_min_size(egui::pos2(10.0, top), egui::vec2(0.0, 10.0));
+    // Two rows of margin: the cursor row must end at 280 at most
+    assert_eq!(layout::scrolloff_delta(row(250.0), viewport, 20.0), 0.0);
+    assert_eq!(layout::scrolloff_delta(row(270.0), viewport, 20.0), 0.0);
+    assert_eq!(layout::scrolloff_delta(row(275.0), viewport, 20.0), 5.0);
+    assert_eq!(layout::scrolloff_delta(row(310.0), viewport, 20.0), 40.0);
+    assert_eq!(layout::scrolloff_delta(row(105.0), viewport, 20.0), -15.0);
+    // Margin is limited to half of the viewport
+    assert_eq!(layout::scrolloff_delta(row(280.0), viewport, 500.0), 85.0);
+}
+
+#[test]
+fn syntax_from_extension() {
+    let rust = Syntax::from_extension("rs").expect("rs is built in");
+    assert_eq!(rust.language(), Syntax::rust().language());
+    assert_eq!(
+        Syntax::from_extension("PY").map(|s| s.language),
+        Some("Python")
+    );
+    assert!(Syntax::from_extension("xyz").is_none());
+}
+
+#[test]
+fn plain_syntax() {
+    let text = "fn main() { // \"quoted\" 42\nlet x = 'a'; /* TODO */\n";
+    let tokens = Token::default().tokens(&Syntax::none(), text);
+    assert!(
+        tokens
+            .iter()
+            .all(|t| matches!(t.ty(), TokenType::Literal | TokenType::Whitespace('\n')))
+    );
+    let literals = tokens
+        .iter()
+        .filter(|t| t.ty() == TokenType::Literal)
+        .map(Token::buffer)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        literals,
+        ["fn main() { // \"quoted\" 42", "let x = 'a'; /* TODO */"]
+    );
+}
+
+#[test]
+fn show_output_metrics() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_numlines(false);
+    let mut text = String::from("one\ntwo\nthree\n");
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(output.line_count, 4);
+    assert_eq!(output.content_size, output.galley.size());
+    assert!(output.content_size.y > 3.0 * editor.fontsize);
+    assert!(output.response.rect.height() >= output.content_size.y);
+}
+
+#[test]
+fn caret_style() {
+    let editor = CodeEditor::default().with_fontsize(20.0);
+    assert_eq!(editor.caret(), (2.0, true));
+
+    let editor = editor.with_caret(3.5, false);
+    assert_eq!(editor.caret(), (3.5, false));
+    let mut style = egui::Style::default();
+    editor.modify_caret_style(&mut style);
+    assert_eq!(style.visuals.text_cursor.stroke.width, 3.5);
+    assert!(!style.visuals.text_cursor.blink);
+}
+
+#[test]
+fn overtype_replaces_text() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_overtype(true);
+    let mut text = String::from("abcd\nefgh");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+
+    set_cursor(&ctx, id, 1..1);
+    let output = frame(&ctx, vec![egui::Event::Text("XY".into())], |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert!(output.overtype);
+    assert_eq!(text, "aXYd\nefgh");
+
+    // Chars past the line end are inserted
+    frame(&ctx, vec![egui::Event::Text("Z".into())], |ui| {
+        editor.show(ui, &mut text);
+    });
+    frame(&ctx, vec![egui::Event::Text("!?".into())], |ui| {
+        editor.show(ui, &mut text);
+    });
+    assert_eq!(text, "aXYZ!?\nefgh");
+
+    // Insert switches back to inserting
+    let output = frame(
+        &ctx,
+        vec![
+            key(egui::Key::Insert, egui::Modifiers::NONE),
+            egui::Event::Text("_".into()),
+        ],
+        |ui| editor.show(ui, &mut text),
+    );
+    assert!(!output.overtype);
+    assert_eq!(text, "aXYZ!?_\nefgh");
+}
+
+#[test]
+fn member_access_skips_globals() {
+    use completer::custom_types::{CompType, CustomTypeRegistry};
+
+    let mut registry = CustomTypeRegistry::new();
+    registry.register_type_simple("player", vec!["x".into()]);
+    registry.register_global_simple("unknowntype.x_global", CompType::Function);
+    registry.register_global_simple("unknowntype", CompType::Function);
+
+    assert!(registry.get_completions("unknowntype.x").is_empty());
+    assert_eq!(registry.get_completions("player.").len(), 1);
+    assert_eq!(registry.get_completions("unknown").len(), 2);
+}
+
+#[test]
+fn dynamic_type_members() {
+    use completer::custom_types::{CompType, CompletionItem, CustomTypeRegistry};
+
+    let mut registry = CustomTypeRegistry::new();
+    registry.register_type_simple("node", vec!["name".into()]);
+    registry.register_type_dynamic("node", |prefix| {
+        vec![CompletionItem::new(
+            format!("{prefix}_echo"),
+            CompType::Field,
+        )]
+    });
+
+    let completions = registry.get_completions("node.na");
+    let members = completions
+        .iter()
+        .map(|(d, _)| d.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(members, ["node.name", "node.na_echo"]);
+    assert_eq!(completions[1].1.comp_type, CompType::Field);
+
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    completer.register_custom_type_dynamic("obj", |prefix| {
+        vec![CompletionItem::new(prefix.to_uppercase(), CompType::Field)]
+    });
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut text = String::from("obj.ab");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 6);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["obj.AB"]);
+}
+
+#[test]
+fn multi_char_separator() {
+    use completer::custom_types::{CompType, CustomTypeRegistry, SyntaxStyle};
+
+    let mut registry = CustomTypeRegistry::new();
+    registry.register_type_with_snippets_and_style(
+        "obj",
+        vec![("method", "method($)", "", CompType::Function)],
+        SyntaxStyle::Separator("::"),
+    );
+    let completions = registry.get_completions("obj::me");
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].0, "obj::method");
+    assert_eq!(registry.context_start("x = obj::me"), 4);
+    assert_eq!(registry.context_start("a - b"), 4);
+
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type_snippets_docs_and_style(
+            "obj",
+            vec![("method", "method($)", "", CompType::Function)],
+            SyntaxStyle::Separator("::"),
+        );
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut text = String::from("let x = obj::me");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 15);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["obj::method"]);
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(text, "let x = obj::method()");
+}
+
+#[test]
+fn accept_hook_fires_once() {
+    use std::sync::{Arc, Mutex};
+
+    let accepted = Arc::new(Mutex::new(vec![]));
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).on_accept({
+        let accepted = Arc::clone(&accepted);
+        move |display, item| {
+            accepted
+                .lock()
+                .unwrap()
+                .push((display.to_string(), item.display.clone()))
+        }
+    });
+    let mut text = String::from("whi");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 3);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(accepted.lock().unwrap().is_empty());
+
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(text, "while");
+    assert_eq!(
+        *accepted.lock().unwrap(),
+        [("while".to_string(), "while".to_string())]
+    );
+}
+
+#[test]
+fn tokenize_lines_comment_state() {
+    let text = "let a = 1; /* one\ntwo\nthree */ let b\n";
+    let lines = tokenize_lines(text, &Syntax::rust());
+    assert_eq!(
+        lines.iter().map(|l| l.in_comment).collect::<Vec<_>>(),
+        [false, true, true, false]
+    );
+    assert_eq!(
+        lines[1].tokens,
+        [Token::new(TokenType::Comment(true), "two\n")]
+    );
+    assert_eq!(
+        lines[2].tokens[0],
+        Token::new(TokenType::Comment(true), "three */")
+    );
+    assert!(lines[3].tokens.is_empty());
+    let joined: String = lines
+        .iter()
+        .flat_map(|l| &l.tokens)
+        .map(Token::buffer)
+        .collect();
+    assert_eq!(joined, text);
+}
+
+#[test]
+fn fold_python_def() {
+    use folding::fold_ranges;
+
+    let text = "import os\n\ndef run(x):\n    if x:\n        go()\n\n    return x\n\nrun(1)\n";
+    assert_eq!(Syntax::python().fold_style, FoldStyle::Indent);
+    assert_eq!(fold_ranges(&Syntax::python(), text), [2..=6, 3..=4]);
+    // Tabs and trailing blank lines
+    assert_eq!(
+        fold_ranges(&Syntax::python(), "class A:\n\tx = 1\n\n"),
+        [0..=1]
+    );
+    // Several blocks closed by one dedent
+    let text = "a:\n  b:\n    c:\n      d\ne\n";
+    assert_eq!(fold_ranges(&Syntax::python(), text), [0..=3, 1..=3, 2..=3]);
+
+    let text = "fn main() {\n    if x { y }\n    let v = [\n        1,\n    ];\n}\n";
+    assert_eq!(fold_ranges(&Syntax::rust(), text), [0..=5]);
+    // Blocks closed by `end` fold by indentation too
+    let text = "def run(x)\n  if x\n    go\n  end\nend\n";
+    assert_eq!(fold_ranges(&Syntax::ruby(), text), [0..=3, 1..=2]);
+    let text = "function run(x)\n  if x then\n    go()\n  end\nend\n";
+    assert_eq!(fold_ranges(&Syntax::lua(), text), [0..=3, 1..=2]);
+    let text = "for f in *; do\n  echo $f\ndone\n";
+    assert_eq!(fold_ranges(&Syntax::bash(), text), [0..=1]);
+}
+
+#[test]
+fn outline_rust_symbols() {
+    use outline::{Symbol, SymbolKind, outline};
+
+    let text = "/// fn in a comment\nstruct Point {\n    x: f32,\n}\n\nfn new() {}\n\npub fn len(p: Point) -> f32 {\n    p.x\n}\n";
+    let symbols = outline(text, &Syntax::rust());
+    let at = |name: &str| text.find(name).unwrap()..text.find(name).unwrap() + name.len();
+    assert_eq!(
+        symbols,
+        [
+            Symbol {
+                name: "Point".into(),
+                kind: SymbolKind::Type,
+                line: 1,
+                range: at("Point"),
+            },
+            Symbol {
+                name: "new".into(),
+                kind: SymbolKind::Function,
+                line: 5,
+                range: at("new"),
+            },
+            Symbol {
+                name: "len".into(),
+                kind: SymbolKind::Function,
+                line: 7,
+                range: at("len"),
+            },
+        ]
+    );
+    let python = outline(
+        "class A:\n    def run(self):\n        pass\n",
+        &Syntax::python(),
+    );
+    assert_eq!(
+        python
+            .iter()
+            .map(|s| (s.name.as_str(), s.line))
+            .collect::<Vec<_>>(),
+        [("A", 0), ("run", 1)]
+    );
+}
+
+#[test]
+fn active_line_number_format() {
+    let editor = CodeEditor::default().with_theme(ColorTheme::GITHUB_DARK);
+    let normal = editor.gutter_colors().1;
+    // Off by default
+    assert_eq!(editor.line_number_format(2, Some(2)).color, normal);
+
+    let editor = editor.with_active_line_number_highlight(true);
+    assert_eq!(
+        editor.line_number_format(2, Some(2)).color,
+        ColorTheme::GITHUB_DARK.cursor()
+    );
+    assert_eq!(editor.line_number_format(1, Some(2)).color, normal);
+    assert_eq!(editor.line_number_format(2, None).color, normal);
+}
+
+#[test]
+fn gutter_style_colors() {
+    let editor = CodeEditor::default();
+    assert_eq!(editor.gutter_style(), GutterStyle::default());
+    assert_eq!(
+        editor.gutter_colors(),
+        (
+            ColorTheme::GRUVBOX.bg(),
+            ColorTheme::GRUVBOX.type_color(TokenType::Comment(true))
+        )
+    );
+
+    let style = GutterStyle {
+        background: Some(egui::Color32::DARK_GRAY),
+        text: Some(egui::Color32::YELLOW),
+        separator: true,
+    };
+    let editor = editor.with_gutter_style(style);
+    assert_eq!(editor.gutter_style(), style);
+    assert_eq!(
+        editor.gutter_colors(),
+        (egui::Color32::DARK_GRAY, egui::Color32::YELLOW)
+    );
+
+    let ctx = egui::Context::default();
+    let mut text = String::from("a\nb");
+    frame(&ctx, vec![], |ui| editor.clone().show(ui, &mut text));
+}
+
+#[test]
+fn completion_prefix_occurrences() {
+    use completer::prefix_occurrences;
+
+    let text = "let value = 1;\nval = value + interval;";
+    assert_eq!(prefix_occurrences(text, "val"), [4..7, 15..18, 21..24]);
+    assert_eq!(prefix_occurrences("while x { whi }", "whi"), [0..3, 10..13]);
+    assert!(prefix_occurrences(text, "").is_empty());
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_prefix_highlight(true);
+    let mut text = String::from("while x { whi }");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 13);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["while"]);
+
+    // Only rows in view are searched, offsets are chars
+    let mut text = "é val\n".repeat(200);
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 5);
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let row_height = output.galley.rows[0].rect().height();
+    let visible = egui::Rangef::new(row_height * 10.5, row_height * 12.5);
+    let found = completer::visible_prefix_occurrences(&output.galley, visible, "va");
+    assert_eq!(found, [62..64, 68..70, 74..76]);
+}
+
+#[test]
+fn syntax_completions_per_category() {
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    completer.push_word("Stream");
+    assert_eq!(
+        completer.syntax_completions(TokenType::Type, "St"),
+        ["String"]
+    );
+    assert_eq!(
+        completer.syntax_completions(TokenType::Keyword, "st"),
+        ["struct"]
+    );
+    assert!(
+        completer
+            .syntax_completions(TokenType::Keyword, "St")
+            .is_empty()
+    );
+    assert_eq!(
+        completer.syntax_completions(TokenType::Literal, "St"),
+        ["Stream"]
+    );
+    assert!(
+        completer
+            .syntax_completions(TokenType::Comment(false), "St")
+            .is_empty()
+    );
+}
+
+#[test]
+fn html_export_of_selection() {
+    let theme = ColorTheme::GRUVBOX;
+    let text = "let s = \"<a>\"; // x & y";
+    let start = text.find('"').unwrap();
+    let html = export::html_range(text, start..text.len(), &Syntax::rust(), &theme);
+    let str_color = theme.type_color_str(TokenType::Str('"'));
+    let comment_color = theme.type_color_str(TokenType::Comment(false));
+    assert_eq!(
+        html,
+        format!(
+            "<pre style=\"background-color:{};color:{}\">\
+             <span style=\"color:{str_color}\">&quot;&lt;a&gt;&quot;</span>\
+             <span style=\"color:{}\">;</span> \
+             <span style=\"color:{comment_color}\">// x &amp; y</span></pre>",
+            theme.bg, theme.literals, theme.punctuation,
+        )
+    );
+
+    // Selection inside a comment keeps the comment color
+    let html = export::html_range(text, text.len() - 1..text.len(), &Syntax::rust(), &theme);
+    assert!(html.contains(&format!("<span style=\"color:{comment_color}\">y</span>")));
+}
+
+#[test]
+fn set_selection_clamped() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut text = String::from("fn main() {}");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(editor.selection(), None);
+
+    editor.set_selection(3..100);
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(editor.selection(), Some(3..12));
+    assert!(output.response.has_focus());
+
+    // Kept on the following frames
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(editor.selection(), Some(3..12));
+}
+
+#[test]
+fn line_highlights_follow_edits() {
+    use layout::shift_line;
+
+    let old = "a\nb\nc";
+    assert_eq!(shift_line(old, "a\nnew\nb\nc", 1), Some(2));
+    assert_eq!(shift_line(old, "a\nnew\nb\nc", 0), Some(0));
+    assert_eq!(shift_line(old, "a\nbx\nc", 1), Some(1));
+    assert_eq!(shift_line(old, "a\nb\nc\nd", 2), Some(2));
+    assert_eq!(shift_line(old, "a\nc", 1), None);
+    assert_eq!(shift_line(old, "a\nc", 2), Some(1));
+    assert_eq!(shift_line(old, "a\nb", 1), Some(1));
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    editor.set_line_highlights(vec![(1, egui::Color32::GREEN), (0, egui::Color32::RED)]);
+    let mut text = String::from("fn main() {\n    run();\n}");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    set_cursor(&ctx, id, 0..0);
+    frame(
+        &ctx,
+        vec![key(egui::Key::Enter, egui::Modifiers::NONE)],
+        |ui| editor.show(ui, &mut text),
+    );
+    assert_eq!(text, "\nfn main() {\n    run();\n}");
+    assert_eq!(
+        editor.line_highlights(),
+        [(2, egui::Color32::GREEN), (1, egui::Color32::RED)]
+    );
+}
+
+#[test]
+fn line_highlights_keep_theme_background() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_theme(ColorTheme::GRUVBOX);
+    editor.set_line_highlights(vec![(1, egui::Color32::RED)]);
+    let mut text = String::from("a\nb");
+    let output = ctx.run(raw_input(vec![]), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| editor.show(ui, &mut text));
+    });
+    // The slot beneath the text edit starts with the editor background
+    let fill = output
+        .shapes
+        .iter()
+        .find_map(|clipped| match &clipped.shape {
+            egui::Shape::Vec(shapes) => match shapes.first() {
+                Some(egui::Shape::Rect(rect)) => Some(rect.fill),
+                _ => None,
+            },
+            _ => None,
+        });
+    assert_eq!(fill, Some(ColorTheme::GRUVBOX.bg()));
+}
+
+#[test]
+fn diff_line_backgrounds() {
+    use DiffLine::*;
+
+    assert_eq!(Unchanged.stripe(), None);
+    assert_eq!(Unchanged.background(), None);
+    let added = Added.background().unwrap();
+    let removed = Removed.background().unwrap();
+    let modified = Modified.background().unwrap();
+    assert!(added != removed && removed != modified && added != modified);
+    assert!(added.a() < Added.stripe().unwrap().a());
+
+    let mut editor = CodeEditor::default().with_interaction(Interaction::SelectCopy);
+    editor.set_line_highlights(vec![(0, egui::Color32::RED)]);
+    let diff = vec![Unchanged, Added, Removed, Modified, Unchanged];
+    editor.set_diff(diff.clone());
+    assert_eq!(editor.diff(), diff);
+    // Diff backgrounds are their own layer, the line highlights stay
+    assert_eq!(editor.line_highlights(), [(0, egui::Color32::RED)]);
+
+    let ctx = egui::Context::default();
+    let mut text = String::from("a\nb\nc\nd\ne");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    // Read-only text keeps the tags on their lines
+    assert_eq!(editor.diff(), diff);
+    assert_eq!(editor.line_highlights(), [(0, egui::Color32::RED)]);
+    editor.set_line_highlights(vec![(3, egui::Color32::BLUE)]);
+    assert_eq!(editor.diff(), diff);
+}
+
+#[test]
+fn separator_lists_all_members() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_custom_type(
+        "self",
+        vec!["move_to".into(), "health".into(), "attack".into()],
+    );
+    let mut text = String::from("self");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![egui::Event::Text(".".into())],
+    );
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(text, "self.");
+    assert_eq!(
+        displays(&completer),
+        ["self.attack", "self.health", "self.move_to"]
+    );
+
+    // A lone separator has no type to list members of
+    let mut text = String::from("x = ");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![egui::Event::Text(".".into())],
+    );
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(text, "x = .");
+    assert!(displays(&completer).is_empty());
+}
+
+/// Minimal non-`String` buffer, as a rope-backed document would plug in.
+struct CustomBuffer {
+    text: String,
+}
+
+impl egui::TextBuffer for CustomBuffer {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        self.text.insert_text(text, char_index)
+    }
+
+    fn delete_char_range(&mut self, char_range: std::ops::Range<usize>) {
+        self.text.delete_char_range(char_range);
+    }
+
+    fn type_id(&self) -> std::any::TypeId {
+        std::any::TypeId::of::<Self>()
+    }
+}
+
+#[test]
+fn custom_text_buffer() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_syntax(Syntax::rust());
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    let mut buffer = CustomBuffer {
+        text: String::from("let x = 1;\n"),
+    };
+    let id = frame(&ctx, vec![], |ui| editor.show(ui, &mut buffer).response.id);
+    ctx.memory_mut(|m| m.request_focus(id));
+
+    set_cursor(&ctx, id, 11..11);
+    frame(&ctx, vec![egui::Event::Text("whi".into())], |ui| {
+        editor.show(ui, &mut buffer);
+    });
+    assert_eq!(buffer.text, "let x = 1;\nwhi");
+
+    let output = frame(&ctx, vec![], |ui| {
+        editor.show_with_completer(ui, &mut buffer, &mut completer)
+    });
+    assert_eq!(output.galley.text(), "let x = 1;\nwhi");
+    frame(
+        &ctx,
+        vec![key(egui::Key::Enter, egui::Modifiers::NONE)],
+        |ui| {
+            editor.show_with_completer(ui, &mut buffer, &mut completer);
+        },
+    );
+    assert_eq!(buffer.text, "let x = 1;\nwhile");
+}
+
+#[test]
+fn selection_highlight_fill() {
+    use completer::SelectionHighlight;
+
+    let theme = ColorTheme::GRUVBOX;
+    let fill = egui::Color32::from_rgb(1, 2, 3);
+    let highlight = SelectionHighlight::Fill(fill);
+    assert_eq!(highlight.fill(&theme, true), fill);
+    assert_eq!(highlight.fill(&theme, false), theme.bg());
+    assert_eq!(highlight.stroke(&theme, true, 1.0), egui::Stroke::NONE);
+    assert_eq!(
+        SelectionHighlight::default().stroke(&theme, true, 1.0),
+        egui::Stroke::new(1.0, theme.type_color(TokenType::Literal))
+    );
+
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default()
+        .with_theme(theme)
+        .with_syntax(Syntax::rust());
+    let mut completer =
+        Completer::new_with_syntax(&Syntax::rust()).with_selection_highlight(highlight);
+    let mut text = String::from("wh");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    // The popup area is laid out invisibly the first time it shows
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let output = ctx.run(raw_input(vec![]), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            editor.show_with_completer(ui, &mut text, &mut completer);
+        });
+    });
+    let fills = output
+        .shapes
+        .iter()
+        .filter_map(|clipped| match &clipped.shape {
+            egui::Shape::Rect(rect) => Some(rect.fill),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // "where" and "while" are offered, only the selected one is filled
+    assert_eq!(fills.iter().filter(|f| **f == fill).count(), 1);
+    assert!(fills.iter().filter(|f| **f == theme.bg()).count() >= 2);
+}
+
+#[test]
+fn auto_close_pairs() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_auto_close(true);
+    let mut text = String::from("x = ;");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    let backspace = || vec![key(egui::Key::Backspace, egui::Modifiers::NONE)];
+
+    set_cursor(&ctx, id, 4..4);
+    frame(&ctx, vec![egui::Event::Text("[".into())], |ui| {
+        editor.show(ui, &mut text);
+    });
+    assert_eq!(text, "x = [];");
+
+    // Backspace in [|] removes both brackets
+    frame(&ctx, backspace(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "x = ;");
+
+    // Backspace in [x|] deletes only x, a typed closer steps over the existing one
+    frame(
+        &ctx,
+        vec![
+            egui::Event::Text("[".into()),
+            egui::Event::Text("x".into()),
+            egui::Event::Text("y".into()),
+        ],
+        |ui| editor.show(ui, &mut text),
+    );
+    assert_eq!(text, "x = [xy];");
+    let output = frame(&ctx, backspace(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "x = [x];");
+    assert_eq!(output.cursor_range.map(|c| c.primary.index), Some(6));
+    let output = frame(&ctx, vec![egui::Event::Text("]".into())], |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(text, "x = [x];");
+    assert_eq!(output.cursor_range.map(|c| c.primary.index), Some(7));
+
+    // Quotes don't pair right after a word
+    frame(
+        &ctx,
+        vec![egui::Event::Text("s".into()), egui::Event::Text("'".into())],
+        |ui| editor.show(ui, &mut text),
+    );
+    assert_eq!(text, "x = [x]s';");
+}
+
+#[cfg(feature = "lsp")]
+#[test]
+fn lsp_completion_list() {
+    use completer::custom_types::CompType;
+
+    let json = r#"{
+        "isIncomplete": false,
+        "items": [
+            { "label": "println!", "kind": 3, "insertTextFormat": 2,
+              "insertText": "println!(\"${1:fmt}\", $2)$0",
+              "documentation": { "kind": "markdown", "value": "Prints to stdout" } },
+            { "label": "len", "kind": 2, "detail": "fn len(&self) -> usize" },
+            { "label": "price", "kind": 5, "insertText": "$price" },
+            { "kind": 14 }
+        ]
+    }"#;
+    let completions = completer::completions_from_lsp(json).unwrap();
+    assert_eq!(completions.len(), 3);
+
+    let (display, item) = &completions[0];
+    assert_eq!(display, "println!");
+    assert_eq!(item.comp_type, CompType::Function);
+    assert_eq!(item.snippet.as_deref(), Some("println!(\"$1fmt\", $2)$0"));
+    // Tabstops keep their LSP order
+    let (text, stops) =
+        crate::completer::custom_types::snippet_tabstops(item.snippet.as_deref().unwrap());
+    assert_eq!(text, "println!(\"fmt\", )");
+    assert_eq!(stops, [10, 16, 17]);
+    assert_eq!(item.documentation.as_deref(), Some("Prints to stdout"));
+
+    let (_, item) = &completions[1];
+    assert_eq!(item.comp_type, CompType::Function);
+    assert_eq!(item.snippet.as_deref(), Some("len"));
+    assert_eq!(
+        item.documentation.as_deref(),
+        Some("fn len(&self) -> usize")
+    );
+
+    let (_, item) = &completions[2];
+    assert_eq!(item.comp_type, CompType::Field);
+    assert_eq!(item.cursor_info(), ("$price".to_string(), None));
+
+    // Bare item arrays are accepted, other values are not
+    assert_eq!(
+        completer::completions_from_lsp(r#"[{ "label": "x" }]"#).unwrap()[0]
+            .1
+            .comp_type,
+        CompType::Global
+    );
+    assert!(completer::completions_from_lsp(r#"{ "label": "x" }"#).is_err());
+    assert!(completer::completions_from_lsp("{").is_err());
+}
+
+#[test]
+fn completion_retrigger_on_accept() {
+    use completer::custom_types::{CompType, CompletionItem};
+
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default();
+    let item = CompletionItem::with_snippet("player", "player.", CompType::Global)
+        .with_retrigger_on_accept(true);
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type("player", vec!["health".to_string(), "move_to".to_string()])
+        .with_global_item(item);
+    let mut text = String::from("pla");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 3);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "player.");
+    assert_eq!(completer.prefix(), "player.");
+    assert_eq!(displays(&completer), ["player.health", "player.move_to"]);
+    assert!(completer.is_open());
+
+    // The member list takes the keys on the next cycle
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "player.health");
+}
+
+#[test]
+fn expand_selection_key_with_completer() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    let mut text = String::from("foo bar");
+    let id = completer_at(&ctx, &mut editor, &mut completer, &mut text, 3);
+    set_cursor(&ctx, id, 3..3);
+    // Ctrl+Shift+Space grows the selection, it is not the Ctrl+Space trigger
+    let expand = vec![key(
+        egui::Key::Space,
+        egui::Modifiers::CTRL | egui::Modifiers::SHIFT,
+    )];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, expand);
+    assert_eq!(editor.selection(), Some(0..3));
+    assert!(!completer.is_open());
+}
+
+#[test]
+fn completer_trigger_key() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::simple("#"))
+        .with_global_simple("beta", CompType::Global)
+        .with_global_simple("alpha", CompType::Function)
+        .with_custom_type("obj", vec!["name".to_string()]);
+    let mut text = String::from("x = ");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(completer.current_completions().is_empty());
+
+    let trigger = vec![key(egui::Key::Space, egui::Modifiers::CTRL)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, trigger);
+    assert_eq!(text, "x = ");
+    assert_eq!(displays(&completer), ["obj", "beta", "alpha"]);
+    assert!(completer.is_open());
+
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "x = obj");
+
+    // Unfocused editor leaves the shortcut to the widget with focus
+    let mut text = String::from("x = ");
+    let id = completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    ctx.memory_mut(|m| m.surrender_focus(id));
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let trigger = vec![key(egui::Key::Space, egui::Modifiers::CTRL)];
+    let unconsumed = frame(&ctx, trigger, |ui| {
+        completer.handle_input(ui.ctx());
+        ui.input(|i| i.key_pressed(egui::Key::Space))
+    });
+    assert!(unconsumed);
+    assert!(!completer.is_open());
+
+    // Turned off, the shortcut is left to the editor
+    let mut completer = completer.with_trigger_key(None);
+    let mut text = String::from("x = ");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    let trigger = vec![key(egui::Key::Space, egui::Modifiers::CTRL)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, trigger);
+    assert!(!completer.is_open());
+}
+
+#[test]
+fn completer_state_accessors() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::simple("#"))
+        .with_global_simple("count", CompType::Function)
+        .with_global_simple("counter", CompType::Global)
+        .with_global_simple("total", CompType::Global);
+    assert_eq!(completer.prefix(), "");
+    assert!(completer.current_completions().is_empty());
+
+    let mut text = String::from("x = cou");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 7);
+    assert_eq!(completer.prefix(), "cou");
+    let down = vec![key(egui::Key::ArrowDown, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, down);
+    let completions = completer.current_completions();
+    assert_eq!(completions.len(), 2);
+    assert_eq!(completions[0].0, "count");
+    assert_eq!(completions[1].1.comp_type, CompType::Global);
+    assert_eq!(completer.selected_index(), 1);
+}
+
+#[test]
+fn completer_reset() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type("self", vec!["health".to_string(), "move_to".to_string()]);
+    let mut text = String::from("self.");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 5);
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::ArrowDown, egui::Modifiers::NONE)],
+    );
+    assert_eq!(displays(&completer), ["self.health", "self.move_to"]);
+    assert_eq!(completer.selected_index(), 1);
+
+    completer.reset();
+    assert!(completer.current_completions().is_empty());
+    assert_eq!(completer.selected_index(), 0);
+    assert!(completer.custom_types().types().contains_key("self"));
+
+    // Another document completes the registered type from scratch
+    let mut text = String::from("fn f() {}\nself.");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 15);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["self.health", "self.move_to"]);
+    assert_eq!(completer.selected_index(), 0);
+
+    // An applied completion is not reported again
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "fn f() {}\nself.health");
+    assert!(completer.applied().is_some());
+    completer.reset();
+    assert!(completer.applied().is_none());
+
+    // Nor is one accepted before the reset
+    let mut text = String::from("self.");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 5);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    frame(&ctx, tab, |ui| {
+        completer.handle_input(ui.ctx());
+        completer.reset();
+        editor.show(ui, &mut text);
+    });
+    assert_eq!(text, "self.health");
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(completer.applied().is_none());
+}
+
+#[test]
+fn completer_is_open() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    let mut text = String::from("x wh");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    set_cursor(&ctx, id, 1..1);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(!completer.is_open());
+
+    set_cursor(&ctx, id, 4..4);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(completer.is_open());
+
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Escape, egui::Modifiers::NONE)],
+    );
+    assert!(!completer.is_open());
+    assert_eq!(text, "x wh");
+}
+
+#[test]
+fn dismissed_completer_keeps_arrows() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    let mut text = String::from("wh\nx");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(completer.is_open());
+
+    let escape = key(egui::Key::Escape, egui::Modifiers::NONE);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![escape]);
+    assert!(!completer.is_open());
+
+    let down = key(egui::Key::ArrowDown, egui::Modifiers::NONE);
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![down]);
+    assert_eq!(output.cursor_range.map(|c| c.primary.index), Some(4));
+    assert_eq!(completer.selected_index(), 0);
+}
+
+#[test]
+fn theme_selection_color() {
+    let theme = ColorTheme::GITHUB_DARK;
+    assert_eq!(
+        theme.search_match(),
+        egui::Color32::from_rgba_unmultiplied(0xc9, 0xd1, 0xd9, 0x40)
+    );
+    let mono = ColorTheme::monocolor(true, "#000000", "#ffffff", "#ffffff", "#333333");
+    assert_eq!(
+        mono.search_active_match(),
+        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 0x80)
+    );
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_theme(theme);
+    let mut text = String::from("let x = 1;");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    set_cursor(&ctx, id, 0..3);
+    let output = ctx.run(raw_input(vec![]), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            editor.show(ui, &mut text);
+        });
+    });
+    let selected = output.shapes.iter().any(|clipped| match &clipped.shape {
+        egui::Shape::Text(shape) => shape.galley.rows.iter().any(|row| {
+            row.visuals
+                .mesh
+                .vertices
+                .iter()
+                .any(|vertex| vertex.color == theme.selection())
+        }),
+        _ => false,
+    });
+    assert!(selected);
+}
+
+#[test]
+fn theme_search_matches() {
+    let mono = ColorTheme::monocolor(true, "#000", "#fff", "#fff", "#333");
+    assert_eq!(
+        mono.search_match(),
+        egui::Color32::from_rgba_unmultiplied(255, 255, 255, 0x40)
+    );
+    assert_eq!(mono.bg(), egui::Color32::BLACK);
+    let theme = mono.builder().search_active_match("#f008").build();
+    assert_eq!(
+        theme.search_active_match(),
+        egui::Color32::from_rgba_unmultiplied(255, 0, 0, 0x88)
+    );
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_theme(theme);
+    editor.set_search_matches(vec![0..1, 4..5], Some(1));
+    let mut text = String::from("x = x;");
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    let background = |at: usize| {
+        let sections = &output.galley.job.sections;
+        let section = sections.iter().find(|s| s.byte_range.contains(&at));
+        section.map(|s| s.format.background)
+    };
+    assert_eq!(background(0), Some(theme.search_match()));
+    assert_eq!(background(4), Some(theme.search_active_match()));
+    assert_eq!(background(2), Some(egui::Color32::TRANSPARENT));
+
+    // Unsorted matches over multibyte chars, `active` indexes the given order
+    let mut text = String::from("é = é; é");
+    editor.set_search_matches(vec![7..8, 0..1, 4..5], Some(0));
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    let background = |at: usize| {
+        let sections = &output.galley.job.sections;
+        let section = sections.iter().find(|s| s.byte_range.contains(&at));
+        section.map(|s| s.format.background)
+    };
+    assert_eq!(background(0), Some(theme.search_match()));
+    assert_eq!(background(5), Some(theme.search_match()));
+    assert_eq!(background(9), Some(theme.search_active_match()));
+    assert_eq!(background(3), Some(egui::Color32::TRANSPARENT));
+
+    // Overlapping matches, the earlier one keeps the overlap
+    editor.set_search_matches(vec![0..3, 2..5], Some(1));
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    let background = |at: usize| {
+        let sections = &output.galley.job.sections;
+        let section = sections.iter().find(|s| s.byte_range.contains(&at));
+        section.map(|s| s.format.background)
+    };
+    assert_eq!(background(3), Some(theme.search_match()));
+    assert_eq!(background(4), Some(theme.search_active_match()));
+    assert_eq!(background(7), Some(egui::Color32::TRANSPARENT));
+}
+
+#[test]
+fn case_insensitive_members() {
+    let members = vec!["move_to".to_string(), "name".to_string()];
+    let completer = Completer::new_with_syntax(&Syntax::sql()).with_custom_type("obj", members);
+    assert!(
+        completer
+            .custom_types()
+            .get_completions("obj.MO")
+            .is_empty()
+    );
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = completer.with_case_insensitive_members("obj", true);
+    let mut text = String::from("obj.MO");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 6);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["obj.move_to"]);
+
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, "obj.move_to");
+}
+
+#[test]
+fn completer_docs_size() {
+    use completer::custom_types::CompType;
+
+    assert_eq!(Completer::default().docs_size(), egui::vec2(300.0, 400.0));
+
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default();
+    let docs = "Long documentation line that has to wrap. ".repeat(40);
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_snippet_docs("load", "load()", docs, CompType::Function)
+        .with_docs_size(150.0, 120.0);
+    assert_eq!(completer.docs_size(), egui::vec2(150.0, 120.0));
+
+    let mut text = String::from("lo");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    for _ in 0..3 {
+        completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    }
+    let rect = ctx
+        .memory(|m| m.area_rect(egui::Id::new("Completer_Docs")))
+        .expect("docs popup should show");
+    let margin = egui::Frame::popup(&ctx.style()).total_margin().sum();
+    assert!(rect.width() <= 150.0 + margin.x + 1.0, "{rect:?}");
+    assert!(rect.height() <= 120.0 + margin.y + 1.0, "{rect:?}");
+    assert!(rect.width() >= 150.0, "{rect:?}");
+}
+
+#[test]
+fn docs_popup_member_header() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type_docs(
+            "self",
+            vec![("move_to", "Moves the character", CompType::Function)],
+        )
+        .with_global_docs("spawn", "Spawns one", CompType::Function);
+    assert_eq!(completer.docs_header("spawn"), None);
+
+    let mut text = String::from("self.mo");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 7);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let (display, _) = &completer.current_completions()[0];
+    assert_eq!(display, "self.move_to");
+    assert_eq!(completer.docs_header(display), Some("self.move_to"));
+
+    // Shown above the docs
+    let output = ctx.run(raw_input(vec![]), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            editor.show_with_completer(ui, &mut text, &mut completer);
+        });
+    });
+    let docs = ctx
+        .memory(|m| m.area_rect(egui::Id::new("Completer_Docs")))
+        .unwrap();
+    let texts = output
+        .shapes
+        .iter()
+        .filter_map(|clipped| match &clipped.shape {
+            egui::Shape::Text(text) if docs.contains(text.pos) => {
+                Some((text.pos.y, text.galley.text().to_owned()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let at = |s: &str| texts.iter().find(|(_, t)| t == s).map(|(y, _)| *y);
+    assert!(at("self.move_to").unwrap() < at("Moves the character").unwrap());
+}
+
+#[test]
+fn detect_indent_style() {
+    let tabs = "fn main() {\n\tif x {\n\t\ty();\n\t}\n}\n";
+    assert_eq!(CodeEditor::detect_indent(tabs), IndentStyle::Tabs);
+
+    let two = "a:\n  b:\n    c: 1\n\n    d: 2\n  e:\n      // aligned\n    f: 3\n";
+    assert_eq!(CodeEditor::detect_indent(two), IndentStyle::Spaces(2));
+
+    let mixed = "fn main() {\n\tlet x = 1;\n    let y = 2;\n}\n";
+    assert_eq!(CodeEditor::detect_indent(mixed), IndentStyle::Mixed);
+
+    assert_eq!(CodeEditor::detect_indent("x\ny\n"), IndentStyle::Spaces(4));
+}
+
+#[test]
+fn gutter_lines_skip_wrapped_rows() {
+    // Line 0 wraps into three rows, line 1 fits, line 2 wraps into two, line 3 is last
+    let row_ends = [false, false, true, true, false, true, false];
+    assert_eq!(
+        layout::gutter_lines(&row_ends),
+        [Some(0), None, None, Some(1), Some(2), None, Some(3)]
+    );
+    assert_eq!(layout::gutter_lines(&[false]), [Some(0)]);
+    assert!(layout::gutter_lines(&[]).is_empty());
+}
+
+#[test]
+fn register_trait_types_tuple() {
+    use completer::custom_types::{CompType, CompletionsBuilder, CustomType, SyntaxStyle};
+
+    struct Player;
+    impl CustomType for Player {
+        fn type_name() -> &'static str {
+            "player"
+        }
+
+        fn build_completions(builder: &mut CompletionsBuilder) {
+            builder
+                .add("jump()", CompType::Function)
+                .with_snippet("jump()")
+                .done();
+        }
+    }
+
+    struct World;
+    impl CustomType for World {
+        fn type_name() -> &'static str {
+            "world"
+        }
+
+        fn build_completions(builder: &mut CompletionsBuilder) {
+            builder.add("gravity", CompType::Field).done();
+        }
+    }
+
+    struct Vec3;
+    impl CustomType for Vec3 {
+        fn type_name() -> &'static str {
+            "Vec3"
+        }
+
+        fn build_completions(builder: &mut CompletionsBuilder) {
+            builder
+                .add("new(..)", CompType::Function)
+                .with_snippet("new($)")
+                .done();
+        }
+
+        fn syntax_style() -> SyntaxStyle {
+            SyntaxStyle::Separator("::")
+        }
+    }
+
+    let completer =
+        Completer::new_with_syntax(&Syntax::rust()).with_trait_types::<(Player, World, Vec3)>();
+    let registry = completer.custom_types();
+    let displays = |prefix| {
+        registry
+            .get_completions(prefix)
+            .into_iter()
+            .map(|(display, _)| display)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(displays("player."), ["player.jump()"]);
+    assert_eq!(displays("world.gr"), ["world.gravity"]);
+    assert_eq!(displays("Vec3::"), ["Vec3::new(..)"]);
+}
+
+#[test]
+fn namespace_constants() {
+    use completer::custom_types::{CompType, SyntaxStyle};
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type_snippets_docs_and_style(
+            "Color",
+            vec![
+                ("Red", "Red", "", CompType::Constant),
+                ("Green", "Green", "", CompType::Constant),
+                ("from_rgb(..)", "from_rgb($)", "", CompType::Function),
+            ],
+            SyntaxStyle::Separator("::"),
+        );
+    let mut text = String::from("Color::R");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 8);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["Color::Red"]);
+    let (display, item) = &completer.current_completions()[0];
+    assert_eq!(item.comp_type, CompType::Constant);
+    assert_eq!(
+        completer.token_type(&Syntax::rust(), display, item),
+        TokenType::Special
+    );
+
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, "Color::Red");
+}
+
+#[test]
+fn max_highlight_line_len() {
+    let theme = ColorTheme::GRUVBOX;
+    let long = "var a=\"x\";".repeat(10_000);
+    let text = format!("{long}\nlet x = 1;");
+    let editor = CodeEditor::default()
+        .with_theme(theme)
+        .with_syntax(Syntax::rust())
+        .with_max_highlight_line_len(1000);
+    let job = Token::default().highlight(&editor, &text);
+    let first = &job.sections[0];
+    assert_eq!(first.byte_range, 0..long.len());
+    assert_eq!(first.format.color, theme.type_color(TokenType::Literal));
+    // Following lines are tokenized as usual
+    let keyword = &job.sections[2];
+    assert_eq!(&text[keyword.byte_range.clone()], "let");
+    assert_eq!(keyword.format.color, theme.type_color(TokenType::Keyword));
+
+    let editor = editor.with_max_highlight_line_len(usize::MAX);
+    assert!(Token::default().highlight(&editor, &text).sections.len() > 10_000);
+}
+
+/// Non-whitespace tokens of the text as (type, text) pairs.
+fn classify(syntax: &Syntax, text: &str) -> Vec<(TokenType, String)> {
+    Token::default()
+        .tokens(syntax, text)
+        .into_iter()
+        .filter(|t| !matches!(t.ty(), TokenType::Whitespace(_)))
+        .map(|t| (t.ty(), t.buffer().to_string()))
+        .collect()
+}
+
+#[test]
+fn ruby_method() {
+    let text =
+        "=begin\nDocs\n=end\ndef greet(name) # hi\n  @count = nil\n  puts :sym, name if x=b\nend\n";
+    let tokens = classify(&Syntax::ruby(), text);
+    let expected = [
+        (TokenType::Comment(true), "=begin\nDocs\n=end"),
+        (TokenType::Keyword, "def"),
+        (TokenType::Function, "greet"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Literal, "name"),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Comment(false), "# hi"),
+        (TokenType::Punctuation('@'), "@"),
+        (TokenType::Literal, "count"),
+        (TokenType::Punctuation('='), "="),
+        (TokenType::Special, "nil"),
+        (TokenType::Literal, "puts"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Literal, "sym"),
+        (TokenType::Punctuation(','), ","),
+        (TokenType::Literal, "name"),
+        (TokenType::Keyword, "if"),
+        (TokenType::Literal, "x"),
+        (TokenType::Punctuation('='), "="),
+        (TokenType::Literal, "b"),
+        (TokenType::Keyword, "end"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+    // Start of an opener at the end of the text
+    assert_eq!(
+        classify(&Syntax::ruby(), "x=b").last(),
+        Some(&(TokenType::Literal, "b".to_string()))
+    );
+    assert_eq!(
+        Syntax::from_extension("rb").map(|s| s.language),
+        Some("Ruby")
+    );
+}
+
+#[test]
+fn kotlin_main() {
+    let text = "/** Entry */\nfun main() {\n    val name: String = \"Kotlin $name ${it.size}\"\n    println(null)\n}";
+    let tokens = classify(&Syntax::kotlin(), text);
+    let expected = [
+        (TokenType::DocComment(true), "/** Entry */"),
+        (TokenType::Keyword, "fun"),
+        (TokenType::Function, "main"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Keyword, "val"),
+        (TokenType::Literal, "name"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Type, "String"),
+        (TokenType::Punctuation('='), "="),
+        (TokenType::Str('"'), "\"Kotlin $name ${it.size}\""),
+        (TokenType::Function, "println"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Special, "null"),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Punctuation('}'), "}"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+    assert_eq!(
+        Syntax::from_extension("kt").map(|s| s.language),
+        Some("Kotlin")
+    );
+}
+
+#[test]
+fn swift_func() {
+    let text = "/* outer /* inner */ still */\nfunc greet(name: String) -> String {\n    return \"Hi \\(name)!\"\n}";
+    let tokens = classify(&Syntax::swift(), text);
+    let expected = [
+        (TokenType::Comment(true), "/* outer /* inner */ still */"),
+        (TokenType::Keyword, "func"),
+        (TokenType::Function, "greet"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Literal, "name"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Type, "String"),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Punctuation('-'), "-"),
+        (TokenType::Punctuation('>'), ">"),
+        (TokenType::Type, "String"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Keyword, "return"),
+        (TokenType::Str('"'), "\"Hi \\(name)!\""),
+        (TokenType::Punctuation('}'), "}"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+
+    // Without nesting the first close ends the comment
+    let flat = Syntax::swift().with_nested_comments(false);
+    assert_eq!(
+        classify(&flat, "/* a /* b */ c */")[0],
+        (TokenType::Comment(true), "/* a /* b */".to_string())
+    );
+    assert_eq!(
+        Syntax::from_extension("swift").map(|s| s.language),
+        Some("Swift")
+    );
+}
+
+#[test]
+fn java_class() {
+    let text =
+        "/** Greets */\npublic class Hello {\n    static int count(String s) { return null; }\n}";
+    let tokens = classify(&Syntax::java(), text);
+    let expected = [
+        (TokenType::DocComment(true), "/** Greets */"),
+        (TokenType::Keyword, "public"),
+        (TokenType::Keyword, "class"),
+        (TokenType::Literal, "Hello"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Keyword, "static"),
+        (TokenType::Type, "int"),
+        (TokenType::Function, "count"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Type, "String"),
+        (TokenType::Literal, "s"),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Keyword, "return"),
+        (TokenType::Special, "null"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Punctuation('}'), "}"),
+        (TokenType::Punctuation('}'), "}"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+    assert_eq!(
+        Syntax::from_extension("java").map(|s| s.language),
+        Some("Java")
+    );
+}
+
+#[test]
+fn typescript_interface() {
+    let text = "interface Point { readonly x: number; }";
+    let tokens = classify(&Syntax::typescript(), text);
+    let expected = [
+        (TokenType::Keyword, "interface"),
+        (TokenType::Literal, "Point"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Keyword, "readonly"),
+        (TokenType::Literal, "x"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Type, "number"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Punctuation('}'), "}"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+
+    // Reuses the JavaScript sets
+    let ts = Syntax::typescript();
+    assert!(ts.is_keyword("function") && ts.is_type("Promise"));
+    assert!(!Syntax::javascript().is_keyword("interface"));
+    assert_eq!(
+        Syntax::from_extension("ts").map(|s| s.language),
+        Some("TypeScript")
+    );
+}
+
+#[test]
+fn syntax_extend() {
+    let base = Syntax::rust();
+    let dialect = base.extend(
+        SyntaxExtension::new("Rust+")
+            .with_keywords(["yeet"])
+            .with_comment("#"),
+    );
+    assert_eq!(dialect.language(), "Rust+");
+    assert_eq!(dialect.comment(), "#");
+    // Markers not given keep the base value
+    assert_eq!(dialect.comment_multiline, base.comment_multiline);
+    assert!(base.keywords.iter().all(|k| dialect.is_keyword(k)));
+    assert!(!base.is_keyword("yeet"));
+
+    let tokens = classify(&dialect, "yeet fn");
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::Keyword, "yeet".to_string()),
+            (TokenType::Keyword, "fn".to_string())
+        ]
+    );
+
+    // Same name with other rules hashes apart, so highlighting isn't shared through the cache
+    use std::hash::{BuildHasher, RandomState};
+    let state = RandomState::new();
+    let hash = |syntax: &Syntax| state.hash_one(syntax);
+    let unnamed = base.extend(SyntaxExtension::default().with_keywords(["yeet"]));
+    assert_eq!(unnamed.language(), base.language());
+    assert_ne!(hash(&unnamed), hash(&base));
+    assert_eq!(hash(&base), hash(&Syntax::rust()));
+    let other = base.extend(SyntaxExtension::default().with_keywords(["yolo"]));
+    assert_ne!(hash(&unnamed), hash(&other));
+    let rebuilt = base.extend(SyntaxExtension::default().with_keywords(["yeet"]));
+    assert_eq!(hash(&unnamed), hash(&rebuilt));
+    assert_ne!(hash(&Syntax::simple("#")), hash(&Syntax::simple("//")));
+
+    let ctx = egui::Context::default();
+    let mut text = String::from("yeet");
+    let mut editor = CodeEditor::default().with_syntax(base);
+    let plain = frame(&ctx, vec![], |ui| editor.show(ui, &mut text))
+        .galley
+        .job
+        .clone();
+    let mut editor = editor.with_syntax(unnamed);
+    let extended = frame(&ctx, vec![], |ui| editor.show(ui, &mut text))
+        .galley
+        .job
+        .clone();
+    assert_ne!(plain.sections, extended.sections);
+}
+
+#[test]
+fn completion_ranking() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let syntax = Syntax::rust().extend(
+        SyntaxExtension::default()
+            .with_keywords(["matches"])
+            .with_types(["mat4"]),
+    );
+    let mut completer = Completer::new_with_syntax(&syntax)
+        .with_global_simple("matrix", CompType::Global)
+        .with_global_simple("mat", CompType::Global);
+    let mut text = String::from("mat");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 3);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    // Exact match, then by length, ties alphabetical
+    assert_eq!(
+        displays(&completer),
+        ["mat", "mat4", "match", "matrix", "matches"]
+    );
+}
+
+#[test]
+fn user_word_refresh_interval() {
+    use completer::UserWordRefresh;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_user_words()
+        .with_user_word_refresh(UserWordRefresh::Interval(std::time::Duration::from_secs(1)));
+    let mut text = String::from("x");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    // Frames at a given input time, standing in for the clock
+    let mut type_at = |time: f64, typed: &str, completer: &mut Completer| {
+        set_cursor(&ctx, id, text.len()..text.len());
+        let input = egui::RawInput {
+            time: Some(time),
+            ..raw_input(vec![egui::Event::Text(typed.to_string())])
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                editor.show_with_completer(ui, &mut text, completer)
+            });
+        });
+    };
+
+    type_at(0.0, " alpha", &mut completer);
+    assert_eq!(completer.user_words("al"), ["pha"]);
+    // Second edit within the interval waits
+    type_at(0.2, " beta", &mut completer);
+    assert!(completer.user_words("be").is_empty());
+    type_at(1.5, "", &mut completer);
+    assert_eq!(completer.user_words("be"), ["ta"]);
+}
+
+#[test]
+fn user_words_incremental() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_user_words();
+    let mut text = String::from("apple shared\nbanana shared\ncherry");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    let mut replace = |range: std::ops::Range<usize>, with: &str, completer: &mut Completer| {
+        set_cursor(&ctx, id, range);
+        let events = vec![egui::Event::Text(with.to_string())];
+        completer_frame(&ctx, &mut editor, completer, &mut text, events);
+    };
+    let known = |completer: &Completer, word: &str| {
+        completer
+            .user_words(&word[..1])
+            .contains(&word[1..].to_string())
+    };
+
+    replace(33..33, "s", &mut completer);
+    assert!(
+        ["apple", "banana", "shared", "cherrys"]
+            .iter()
+            .all(|w| known(&completer, w))
+    );
+    assert!(!known(&completer, "cherry"));
+
+    // Rewriting the second line drops its words, `shared` is still on the first one
+    replace(13..26, "date", &mut completer);
+    assert!(known(&completer, "date"));
+    assert!(!known(&completer, "banana"));
+    assert!(
+        ["apple", "shared", "cherrys"]
+            .iter()
+            .all(|w| known(&completer, w))
+    );
+
+    replace(0..12, "fig", &mut completer);
+    assert!(!known(&completer, "shared") && !known(&completer, "apple"));
+    assert!(
+        ["fig", "date", "cherrys"]
+            .iter()
+            .all(|w| known(&completer, w))
+    );
+
+    // Words in a block comment are not learned, opening or closing it re-lexes the lines after
+    replace(0..16, "/*\nzebraword inside\n*/ kiwi", &mut completer);
+    assert!(known(&completer, "kiwi"));
+    assert!(!known(&completer, "zebraword") && !known(&completer, "inside"));
+    replace(0..2, " ", &mut completer);
+    assert!(known(&completer, "zebraword"));
+    replace(0..1, "/*", &mut completer);
+    assert!(!known(&completer, "zebraword"));
+    assert!(known(&completer, "kiwi"));
+}
+
+#[test]
+fn completer_word_chars() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::simple("/*"))
+        .with_global_simple("--main-color", CompType::Global)
+        .with_word_chars(&['-']);
+    let mut text = String::from("color: var(--main-c");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 19);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["--main-color"]);
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, "color: var(--main-color");
+}
+
+#[test]
+fn lua_colon_call() {
+    let text = "--[[ moves ]]\nfunction Player:step()\n  local x = self:move(1)\nend";
+    let tokens = classify(&Syntax::lua(), text);
+    let expected = [
+        (TokenType::Comment(true), "--[[ moves ]]"),
+        (TokenType::Keyword, "function"),
+        (TokenType::Literal, "Player"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Function, "step"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Keyword, "local"),
+        (TokenType::Literal, "x"),
+        (TokenType::Punctuation('='), "="),
+        (TokenType::Special, "self"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Function, "move"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Numeric(false), "1"),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Keyword, "end"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+}
+
+#[test]
+fn bash_if_block() {
+    let text = "# greet\nif [ -n \"$NAME\" ]; then\n  echo ${NAME}\nfi";
+    let tokens = classify(&Syntax::bash(), text);
+    let expected = [
+        (TokenType::Comment(false), "# greet"),
+        (TokenType::Keyword, "if"),
+        (TokenType::Punctuation('['), "["),
+        (TokenType::Punctuation('-'), "-"),
+        (TokenType::Literal, "n"),
+        (TokenType::Str('"'), "\"$NAME\""),
+        (TokenType::Punctuation(']'), "]"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Keyword, "then"),
+        (TokenType::Type, "echo"),
+        (TokenType::Punctuation('$'), "$"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Literal, "NAME"),
+        (TokenType::Punctuation('}'), "}"),
+        (TokenType::Keyword, "fi"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+    assert_eq!(
+        Syntax::from_extension("bash").map(|s| s.language),
+        Some("Bash")
+    );
+}
+
+#[test]
+fn css_rule() {
+    let text = "/* card */\n.card:hover { color: #fff; margin: 0 1.5rem; width: auto; }";
+    let tokens = classify(&Syntax::css(), text);
+    // Units keep the color of their number, like Rust suffixes
+    let expected = [
+        (TokenType::Comment(true), "/* card */"),
+        (TokenType::Punctuation('.'), "."),
+        (TokenType::Literal, "card"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Literal, "hover"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Type, "color"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Punctuation('#'), "#"),
+        (TokenType::Literal, "fff"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Type, "margin"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Numeric(false), "0"),
+        (TokenType::Numeric(true), "1.5rem"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Type, "width"),
+        (TokenType::Punctuation(':'), ":"),
+        (TokenType::Special, "auto"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Punctuation('}'), "}"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+    assert_eq!(
+        classify(&Syntax::css(), "@media")[1],
+        (TokenType::Keyword, "media".to_string())
+    );
+    assert_eq!(
+        Syntax::from_extension("css").map(|s| s.language),
+        Some("CSS")
+    );
+    // Other syntaxes lex the letters after a number apart from it
+    let tokens = Token::default().tokens(&Syntax::rust(), "1usize");
+    assert_eq!(tokens[0], Token::new(TokenType::Numeric(false), "1"));
+}
+
+#[test]
+fn html_tags() {
+    let text = "<div class=\"x\"><!-- c --></div>";
+    let tokens = classify(&Syntax::html(), text);
+    let expected = [
+        (TokenType::Punctuation('<'), "<"),
+        (TokenType::Keyword, "div"),
+        (TokenType::Type, "class"),
+        (TokenType::Punctuation('='), "="),
+        (TokenType::Str('"'), "\"x\""),
+        (TokenType::Punctuation('>'), ">"),
+        (TokenType::Comment(true), "<!-- c -->"),
+        (TokenType::Punctuation('<'), "<"),
+        (TokenType::Punctuation('/'), "/"),
+        (TokenType::Keyword, "div"),
+        (TokenType::Punctuation('>'), ">"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+
+    // Unclosed tag recovers at the next one
+    let tokens = classify(&Syntax::html(), "<p <span>");
+    assert_eq!(tokens[3], (TokenType::Keyword, "span".to_string()));
+    assert_eq!(
+        Syntax::from_extension("html").map(|s| s.language),
+        Some("HTML")
+    );
+}
+
+#[test]
+fn completer_max_display_len() {
+    use completer::custom_types::CompType;
+
+    let long = "configure_everything_at_once";
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_simple(long, CompType::Function)
+        .with_max_display_len(10);
+    assert_eq!(completer.display_label(long), "configure…");
+    assert_eq!(completer.display_label("short"), "short");
+    // Cut on char boundaries
+    assert_eq!(completer.display_label("ééééééééééé"), "ééééééééé…");
+
+    let mut text = String::from("configure_");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 10);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let output = ctx.run(raw_input(vec![]), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            editor.show_with_completer(ui, &mut text, &mut completer);
+        });
+    });
+    let shown = output.shapes.iter().any(|clipped| match &clipped.shape {
+        egui::Shape::Text(text) => text.galley.text() == "configure…",
+        _ => false,
+    });
+    assert!(shown);
+
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, long);
+}
+
+#[test]
+fn completer_page_keys() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = (0..15).fold(Completer::new_with_syntax(&Syntax::rust()), |c, i| {
+        c.with_global_simple(format!("item{i:02}"), CompType::Global)
+    });
+    let mut text = String::from("item");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let mut press = |k: egui::Key, completer: &mut Completer| {
+        let events = vec![key(k, egui::Modifiers::NONE)];
+        completer_frame(&ctx, &mut editor, completer, &mut text, events);
+        completer.selected_index()
+    };
+
+    // Ten rows are visible
+    assert_eq!(press(egui::Key::PageDown, &mut completer), 10);
+    assert_eq!(press(egui::Key::PageDown, &mut completer), 14);
+    assert_eq!(press(egui::Key::PageUp, &mut completer), 4);
+    assert_eq!(press(egui::Key::PageUp, &mut completer), 0);
+    assert_eq!(press(egui::Key::End, &mut completer), 14);
+    assert_eq!(press(egui::Key::Home, &mut completer), 0);
+    assert_eq!(text, "item");
+}
+
+#[test]
+fn completer_popup_gap() {
+    use completer::custom_types::CompType;
+
+    assert_eq!(Completer::default().popup_gap(), egui::vec2(5.0, 0.0));
+    // List and docs popup rects, after they faded in
+    let popups = |completer: Completer| {
+        let ctx = egui::Context::default();
+        ctx.style_mut(|style| style.animation_time = 0.0);
+        let mut editor = CodeEditor::default();
+        let mut completer =
+            completer.with_global_snippet_docs("load", "load()", "Loads it", CompType::Function);
+        let mut text = String::from("lo");
+        completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+        for _ in 0..3 {
+            completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+        }
+        let rect = |id: &str| ctx.memory(|m| m.area_rect(egui::Id::new(id))).unwrap();
+        (rect("Completer"), rect("Completer_Docs"))
+    };
+
+    let (list, docs) = popups(Completer::new_with_syntax(&Syntax::rust()));
+    let (gap_list, gap_docs) =
+        popups(Completer::new_with_syntax(&Syntax::rust()).with_popup_gap(20.0, 12.0));
+    // Rects are rounded to pixels
+    let near = |a: f32, b: f32| (a - b).abs() < 0.5;
+    assert!(
+        near(gap_list.top(), list.top() + 12.0),
+        "{list:?} {gap_list:?}"
+    );
+    assert!(near(docs.left() - list.right(), 5.0), "{list:?} {docs:?}");
+    assert!(near(gap_docs.left() - gap_list.right(), 20.0));
+}
+
+#[test]
+fn completer_plain_text_edit() {
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_simple("load", CompType::Function)
+        .with_global_simple("loader", CompType::Function);
+    let mut text = String::from("x = ");
+    let show = |events: Vec<egui::Event>, completer: &mut Completer, text: &mut String| {
+        frame(&ctx, events, |ui| {
+            completer.show_on_text_widget(ui, &Syntax::rust(), &ColorTheme::default(), |ui| {
+                egui::TextEdit::multiline(text).lock_focus(true).show(ui)
+            })
+        })
+    };
+    let output = show(vec![], &mut completer, &mut text);
+    let id = output.response.id;
+    ctx.memory_mut(|m| m.request_focus(id));
+    set_cursor(&ctx, id, 4..4);
+    show(vec![], &mut completer, &mut text);
+    let output = show(
+        vec![egui::Event::Text("lo".into())],
+        &mut completer,
+        &mut text,
+    );
+    assert_eq!(text, "x = lo");
+    show(vec![], &mut completer, &mut text);
+    assert_eq!(displays(&completer), ["load", "loop", "loader"]);
+
+    // Popup opens below the caret inside the framed text edit
+    let caret = output.galley.pos_from_cursor(egui::text::CCursor::new(6));
+    let caret = caret.translate(output.galley_pos.to_vec2());
+    let popup = ctx
+        .memory(|m| m.area_rect(egui::Id::new("Completer")))
+        .unwrap();
+    assert!(
+        (popup.left() - caret.left()).abs() < 1.0,
+        "{popup:?} {caret:?}"
+    );
+    assert!(popup.top() >= caret.bottom());
+
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    show(tab, &mut completer, &mut text);
+    assert_eq!(text, "x = load");
+    assert_eq!(completer.applied().unwrap().text, "x = load");
+}
+
+#[test]
+fn format_token_overrides() {
+    let theme = ColorTheme::GITHUB_DARK;
+    let plain = format_token(&theme, 12.0, TokenType::Keyword, None);
+    assert_eq!(plain.color, theme.type_color(TokenType::Keyword));
+    assert!(!plain.italics);
+
+    let overrides = TokenFormatOverrides {
+        color: Some(egui::Color32::RED),
+        italics: Some(true),
+        underline: Some(egui::Stroke::new(1.0, egui::Color32::BLUE)),
+        ..Default::default()
+    };
+    let format = format_token(&theme, 12.0, TokenType::Keyword, Some(overrides));
+    assert_eq!(format.color, egui::Color32::RED);
+    assert!(format.italics);
+    assert_eq!(
+        format.underline,
+        egui::Stroke::new(1.0, egui::Color32::BLUE)
+    );
+    // Unset fields keep the theme
+    assert_eq!(format.background, plain.background);
+}
+
+#[test]
+fn expand_selection_steps() {
+    use selection::expand_range;
+
+    let syntax = Syntax::rust();
+    let text = "let v = call(foo, \"s\");";
+    let grow = |range| expand_range(&syntax, text, range);
+    assert_eq!(grow(14..14), Some(13..16));
+    assert_eq!(grow(13..16), Some(13..21));
+    assert_eq!(grow(13..21), Some(12..22));
+    assert_eq!(grow(12..22), Some(0..23));
+    assert_eq!(grow(0..23), None);
+    assert_eq!(grow(19..19), Some(19..20));
+    assert_eq!(grow(19..20), Some(18..21));
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut text = text.to_string();
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    set_cursor(&ctx, id, 14..14);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    let alt = |k| vec![key(k, egui::Modifiers::ALT)];
+    frame(&ctx, alt(egui::Key::ArrowUp), |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(editor.selection(), Some(13..16));
+    frame(&ctx, alt(egui::Key::ArrowUp), |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(editor.selection(), Some(13..21));
+    editor.expand_selection();
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(editor.selection(), Some(12..22));
+
+    frame(&ctx, alt(egui::Key::ArrowDown), |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(editor.selection(), Some(13..21));
+    editor.shrink_selection();
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(editor.selection(), Some(13..16));
+    // Back to the caret, then nothing left to shrink
+    for _ in 0..2 {
+        frame(&ctx, alt(egui::Key::ArrowDown), |ui| {
+            editor.show(ui, &mut text)
+        });
+        assert_eq!(editor.selection(), Some(14..14));
+    }
+    assert_eq!(text, "let v = call(foo, \"s\");");
+}
+
+#[test]
+fn char_literal_brackets() {
+    use brackets::{BracketPairs, unmatched_brackets};
+
+    // `'('` is a char, the `(` of the call pairs with the last `)`
+    let text = "if c == '(' { f(x) }";
+    let brackets = BracketPairs::new(&Syntax::rust(), text);
+    assert!(brackets.unmatched.is_empty());
+    assert_eq!(brackets.pair_of(15), Some(17));
+    assert_eq!(brackets.pair_of(9), None);
+    // Escaped quotes keep the string open over the bracket
+    assert!(unmatched_brackets(&Syntax::rust(), "f(\"\\\")\", ')', b'(')").is_empty());
+    assert!(unmatched_brackets(&Syntax::rust(), "['\\'', ']']").is_empty());
+}
+
+#[cfg(feature = "path_completion")]
+#[test]
+fn path_completion_in_string() {
+    use completer::PathCompletionSource;
+    use completer::path::path_before_cursor;
+
+    let syntax = Syntax::rust();
+    assert_eq!(
+        path_before_cursor(&syntax, "x;\nload(\"assets/im"),
+        Some(("assets/", "im"))
+    );
+    assert_eq!(
+        path_before_cursor(&syntax, "load(\"./ma"),
+        Some(("./", "ma"))
+    );
+    assert_eq!(path_before_cursor(&syntax, "load(\"word"), None);
+    assert_eq!(path_before_cursor(&syntax, "load(\"a/b\") + im"), None);
+
+    let root = std::env::temp_dir().join(format!("code_editor_paths_{}", std::process::id()));
+    std::fs::create_dir_all(root.join("assets/img")).unwrap();
+    for file in ["assets/image.png", "assets/icon.svg", "assets/other.txt"] {
+        std::fs::write(root.join(file), "").unwrap();
+    }
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer =
+        Completer::new_with_syntax(&syntax).with_path_completion(PathCompletionSource::new(&root));
+    let mut text = String::from("load(\"assets/i\")");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 14);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let mut offered = displays(&completer);
+    offered.sort();
+    assert_eq!(offered, ["icon.svg", "image.png", "img/"]);
+
+    let selected = completer.current_completions()[0].0.clone();
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::Tab, egui::Modifiers::NONE)],
+    );
+    assert_eq!(text, format!("load(\"assets/{selected}\")"));
+
+    // Nothing outside of the root is listed
+    let source = PathCompletionSource::new(root.join("assets"));
+    assert_eq!(source.entries("./", "ic").len(), 1);
+    assert!(source.entries("../", "assets").is_empty());
+    assert!(source.entries("img/../../", "").is_empty());
+    assert!(source.entries("/", "").is_empty());
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn invisible_chars_marked() {
+    use highlighting::invisible_chars;
+
+    assert_eq!(invisible_chars("let a\u{200B} = 1;"), [5]);
+    assert_eq!(invisible_chars("\u{FEFF}x\0\ty\n"), [0, 2]);
+    assert!(invisible_chars("tabs\tand\r\nlines é").is_empty());
+
+    let ctx = egui::Context::default();
+    let theme = ColorTheme::GRUVBOX;
+    let mut text = String::from("a\u{200B}b\0");
+    let mut boxes = |highlight| {
+        let mut editor = CodeEditor::default()
+            .with_theme(theme)
+            .with_highlight_control_chars(highlight);
+        let output = ctx.run(raw_input(vec![]), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| editor.show(ui, &mut text));
+        });
+        output
+            .shapes
+            .iter()
+            .filter(|clipped| {
+                matches!(&clipped.shape, egui::Shape::Rect(rect)
+                    if rect.stroke.color == theme.error())
+            })
+            .count()
+    };
+    assert_eq!(boxes(false), 0);
+    assert_eq!(boxes(true), 2);
+}
+
+#[test]
+fn completion_sections() {
+    use completer::CompletionSection::*;
+    use completer::custom_types::{CompType, CompletionItem};
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_simple("fold", CompType::Function)
+        .with_global_snippet("fori", "for $i in 0..n {}", CompType::Snippet)
+        .with_global_simple("font", CompType::Global)
+        .with_grouped_popup(true);
+    let item = |display: &str, ty| (display.to_string(), CompletionItem::new(display, ty));
+    let mixed = vec![
+        item("font", CompType::Global),
+        item("fori", CompType::Snippet),
+        item("for", CompType::Global),
+        item("self.fo", CompType::Field),
+        item("fold", CompType::Function),
+        item("f64", CompType::Global),
+        item("false", CompType::Global),
+    ];
+    let grouped = completer.group_completions(mixed);
+    let groups: Vec<(completer::CompletionSection, Vec<&str>)> = grouped
+        .iter()
+        .map(|(section, entries)| (*section, entries.iter().map(|(d, _)| d.as_str()).collect()))
+        .collect();
+    assert_eq!(
+        groups,
+        [
+            (Keywords, vec!["for", "false"]),
+            (Types, vec!["f64"]),
+            (Members, vec!["self.fo"]),
+            (Functions, vec!["fold"]),
+            (Snippets, vec!["fori"]),
+            (Words, vec!["font"]),
+        ]
+    );
+    assert_eq!(Keywords.label(), "Keywords");
+
+    // Arrows walk the entries in grouped order, headers are not entries
+    let mut text = String::from("fo");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["for", "fold", "fori", "font"]);
+    completer_frame(
+        &ctx,
+        &mut editor,
+        &mut completer,
+        &mut text,
+        vec![key(egui::Key::ArrowDown, egui::Modifiers::NONE)],
+    );
+    assert_eq!(completer.selected_index(), 1);
+}
+
+#[test]
+fn size_limit_degraded() {
+    let ctx = egui::Context::default();
+    let theme = ColorTheme::GRUVBOX;
+    let mut editor = CodeEditor::default()
+        .with_theme(theme)
+        .with_size_limit(1000, 100);
+    let mut text = String::from("let x = 1;\nlet y = 2;");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert!(!editor.is_degraded());
+    assert!(Token::default().highlight(&editor, &text).sections.len() > 2);
+
+    let mut large = "let x = 1;\n".repeat(100);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut large));
+    assert!(editor.is_degraded());
+    let job = Token::default().highlight(&editor, &large);
+    // Lines are appended whole, without being tokenized
+    assert_eq!(job.sections.len(), 200);
+    assert_eq!(&large[job.sections[0].byte_range.clone()], "let x = 1;");
+    assert_eq!(
+        job.sections[0].format.color,
+        theme.type_color(TokenType::Literal)
+    );
+
+    // Over the line limit alone
+    let mut text = "x\n".repeat(100);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert!(editor.is_degraded());
+
+    let mut editor = editor.with_degraded_mode(DegradedMode {
+        plain_text: false,
+        ..Default::default()
+    });
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut large));
+    assert!(editor.is_degraded());
+    assert!(Token::default().highlight(&editor, &large).sections.len() > 200);
+}
+
+#[test]
+fn completion_suppressed_in_comment() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_suppress_in(&[TokenType::Comment(false), TokenType::Str('"')]);
+
+    let mut text = String::from("// wh\nwh");
+    let id = completer_at(&ctx, &mut editor, &mut completer, &mut text, 5);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(completer.token_at_caret(), Some(TokenType::Comment(false)));
+    assert!(displays(&completer).is_empty());
+
+    // Same prefix outside of the comment completes
+    set_cursor(&ctx, id, 8..8);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(completer.token_at_caret(), Some(TokenType::Literal));
+    assert_eq!(displays(&completer), ["where", "while"]);
+
+    assert_eq!(
+        highlighting::token_at(&Syntax::rust(), "x = \"wh\"", 6),
+        Some(TokenType::Str('"'))
+    );
+    // The caret token comes from the tokens the editor lexed for the frame
+    let tokens = editor.last_tokens();
+    assert_eq!(
+        highlighting::token_type_at(tokens, 8),
+        completer.token_at_caret()
+    );
+
+    // The caret in a comment tag the editor splits out is still in the comment
+    let mut text = String::from("// wh TODO");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 10);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(completer.token_at_caret(), Some(TokenType::Comment(false)));
+    assert!(displays(&completer).is_empty());
+}
+
+#[test]
+fn ini_section_and_pair() {
+    let tokens = classify(
+        &Syntax::ini(),
+        "[server]\nport = 8080 ; default port\nsecure = yes\n# off for now\n",
+    );
+    let ty = |ty, text: &str| (ty, text.to_string());
+    assert_eq!(
+        tokens,
+        [
+            ty(TokenType::Punctuation('['), "["),
+            ty(TokenType::Literal, "server"),
+            ty(TokenType::Punctuation(']'), "]"),
+            ty(TokenType::Literal, "port"),
+            ty(TokenType::Punctuation('='), "="),
+            ty(TokenType::Numeric(false), "8080"),
+            ty(TokenType::Comment(false), "; default port"),
+            ty(TokenType::Literal, "secure"),
+            ty(TokenType::Punctuation('='), "="),
+            ty(TokenType::Special, "yes"),
+            ty(TokenType::Comment(false), "# off for now"),
+        ]
+    );
+    // Both markers comment out the rest of their line only
+    let lines = tokenize_lines("# c\nkey = 1 ; d\n", &Syntax::ini());
+    assert!(lines.iter().all(|line| !line.in_comment));
+    assert_eq!(lines[1].tokens[0], Token::new(TokenType::Literal, "key"));
+    let (_, diagnostics) = Token::default().tokens_with_diagnostics(&Syntax::ini(), "# last");
+    assert!(diagnostics.is_empty());
+    assert_eq!(
+        Syntax::from_extension("properties").map(|s| s.language),
+        Some("INI")
+    );
+}
+
+#[test]
+fn coalesce_whitespace_runs() {
+    let text = "   foo   ";
+    let tokens = Token::default().tokens(&Syntax::rust(), text);
+    assert_eq!(tokens.len(), 7);
+    let tokens = coalesce_tokens(tokens);
+    let parts: Vec<_> = tokens.iter().map(|t| (t.ty(), t.buffer())).collect();
+    assert_eq!(
+        parts,
+        [
+            (TokenType::Whitespace(' '), "   "),
+            (TokenType::Literal, "foo"),
+            (TokenType::Whitespace(' '), "   "),
+        ]
+    );
+    // Different whitespace stays apart and the text is covered exactly
+    let text = "fn f() {\n    g();\n}\n";
+    let tokens = coalesce_tokens(Token::default().tokens(&Syntax::rust(), text));
+    assert_eq!(tokens.iter().map(|t| t.buffer()).collect::<String>(), text);
+    assert!(tokens.windows(2).all(|w| w[0].ty() != w[1].ty()));
+    assert!(tokens.iter().any(|t| t.buffer() == "\n"));
+
+    // The editor highlighting merges them into sections too
+    let job = Token::default().highlight(&CodeEditor::default(), "   foo   ");
+    let sections: Vec<_> = job.sections.iter().map(|s| s.byte_range.clone()).collect();
+    assert_eq!(sections, [0..3, 3..6, 6..9]);
+    let job = Token::default().highlight(&CodeEditor::default(), text);
+    let covered: String = job
+        .sections
+        .iter()
+        .map(|s| &text[s.byte_range.clone()])
+        .collect();
+    assert_eq!(covered, text);
+    assert!(job.sections.windows(2).all(|w| w[0].format != w[1].format));
+}
+
+#[test]
+fn layout_only_galley() {
+    let ctx = egui::Context::default();
+    let theme = ColorTheme::GRUVBOX;
+    let editor = CodeEditor::default().with_theme(theme);
+    let text = "fn main() {\n    let x = \"hi\";\n}";
+    let galley = frame(&ctx, vec![], |ui| editor.layout_only(ui, text));
+    assert_eq!(galley.text(), text);
+    let colors: std::collections::HashSet<_> =
+        galley.job.sections.iter().map(|s| s.format.color).collect();
+    assert!(colors.contains(&theme.type_color(TokenType::Keyword)));
+    assert!(colors.contains(&theme.type_color(TokenType::Str('"'))));
+    assert!(colors.len() > 2);
+}
+
+#[test]
+fn auto_close_surrounds_selection() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_auto_close(true);
+    let mut text = String::from("x = abc;");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+
+    set_cursor(&ctx, id, 4..7);
+    let output = frame(&ctx, vec![egui::Event::Text("\"".into())], |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(text, "x = \"abc\";");
+    let selection = output.cursor_range.map(|c| c.as_sorted_char_range());
+    assert_eq!(selection, Some(5..8));
+
+    // Selection is kept for the next frame, so pairs nest
+    frame(&ctx, vec![egui::Event::Text("(".into())], |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(text, "x = \"(abc)\";");
+    assert_eq!(editor.selection(), Some(6..9));
+
+    // Other chars still replace the selection
+    frame(&ctx, vec![egui::Event::Text("d".into())], |ui| {
+        editor.show(ui, &mut text)
+    });
+    assert_eq!(text, "x = \"(d)\";");
+}
+
+#[test]
+fn tab_precedence() {
+    let tab = || vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let ctx = egui::Context::default();
+
+    // Open popup accepts the completion
+    let mut editor = CodeEditor::default().with_indent(IndentStyle::Spaces(4));
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    let mut text = String::from("whi");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 3);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(completer.is_open());
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab());
+    assert_eq!(text, "while");
+
+    // Prefix without completions leaves Tab to the editor, spaces reach the next stop
+    let mut text = String::from("zq");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(!completer.is_open());
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab());
+    assert_eq!(text, "zq  ");
+
+    // Selection spanning lines is indented and stays selected, Shift+Tab outdents it
+    let mut text = String::from("a\n  b\nc");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    // Focus locks Tab to the editor from its first focused frame on
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    set_cursor(&ctx, id, 0..4);
+    frame(&ctx, tab(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "    a\n      b\nc");
+    assert_eq!(editor.selection(), Some(0..12));
+    let shift_tab = vec![key(egui::Key::Tab, egui::Modifiers::SHIFT)];
+    frame(&ctx, shift_tab.clone(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "a\n  b\nc");
+    assert_eq!(editor.selection(), Some(0..4));
+    frame(&ctx, shift_tab, |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "a\nb\nc");
+
+    // Tabs style types a tab over a single line selection
+    let mut editor = CodeEditor::default();
+    let mut text = String::from("a\nbc");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    set_cursor(&ctx, id, 2..3);
+    frame(&ctx, tab(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "a\n\tc");
+}
+
+#[test]
+fn completion_applied_report() {
+    use completer::AppliedCompletion;
+    use completer::custom_types::CompType;
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_global_simple("formatted", CompType::Global)
+        .with_replace_trailing(true);
+    let mut text = String::from("x = forx;");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 7);
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(output.completion, None);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "x = formatted;");
+    assert_eq!(
+        output.completion,
+        Some(AppliedCompletion {
+            display: "formatted".into(),
+            replaced: 4..8,
+            inserted: 4..13,
+            text: "x = formatted;".into(),
+            additional_edit: None,
+        })
+    );
+    // Reported on the frame of the edit only
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(output.completion, None);
+    assert!(completer.applied().is_none());
+}
+
+#[test]
+fn completion_additional_edit() {
+    use completer::custom_types::{CompType, CompletionItem};
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let item = CompletionItem::new("HashMap", CompType::Global)
+        .with_additional_edit(0, "use std::collections::HashMap;\n");
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_item(item);
+    let mut text = String::from("let m = Hash");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 12);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "use std::collections::HashMap;\nlet m = HashMap");
+    let completion = output.completion.unwrap();
+    assert_eq!(completion.inserted, 39..46);
+    assert_eq!(completion.text, text);
+    // The caret stays after the completion
+    let typed = vec![egui::Event::Text(";".into())];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, typed);
+    assert_eq!(text, "use std::collections::HashMap;\nlet m = HashMap;");
+
+    // Laid out in the frame it is inserted, line highlights move down with it
+    let mut text = String::from("let m = Hash");
+    editor.set_line_highlights(vec![(0, egui::Color32::RED)]);
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 12);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(output.galley.text(), text);
+    assert_eq!(output.completion.unwrap().inserted, 39..46);
+    assert_eq!(editor.line_highlights(), [(1, egui::Color32::RED)]);
+
+    // An edit after the completion leaves the caret on it
+    let item = CompletionItem::new("HashMap", CompType::Global).with_additional_edit(12, " // map");
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_item(item);
+    let mut text = String::from("let m = Hash");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 12);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "let m = HashMap // map");
+    assert_eq!(
+        output.completion.unwrap().additional_edit,
+        Some((15, " // map".into()))
+    );
+    assert_eq!(editor.selection(), Some(15..15));
+
+    // An edit inside the replaced prefix goes in front of it
+    let item = CompletionItem::new("HashMap", CompType::Global).with_additional_edit(10, "x");
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_item(item);
+    let mut text = String::from("let m = Hash");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 12);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "let m = xHashMap");
+    let completion = output.completion.unwrap();
+    assert_eq!(completion.additional_edit, Some((8, "x".into())));
+    assert_eq!(completion.inserted, 9..16);
+    assert_eq!(editor.selection(), Some(16..16));
+}
+
+#[test]
+fn json_key_and_value() {
+    let tokens = classify(&Syntax::json(), r#"{"a": "b"}"#);
+    let ty = |ty, text: &str| (ty, text.to_string());
+    assert_eq!(
+        tokens,
+        [
+            ty(TokenType::Punctuation('{'), "{"),
+            ty(TokenType::Type, "\"a\""),
+            ty(TokenType::Punctuation(':'), ":"),
+            ty(TokenType::Str('"'), "\"b\""),
+            ty(TokenType::Punctuation('}'), "}"),
+        ]
+    );
+
+    let text = "{\"k\" :[\"v\", 1.5, true, null] // on\n}";
+    let tokens = Token::default().tokens(&Syntax::jsonc(), text);
+    assert_eq!(tokens.iter().map(Token::buffer).collect::<String>(), text);
+    let tokens = classify(&Syntax::jsonc(), text);
+    assert_eq!(tokens[1], ty(TokenType::Type, "\"k\""));
+    assert_eq!(tokens[4], ty(TokenType::Str('"'), "\"v\""));
+    assert_eq!(tokens[6], ty(TokenType::Numeric(true), "1.5"));
+    assert_eq!(tokens[8], ty(TokenType::Special, "true"));
+    assert_eq!(tokens[10], ty(TokenType::Special, "null"));
+    assert_eq!(tokens[12], ty(TokenType::Comment(false), "// on"));
+    // A string at the end of the text is a value
+    assert_eq!(
+        classify(&Syntax::json(), "\"end\""),
+        [ty(TokenType::Str('"'), "\"end\"")]
+    );
+    assert_eq!(
+        Syntax::from_extension("json").map(|s| s.language),
+        Some("JSON")
+    );
+}
+
+#[test]
+fn glsl_main() {
+    let text =
+        "uniform mat4 mvp;\nvoid main() {\n    gl_Position = mvp * vec4(pos.xyz, 1.0); // clip\n}";
+    let tokens = classify(&Syntax::glsl(), text);
+    let expected = [
+        (TokenType::Keyword, "uniform"),
+        (TokenType::Type, "mat4"),
+        (TokenType::Literal, "mvp"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Type, "void"),
+        (TokenType::Function, "main"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Punctuation('{'), "{"),
+        (TokenType::Special, "gl_Position"),
+        (TokenType::Punctuation('='), "="),
+        (TokenType::Literal, "mvp"),
+        (TokenType::Punctuation('*'), "*"),
+        (TokenType::Type, "vec4"),
+        (TokenType::Punctuation('('), "("),
+        (TokenType::Literal, "pos"),
+        (TokenType::Punctuation('.'), "."),
+        (TokenType::Literal, "xyz"),
+        (TokenType::Punctuation(','), ","),
+        (TokenType::Numeric(true), "1.0"),
+        (TokenType::Punctuation(')'), ")"),
+        (TokenType::Punctuation(';'), ";"),
+        (TokenType::Comment(false), "// clip"),
+        (TokenType::Punctuation('}'), "}"),
+    ]
+    .map(|(ty, text)| (ty, text.to_string()));
+    assert_eq!(tokens, expected);
+    assert_eq!(
+        Syntax::from_extension("frag").map(|s| s.language),
+        Some("GLSL")
+    );
+}
+
+#[test]
+fn layout_hook_appends() {
+    let ctx = egui::Context::default();
+    let hint = egui::text::TextFormat {
+        italics: true,
+        ..Default::default()
+    };
+    let editor = CodeEditor::default().with_layout_hook(move |job, text| {
+        let lines = text.lines().count();
+        job.append(&format!("  // {lines} lines"), 0.0, hint.clone());
+    });
+    let galley = frame(&ctx, vec![], |ui| editor.layout_only(ui, "let x = 1;"));
+    assert_eq!(galley.text(), "let x = 1;  // 1 lines");
+    let last = galley.job.sections.last().expect("hook section");
+    assert_eq!(&galley.text()[last.byte_range.clone()], "  // 1 lines");
+    assert!(last.format.italics);
+
+    // Editor layout runs the hook too
+    let mut editor = editor;
+    let mut text = String::from("a\nb");
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert!(output.galley.text().ends_with("  // 2 lines"));
+    assert_eq!(text, "a\nb");
+}
+
+#[test]
+fn inlay_hint_position() {
+    use egui::text::{CCursor, LayoutJob, TextFormat};
+
+    let ctx = egui::Context::default();
+    let _ = ctx.run(raw_input(vec![]), |_| {});
+    let text = "f(bar)\nx";
+    let job = LayoutJob::single_section(text.into(), TextFormat::default());
+    let mut spaced = job.clone();
+    layout::insert_spaces(&mut spaced, [(2, 30.0)]);
+    let (plain, spaced) = ctx.fonts_mut(|f| (f.layout_job(job), f.layout_job(spaced)));
+    assert_eq!(spaced.text(), text);
+
+    // Hint sits where the char was, which moves right by the hint width
+    let before = plain.pos_from_cursor(CCursor::new(2)).left();
+    let pos = layout::inlay_hint_pos_spaced(&spaced, 2, 30.0, true);
+    assert!((pos.x - before).abs() < 0.01, "{pos:?} {before}");
+    let after = spaced.pos_from_cursor(CCursor::new(2)).left();
+    assert!((after - before - 30.0).abs() < 0.01);
+    // Chars in front of the hint keep their place
+    let f = |galley: &egui::Galley| galley.pos_from_cursor(CCursor::new(1)).left();
+    assert_eq!(f(&plain), f(&spaced));
+
+    // Line end hints follow the line without space
+    assert_eq!(layout::inlay_hint_spaces(text, [6]), [None]);
+    let end = plain.pos_from_cursor(CCursor::new(6)).left_top();
+    assert_eq!(layout::inlay_hint_pos_spaced(&plain, 6, 30.0, false), end);
+
+    // Editor keeps the text and offsets, the hinted char moves
+    let mut editor = CodeEditor::default().with_numlines(false);
+    let mut buffer = String::from(text);
+    let x_of = |output: &CodeEditorOutput| output.galley.pos_from_cursor(CCursor::new(2)).left();
+    let plain_x = x_of(&frame(&ctx, vec![], |ui| editor.show(ui, &mut buffer)));
+    editor.set_inlay_hints(vec![InlayHint {
+        offset: 2,
+        text: "s:".into(),
+    }]);
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut buffer));
+    assert_eq!(output.galley.text(), text);
+    assert!(x_of(&output) > plain_x);
+
+    // Spaces for sorted hints in one pass, sections split in front of them
+    let text = "é(bar)\nx";
+    assert_eq!(
+        layout::inlay_hint_spaces(text, [0, 2, 2, 6, 7, 9]),
+        [Some(0), Some(3), Some(3), None, Some(8), None]
+    );
+    let mut spaced = LayoutJob::single_section(text.into(), TextFormat::default());
+    layout::insert_spaces(&mut spaced, [0, 3, 3, 8].map(|at| (at, 10.0)));
+    let sections = spaced
+        .sections
+        .iter()
+        .map(|s| (s.byte_range.clone(), s.leading_space))
+        .collect::<Vec<_>>();
+    assert_eq!(sections, [(0..3, 10.0), (3..8, 20.0), (8..9, 10.0)]);
+
+    // Hints given out of order are kept sorted
+    editor.set_inlay_hints(vec![
+        InlayHint {
+            offset: 4,
+            text: "b".into(),
+        },
+        InlayHint {
+            offset: 1,
+            text: "a".into(),
+        },
+    ]);
+    let offsets: Vec<_> = editor.inlay_hints().iter().map(|h| h.offset).collect();
+    assert_eq!(offsets, [1, 4]);
+}
+
+#[test]
+fn retab_selection_lines() {
+    let spaced = "fn f() {\n    let s = \"a    b\";\n        g(1,    2);\n}\n";
+    let tabbed = "fn f() {\n\tlet s = \"a    b\";\n\t\tg(1,    2);\n}\n";
+    assert_eq!(CodeEditor::retab(spaced, IndentStyle::Tabs, 4), tabbed);
+    assert_eq!(CodeEditor::retab(tabbed, IndentStyle::Spaces(4), 4), spaced);
+    // Columns short of a tab stay spaces, interior tabs are kept
+    assert_eq!(
+        CodeEditor::retab("      x\ty", IndentStyle::Tabs, 4),
+        "\t  x\ty"
+    );
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_indent(IndentStyle::Spaces(4));
+    let mut text = String::from(spaced);
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    // Second and third line, the selection ends at the start of the last one
+    set_cursor(&ctx, id, 12..51);
+    editor.retab_selection(IndentStyle::Tabs);
+    let output = frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(text, tabbed);
+    assert!(output.response.changed());
+    assert_eq!(editor.selection(), Some(9..41));
+
+    editor.retab_selection(IndentStyle::Spaces(4));
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(text, spaced);
+
+    // One undo reverts the conversion, once egui took the text before it as an undo point
+    let mut at = |time: f64, events: Vec<egui::Event>, editor: &mut CodeEditor| {
+        let input = egui::RawInput {
+            time: Some(time),
+            ..raw_input(events)
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| editor.show(ui, &mut text));
+        });
+        text.clone()
+    };
+    at(10.0, vec![], &mut editor);
+    at(12.0, vec![], &mut editor);
+    editor.retab_selection(IndentStyle::Tabs);
+    at(13.0, vec![], &mut editor);
+    at(15.0, vec![], &mut editor);
+    at(
+        16.0,
+        vec![key(egui::Key::Z, egui::Modifiers::COMMAND)],
+        &mut editor,
+    );
+    assert_eq!(text, spaced);
+}
+
+#[test]
+fn snippet_preview_in_docs() {
+    use completer::custom_types::{CompType, CompletionItem, SNIPPET_CARET};
+
+    let item = CompletionItem::with_snippet_and_docs(
+        "move_to",
+        "move_to($x, \\$y)",
+        "Moves the unit",
+        CompType::Function,
+    );
+    assert_eq!(
+        item.snippet_preview(),
+        Some(format!("move_to({SNIPPET_CARET}x, $y)"))
+    );
+    assert_eq!(
+        CompletionItem::new("x", CompType::Global).snippet_preview(),
+        None
+    );
+
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_snippet_docs(
+        "move_to",
+        "move_to($x, \\$y)",
+        "Moves the unit",
+        CompType::Function,
+    );
+    let mut text = String::from("move");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let output = ctx.run(raw_input(vec![]), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            editor.show_with_completer(ui, &mut text, &mut completer);
+        });
+    });
+    let texts: Vec<String> = output
+        .shapes
+        .iter()
+        .filter_map(|clipped| match &clipped.shape {
+            egui::Shape::Text(text) => Some(text.galley.text().to_string()),
+            _ => None,
+        })
+        .collect();
+    let preview = format!("move_to({SNIPPET_CARET}x, $y)");
+    assert!(texts.contains(&preview), "{texts:?}");
+    assert!(texts.contains(&"Moves the unit".to_string()), "{texts:?}");
+}
+
+#[test]
+fn mixed_direction_text() {
+    use egui::text::CCursor;
+
+    let text = "let שלום = \"مرحبا\"; // עברית\nfn f(x) { x }\n\u{200F}سلام(1)";
+    let ctx = egui::Context::default();
+    ctx.style_mut(|style| style.animation_time = 0.0);
+    let mut editor = CodeEditor::default()
+        .with_highlight_control_chars(true)
+        .with_bracket_matching(BracketMatching {
+            pair: true,
+            unmatched: true,
+        });
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_user_words();
+    let mut buffer = String::from(text);
+    let id = focused_editor(&ctx, &mut editor, &mut buffer);
+    let chars = text.chars().count();
+    for cursor in [0, 5, 9, 14, 20, 30, chars - 2, chars] {
+        set_cursor(&ctx, id, cursor..cursor);
+        let output = completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, vec![]);
+        assert_eq!(output.line_count, 3);
+        assert_eq!(editor.selection(), Some(cursor..cursor));
+        // Caret rect maps back to the same char
+        let rect = output.galley.pos_from_cursor(CCursor::new(cursor));
+        let back = output.galley.cursor_from_pos(rect.center().to_vec2());
+        assert_eq!(back.index, cursor);
+    }
+    // Typing and completing RTL words
+    set_cursor(&ctx, id, chars..chars);
+    let events = vec![egui::Event::Text(" של".into())];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, events);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, vec![]);
+    assert_eq!(displays(&completer), ["שלום"]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, tab);
+    assert!(buffer.ends_with("(1) שלום"), "{buffer}");
+    editor.expand_selection();
+    completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, vec![]);
+    let end = buffer.chars().count();
+    assert_eq!(editor.selection(), Some(end - 4..end));
+
+    // Members with multibyte names replace only the typed part
+    let mut completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type("self", vec!["שם_מלא".to_string()]);
+    let mut buffer = String::from("self.שם");
+    completer_at(&ctx, &mut editor, &mut completer, &mut buffer, 7);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut buffer, tab);
+    assert_eq!(buffer, "self.שם_מלא");
+}
+
+#[test]
+fn completions_rebuilt_on_prefix_change() {
+    use completer::custom_types::{CompType, CompletionItem};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer =
+        Completer::new_with_syntax(&Syntax::rust()).with_global_simple("whale", CompType::Global);
+    let mut text = String::from("wh");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 2);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["whale", "where", "while"]);
+
+    // Unchanged prefix keeps the list
+    for _ in 0..3 {
+        completer.handle_input(&ctx);
+        completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    }
+    assert_eq!(displays(&completer), ["whale", "where", "while"]);
+
+    // Registering a global rebuilds it
+    completer.register_global_simple("whim", CompType::Global);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["whim", "whale", "where", "while"]);
+
+    // So does typing
+    let typed = vec![egui::Event::Text("i".into())];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, typed);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["whim", "while"]);
+
+    // Members computed on demand are asked again every frame, so they stay live
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+    let members = Arc::new(Mutex::new(vec!["whale", "where"]));
+    let live = Arc::clone(&members);
+    let mut completer = Completer::new_with_syntax(&Syntax::rust());
+    completer.register_custom_type_dynamic("obj", move |prefix| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        live.lock()
+            .unwrap()
+            .iter()
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| CompletionItem::new(*name, CompType::Field))
+            .collect()
+    });
+    let mut text = String::from("obj.wh");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 6);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let built = calls.load(Ordering::Relaxed);
+    assert!(built > 0);
+    assert_eq!(displays(&completer), ["obj.whale", "obj.where"]);
+    members.lock().unwrap().push("whelk");
+    completer.handle_input(&ctx);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(
+        displays(&completer),
+        ["obj.whale", "obj.whelk", "obj.where"]
+    );
+    assert!(calls.load(Ordering::Relaxed) > built);
+    let built = calls.load(Ordering::Relaxed);
+
+    // Any change to the registry or the pushed words rebuilds it
+    completer.register_global_simple("whim", CompType::Global);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let rebuilt = calls.load(Ordering::Relaxed);
+    assert!(rebuilt > built);
+    completer.push_word("whisk");
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(calls.load(Ordering::Relaxed) > rebuilt);
+
+    let typed = vec![egui::Event::Text("a".into())];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, typed);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert_eq!(displays(&completer), ["obj.whale"]);
+}
+
+#[test]
+fn registry_accessors() {
+    use completer::custom_types::CompType;
+
+    let completer = Completer::new_with_syntax(&Syntax::rust())
+        .with_custom_type_snippets_docs(
+            "player",
+            vec![
+                (
+                    "move_to",
+                    "move_to($x, y)",
+                    "Moves the player",
+                    CompType::Function,
+                ),
+                ("health", "health", "Hit points", CompType::Field),
+            ],
+        )
+        .with_custom_type("world", vec!["spawn".to_string()])
+        .with_global_docs("print", "Prints a line", CompType::Function);
+    let registry = completer.custom_types();
+
+    assert_eq!(registry.type_names(), ["player", "world"]);
+    let members = registry.type_members("player").expect("registered type");
+    let names: Vec<_> = members.keys().map(String::as_str).collect();
+    assert_eq!(names, ["health", "move_to"]);
+    let move_to = &members["move_to"];
+    assert_eq!(move_to.snippet.as_deref(), Some("move_to($x, y)"));
+    assert_eq!(move_to.documentation.as_deref(), Some("Moves the player"));
+    assert_eq!(move_to.comp_type, CompType::Function);
+    assert!(registry.type_members("enemy").is_none());
+
+    let globals: Vec<_> = registry.globals().keys().collect();
+    assert_eq!(globals, ["print"]);
+}
+
+#[test]
+fn last_tokens_of_shown_text() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    assert!(editor.last_tokens().is_empty());
+
+    let mut text = String::from("fn main() {\n    let s = \"hi\"; // done\n}");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(
+        editor.last_tokens(),
+        Token::default().tokens(&Syntax::rust(), &text)
+    );
+
+    let mut text = String::from("let x = 1;");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(
+        editor.last_tokens(),
+        Token::default().tokens(&Syntax::rust(), &text)
+    );
+
+    // Tokens as drawn: tags and quotes split out, over long lines left plain
+    let mut editor = CodeEditor::default()
+        .with_string_delimiters(true)
+        .with_max_highlight_line_len(20);
+    let mut text = String::from("s = \"hi\"; // TODO\nlet long = \"not lexed at all\";");
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    let drawn: Vec<_> = editor
+        .last_tokens()
+        .iter()
+        .map(|t| (t.ty(), t.buffer()))
+        .collect();
+    assert!(drawn.contains(&(TokenType::StringDelim('"'), "\"")));
+    assert!(drawn.contains(&(TokenType::Str('"'), "hi")));
+    assert!(drawn.contains(&(TokenType::CommentTag, "TODO")));
+    assert_eq!(
+        drawn.last(),
+        Some(&(TokenType::Literal, "let long = \"not lexed at all\";"))
+    );
+    let joined: String = drawn.iter().map(|(_, buffer)| *buffer).collect();
+    assert_eq!(joined, text);
+}
+
+#[test]
+fn snippet_tabstops_indented() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_snippet(
+        "unless",
+        "unless $1 {\n\t$0\n}",
+        completer::custom_types::CompType::Snippet,
+    );
+    let mut text = String::from("fn f() {\n    unl\n}");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 16);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = || vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab());
+    assert_eq!(text, "fn f() {\n    unless  {\n    \t\n    }\n}");
+    let caret = output.cursor_range.map(|range| range.primary.index);
+    assert_eq!(caret, Some(20));
+
+    let typed = vec![egui::Event::Text("x".into())];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, typed);
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab());
+    let caret = output.cursor_range.map(|range| range.primary.index);
+    assert_eq!(caret, Some(29));
+    let typed = vec![egui::Event::Text("z".into())];
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, typed);
+    assert_eq!(text, "fn f() {\n    unless x {\n    \tz\n    }\n}");
+
+    // With the stops used up Tab indents again
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab());
+    assert_eq!(text, "fn f() {\n    unless x {\n    \tz\t\n    }\n}");
+}
+
+#[test]
+fn completer_disabled_keys() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut completer =
+        Completer::new_with_syntax(&Syntax::rust()).with_disabled_keys(&[egui::Key::Escape]);
+    let mut text = String::from("whil");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 4);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    assert!(completer.is_open());
+
+    let escape = vec![key(egui::Key::Escape, egui::Modifiers::NONE)];
+    let unconsumed = frame(&ctx, escape, |ui| {
+        completer.handle_input(ui.ctx());
+        ui.input(|i| i.key_pressed(egui::Key::Escape))
+    });
+    assert!(unconsumed);
+    assert!(completer.is_open());
+}
+
+#[test]
+fn completion_additional_edit_read_only() {
+    use completer::custom_types::{CompType, CompletionItem};
+
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default().with_interaction(Interaction::SelectCopy);
+    let item = CompletionItem::new("HashMap", CompType::Global).with_additional_edit(0, "use x;\n");
+    let mut completer = Completer::new_with_syntax(&Syntax::rust()).with_global_item(item);
+    let mut text = String::from("let m = Hash");
+    completer_at(&ctx, &mut editor, &mut completer, &mut text, 12);
+    completer_frame(&ctx, &mut editor, &mut completer, &mut text, vec![]);
+    let tab = vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let output = completer_frame(&ctx, &mut editor, &mut completer, &mut text, tab);
+    assert_eq!(text, "let m = Hash");
+    assert!(output.completion.is_none());
+    assert!(completer.applied().is_none());
+}
+
+#[test]
+fn tab_stale_cursor_and_zero_width() {
+    let tab = || vec![key(egui::Key::Tab, egui::Modifiers::NONE)];
+    let ctx = egui::Context::default();
+
+    // Cursor stored for a longer text is clamped to the new one
+    let mut editor = CodeEditor::default().with_indent(IndentStyle::Spaces(4));
+    let mut text = String::from("let long = 1;\nlet other = 2;");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    set_cursor(&ctx, id, 2..20);
+    let mut text = String::from("x");
+    frame(&ctx, tab(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "x   ");
+
+    // Zero width indents by a single space
+    let mut editor = CodeEditor::default().with_indent(IndentStyle::Spaces(0));
+    let mut text = String::from("x");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    set_cursor(&ctx, id, 1..1);
+    frame(&ctx, tab(), |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "x ");
+}
+
+#[test]
+fn retab_stale_cursor_and_read_only() {
+    let ctx = egui::Context::default();
+    let mut editor = CodeEditor::default();
+    let mut text = String::from("    let long = 1;\n    let other = 2;");
+    let id = focused_editor(&ctx, &mut editor, &mut text);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    set_cursor(&ctx, id, 14..20);
+    let mut text = String::from("    x");
+    editor.retab_selection(IndentStyle::Tabs);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "\tx");
+
+    let mut editor = editor.with_interaction(Interaction::SelectCopy);
+    let mut text = String::from("    y");
+    editor.retab_selection(IndentStyle::Tabs);
+    frame(&ctx, vec![], |ui| editor.show(ui, &mut text));
+    assert_eq!(text, "    y");
+}
@@ -1,4 +1,4 @@
-use super::ColorTheme;
+use super::{ColorTheme, MAX_BACKGROUNDS};
 
 impl ColorTheme {
     /// Author: André Sá <enkodr@outlook.com>
@@ -8,50 +8,65 @@ impl ColorTheme {
         name: "Ayu",
         dark: false,
         bg: "#fafafa",
-        cursor: "#5c6166",      // foreground
-        selection: "#fa8d3e",   // orange
-        comments: "#828c9a",    // gray
-        functions: "#ffaa33",   // yellow
-        keywords: "#fa8d3e",    // orange
-        literals: "#5c6166",    // foreground
-        numerics: "#a37acc",    // magenta
-        punctuation: "#5c6166", // foreground
-        strs: "#86b300",        // green
-        types: "#399ee6",       // blue
-        special: "#f07171",     // red
+        cursor: "#5c6166",                // foreground
+        selection: "#fa8d3e",             // orange
+        search_match: "#5c616640",        // foreground, 25%
+        search_active_match: "#5c616680", // foreground, 50%
+        comments: "#828c9a",              // gray
+        doc_comments: "#828c9a",          // gray
+        functions: "#ffaa33",             // yellow
+        keywords: "#fa8d3e",              // orange
+        literals: "#5c6166",              // foreground
+        numerics: "#a37acc",              // magenta
+        punctuation: "#5c6166",           // foreground
+        strs: "#86b300",                  // green
+        types: "#399ee6",                 // blue
+        special: "#f07171",               // red
+        errors: "#e65050",                // red
+        backgrounds: [None; MAX_BACKGROUNDS],
     };
 
     pub const AYU_MIRAGE: ColorTheme = ColorTheme {
         name: "Ayu Mirage",
         dark: true,
         bg: "#1f2430",
-        cursor: "#cccac2",      // foreground
-        selection: "#ffad66",   // orange
-        comments: "#565b66",    // gray
-        functions: "#ffcc77",   // yellow
-        keywords: "#ffad66",    // orange
-        literals: "#cccac2",    // foreground
-        numerics: "#dfbfff",    // magenta
-        punctuation: "#cccac2", // foreground
-        strs: "#d5ff80",        // green
-        types: "#73d0ff",       // blue
-        special: "#f28779",     // red
+        cursor: "#cccac2",                // foreground
+        selection: "#ffad66",             // orange
+        search_match: "#cccac240",        // foreground, 25%
+        search_active_match: "#cccac280", // foreground, 50%
+        comments: "#565b66",              // gray
+        doc_comments: "#565b66",          // gray
+        functions: "#ffcc77",             // yellow
+        keywords: "#ffad66",              // orange
+        literals: "#cccac2",              // foreground
+        numerics: "#dfbfff",              // magenta
+        punctuation: "#cccac2",           // foreground
+        strs: "#d5ff80",                  // green
+        types: "#73d0ff",                 // blue
+        special: "#f28779",               // red
+        errors: "#ff6666",                // red
+        backgrounds: [None; MAX_BACKGROUNDS],
     };
 
     pub const AYU_DARK: ColorTheme = ColorTheme {
         name: "Ayu Dark",
         dark: true,
         bg: "#0f1419",
-        cursor: "#bfbdb6",      // foreground
-        selection: "#ffad66",   // orange
-        comments: "#5c6773",    // gray
-        functions: "#e6b450",   // yellow
-        keywords: "#ffad66",    // orange
-        literals: "#bfbdb6",    // foreground
-        numerics: "#dfbfff",    // magenta
-        punctuation: "#bfbdb6", // foreground
-        strs: "#aad94c",        // green
-        types: "#59c2ff",       // blue
-        special: "#f28779",     // red
+        cursor: "#bfbdb6",                // foreground
+        selection: "#ffad66",             // orange
+        search_match: "#bfbdb640",        // foreground, 25%
+        search_active_match: "#bfbdb680", // foreground, 50%
+        comments: "#5c6773",              // gray
+        doc_comments: "#5c6773",          // gray
+        functions: "#e6b450",             // yellow
+        keywords: "#ffad66",              // orange
+        literals: "#bfbdb6",              // foreground
+        numerics: "#dfbfff",              // magenta
+        punctuation: "#bfbdb6",           // foreground
+        strs: "#aad94c",                  // green
+        types: "#59c2ff",                 // blue
+        special: "#f28779",               // red
+        errors: "#d95757",                // red
+        backgrounds: [None; MAX_BACKGROUNDS],
     };
 }
@@ -11,6 +11,9 @@ use egui::Color32;
 #[cfg(feature = "egui")]
 pub const ERROR_COLOR: Color32 = Color32::from_rgb(255, 0, 255);
 
+/// Number of token types a theme can give a background.
+pub const MAX_BACKGROUNDS: usize = 8;
+
 /// Array of default themes.
 pub const DEFAULT_THEMES: [ColorTheme; 8] = [
     ColorTheme::AYU,
@@ -28,23 +31,58 @@ fn color_from_hex(hex: &str) -> Option<Color32> {
     if hex == "none" {
         return Some(Color32::from_rgba_premultiplied(255, 0, 255, 0));
     }
-    let rgb = (1..hex.len())
-        .step_by(2)
-        .filter_map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
-        .collect::<Vec<u8>>();
-    let color = Color32::from_rgb(*rgb.first()?, *rgb.get(1)?, *rgb.get(2)?);
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let rgba = if digits.len() <= 4 {
+        // Short notation, `#rgb` or `#rgba`, doubles every digit
+        digits
+            .chars()
+            .filter_map(|c| c.to_digit(16))
+            .map(|d| d as u8 * 0x11)
+            .collect::<Vec<u8>>()
+    } else {
+        (0..digits.len())
+            .step_by(2)
+            .filter_map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+            .collect::<Vec<u8>>()
+    };
+    let color = Color32::from_rgba_unmultiplied(
+        *rgba.first()?,
+        *rgba.get(1)?,
+        *rgba.get(2)?,
+        rgba.get(3).copied().unwrap_or(255),
+    );
     Some(color)
 }
 
+#[cfg(feature = "egui")]
+/// Search background, `alpha` applies to colors without their own
+fn search_color(hex: &str, alpha: u8) -> Color32 {
+    let digits = hex.trim_start_matches('#').len();
+    match color_from_hex(hex) {
+        Some(color) if digits == 3 || digits == 6 => {
+            let [r, g, b, _] = color.to_srgba_unmultiplied();
+            Color32::from_rgba_unmultiplied(r, g, b, alpha)
+        }
+        color => color.unwrap_or(ERROR_COLOR),
+    }
+}
+
 #[derive(Hash, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-/// Colors in hexadecimal notation as used in HTML and CSS.
+/// Colors in hexadecimal notation as used in HTML and CSS, `#rrggbb` or `#rrggbbaa`,
+/// or the short `#rgb` and `#rgba`. Build custom themes with [`ColorTheme::builder`].
+#[non_exhaustive]
 pub struct ColorTheme {
     pub name: &'static str,
     pub dark: bool,
     pub bg: &'static str,
     pub cursor: &'static str,
     pub selection: &'static str,
+    /// Background of search matches, drawn at 25% opacity when the color has no alpha
+    pub search_match: &'static str,
+    /// Background of the current search match, drawn at 50% opacity when the color has no alpha
+    pub search_active_match: &'static str,
     pub comments: &'static str,
+    pub doc_comments: &'static str,
     pub functions: &'static str,
     pub keywords: &'static str,
     pub literals: &'static str,
@@ -53,6 +91,10 @@ pub struct ColorTheme {
     pub strs: &'static str,
     pub types: &'static str,
     pub special: &'static str,
+    pub errors: &'static str,
+    /// Optional backgrounds of token types. A type without its own background takes the one
+    /// of the same variant with other data, like `Str('"')` for `Str('\'')`.
+    pub backgrounds: [Option<(TokenType, &'static str)>; MAX_BACKGROUNDS],
 }
 impl Default for ColorTheme {
     fn default() -> Self {
@@ -83,6 +125,22 @@ impl ColorTheme {
         color_from_hex(self.selection).unwrap_or(ERROR_COLOR)
     }
 
+    #[cfg(feature = "egui")]
+    pub fn search_match(&self) -> Color32 {
+        search_color(self.search_match, 0x40)
+    }
+
+    #[cfg(feature = "egui")]
+    pub fn search_active_match(&self) -> Color32 {
+        search_color(self.search_active_match, 0x80)
+    }
+
+    #[cfg(feature = "egui")]
+    /// Color of syntax errors, like unmatched brackets
+    pub fn error(&self) -> Color32 {
+        color_from_hex(self.errors).unwrap_or(ERROR_COLOR)
+    }
+
     #[cfg(feature = "egui")]
     pub fn modify_style(&self, ui: &mut egui::Ui, fontsize: f32) {
         let style = ui.style_mut();
@@ -98,12 +156,13 @@ impl ColorTheme {
     pub const fn type_color_str(&self, ty: TokenType) -> &'static str {
         match ty {
             TokenType::Comment(_) => self.comments,
+            TokenType::DocComment(_) => self.doc_comments,
             TokenType::Function => self.functions,
             TokenType::Keyword => self.keywords,
             TokenType::Literal => self.literals,
-            TokenType::Hyperlink => self.special,
+            TokenType::Hyperlink | TokenType::CommentTag => self.special,
             TokenType::Numeric(_) => self.numerics,
-            TokenType::Punctuation(_) => self.punctuation,
+            TokenType::Punctuation(_) | TokenType::StringDelim(_) => self.punctuation,
             TokenType::Special => self.special,
             TokenType::Str(_) => self.strs,
             TokenType::Type => self.types,
@@ -115,12 +174,15 @@ impl ColorTheme {
     pub fn type_color(&self, ty: TokenType) -> Color32 {
         match ty {
             TokenType::Comment(_) => color_from_hex(self.comments),
+            TokenType::DocComment(_) => color_from_hex(self.doc_comments),
             TokenType::Function => color_from_hex(self.functions),
             TokenType::Keyword => color_from_hex(self.keywords),
             TokenType::Literal => color_from_hex(self.literals),
-            TokenType::Hyperlink => color_from_hex(self.special),
+            TokenType::Hyperlink | TokenType::CommentTag => color_from_hex(self.special),
             TokenType::Numeric(_) => color_from_hex(self.numerics),
-            TokenType::Punctuation(_) => color_from_hex(self.punctuation),
+            TokenType::Punctuation(_) | TokenType::StringDelim(_) => {
+                color_from_hex(self.punctuation)
+            }
             TokenType::Special => color_from_hex(self.special),
             TokenType::Str(_) => color_from_hex(self.strs),
             TokenType::Type => color_from_hex(self.types),
@@ -129,7 +191,36 @@ impl ColorTheme {
         .unwrap_or(ERROR_COLOR)
     }
 
-    pub fn monocolor(
+    /// Start building a new theme from this one
+    pub fn builder(&self) -> ColorThemeBuilder {
+        ColorThemeBuilder { theme: *self }
+    }
+
+    /// Background of the token type, if set
+    pub fn type_background_str(&self, ty: TokenType) -> Option<&'static str> {
+        let backgrounds = self.backgrounds.iter().flatten();
+        let variant = std::mem::discriminant(&ty);
+        backgrounds
+            .clone()
+            .find(|(bg_ty, _)| *bg_ty == ty)
+            .or_else(|| {
+                backgrounds
+                    .clone()
+                    .find(|(bg_ty, _)| std::mem::discriminant(bg_ty) == variant)
+            })
+            .map(|(_, hex)| *hex)
+    }
+
+    #[cfg(feature = "egui")]
+    /// Background of the token type, transparent if not set
+    pub fn type_background(&self, ty: TokenType) -> Color32 {
+        self.type_background_str(ty)
+            .and_then(color_from_hex)
+            .unwrap_or(Color32::TRANSPARENT)
+    }
+
+    /// Search matches are `fg` at 25% and 50% opacity.
+    pub const fn monocolor(
         dark: bool,
         bg: &'static str,
         fg: &'static str,
@@ -142,6 +233,8 @@ impl ColorTheme {
             bg,
             cursor,
             selection,
+            search_match: fg,
+            search_active_match: fg,
             literals: fg,
             numerics: fg,
             keywords: fg,
@@ -150,7 +243,102 @@ impl ColorTheme {
             types: fg,
             strs: fg,
             comments: fg,
+            doc_comments: fg,
             special: fg,
+            errors: fg,
+            backgrounds: [None; MAX_BACKGROUNDS],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// Overrides colors of a base theme, see [`ColorTheme::builder`].
+///
+/// Colors are in hexadecimal notation, like `"#fa8d3e"`.
+pub struct ColorThemeBuilder {
+    theme: ColorTheme,
+}
+impl ColorThemeBuilder {
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.theme.name = name;
+        self
+    }
+
+    pub fn dark(mut self, dark: bool) -> Self {
+        self.theme.dark = dark;
+        self
+    }
+
+    pub fn bg(mut self, bg: &'static str) -> Self {
+        self.theme.bg = bg;
+        self
+    }
+
+    pub fn cursor(mut self, cursor: &'static str) -> Self {
+        self.theme.cursor = cursor;
+        self
+    }
+
+    pub fn selection(mut self, selection: &'static str) -> Self {
+        self.theme.selection = selection;
+        self
+    }
+
+    pub fn search_match(mut self, search_match: &'static str) -> Self {
+        self.theme.search_match = search_match;
+        self
+    }
+
+    pub fn search_active_match(mut self, search_active_match: &'static str) -> Self {
+        self.theme.search_active_match = search_active_match;
+        self
+    }
+
+    pub fn errors(mut self, errors: &'static str) -> Self {
+        self.theme.errors = errors;
+        self
+    }
+
+    /// Set color of the token type.
+    /// Types sharing a color in [`ColorTheme::type_color_str`] are changed together.
+    pub fn color(mut self, ty: TokenType, color: &'static str) -> Self {
+        let theme = &mut self.theme;
+        match ty {
+            TokenType::Comment(_) | TokenType::Whitespace(_) | TokenType::Unknown => {
+                theme.comments = color
+            }
+            TokenType::DocComment(_) => theme.doc_comments = color,
+            TokenType::Function => theme.functions = color,
+            TokenType::Keyword => theme.keywords = color,
+            TokenType::Literal => theme.literals = color,
+            TokenType::Numeric(_) => theme.numerics = color,
+            TokenType::Punctuation(_) | TokenType::StringDelim(_) => theme.punctuation = color,
+            TokenType::Hyperlink | TokenType::CommentTag | TokenType::Special => {
+                theme.special = color
+            }
+            TokenType::Str(_) => theme.strs = color,
+            TokenType::Type => theme.types = color,
         }
+        self
+    }
+
+    /// Set background of the token type. Once [`MAX_BACKGROUNDS`] types have one,
+    /// the background set first is dropped.
+    pub fn background(mut self, ty: TokenType, color: &'static str) -> Self {
+        let backgrounds = &mut self.theme.backgrounds;
+        let slot = backgrounds
+            .iter()
+            .position(|bg| bg.is_some_and(|(bg_ty, _)| bg_ty == ty))
+            .or_else(|| backgrounds.iter().position(Option::is_none))
+            .unwrap_or_else(|| {
+                backgrounds.rotate_left(1);
+                MAX_BACKGROUNDS - 1
+            });
+        backgrounds[slot] = Some((ty, color));
+        self
+    }
+
+    pub fn build(self) -> ColorTheme {
+        self.theme
     }
 }
@@ -1,4 +1,4 @@
-use super::ColorTheme;
+use super::{ColorTheme, MAX_BACKGROUNDS};
 
 impl ColorTheme {
     ///  Original Author: sainnhe <https://github.com/sainnhe/sonokai>
@@ -6,17 +6,22 @@ impl ColorTheme {
     pub const SONOKAI: ColorTheme = ColorTheme {
         name: "Sonokai",
         dark: true,
-        bg: "#2c2e34",          // bg0
-        cursor: "#76cce0",      // blue
-        selection: "#444852",   // bg5
-        comments: "#7f8490",    // gray
-        functions: "#9ed072",   // green
-        keywords: "#fc5d7c",    // red
-        literals: "#e2e2e3",    // foreground
-        numerics: "#b39df3",    // purple
-        punctuation: "#7f8490", // gray
-        strs: "#e7c664",        // yellow
-        types: "#399ee6",       // blue
-        special: "#f39660",     // orange
+        bg: "#2c2e34",                    // bg0
+        cursor: "#76cce0",                // blue
+        selection: "#444852",             // bg5
+        search_match: "#e2e2e340",        // foreground, 25%
+        search_active_match: "#e2e2e380", // foreground, 50%
+        comments: "#7f8490",              // gray
+        doc_comments: "#7f8490",          // gray
+        functions: "#9ed072",             // green
+        keywords: "#fc5d7c",              // red
+        literals: "#e2e2e3",              // foreground
+        numerics: "#b39df3",              // purple
+        punctuation: "#7f8490",           // gray
+        strs: "#e7c664",                  // yellow
+        types: "#399ee6",                 // blue
+        special: "#f39660",               // orange
+        errors: "#fc5d7c",                // red
+        backgrounds: [None; MAX_BACKGROUNDS],
     };
 }